@@ -0,0 +1,198 @@
+//! Deterministic fixed-rate ticking for [`CoreStage::FixedUpdate`](crate::CoreStage::FixedUpdate).
+//!
+//! Every frame the scaled [`Time::delta`] is added to an accumulator. The fixed stage then
+//! runs once for every whole `step` inside the accumulator, which can be zero times on a
+//! fast frame and several times on a slow one.
+use bevy_ecs::{
+    schedule::ShouldRun,
+    system::{Res, ResMut, Resource},
+};
+use instant::Duration;
+use tracing::warn;
+
+use crate::time::Time;
+
+/// Default tick rate of the fixed stage, in ticks per second.
+pub const DEFAULT_TICK_RATE: f64 = 60.0;
+/// Default amount of fixed ticks that may run in a single frame.
+pub const DEFAULT_MAX_STEPS_PER_FRAME: u32 = 5;
+
+/// Settings and accumulator of the [`CoreStage::FixedUpdate`](crate::CoreStage::FixedUpdate) stage.
+///
+/// Insert your own before adding plugins to change the tick rate:
+/// ```
+/// # use ne_app::{App, fixed_timestep::FixedTime, time::TimePlugin};
+/// App::new()
+///     .insert_resource(FixedTime::from_hz(30.0))
+///     .add_plugin(TimePlugin);
+/// ```
+#[derive(Debug, Clone, Resource)]
+pub struct FixedTime {
+    step: Duration,
+    accumulator: Duration,
+    /// Spiral-of-death protection: a frame never runs more ticks than this.
+    /// Time that doesn't fit is dropped.
+    pub max_steps_per_frame: u32,
+    steps_this_frame: u32,
+}
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self::from_hz(DEFAULT_TICK_RATE)
+    }
+}
+impl FixedTime {
+    /// Creates a fixed clock that ticks every `step`.
+    pub fn new(step: Duration) -> Self {
+        assert!(!step.is_zero(), "FixedTime step must be larger than zero");
+        Self {
+            step,
+            accumulator: Duration::ZERO,
+            max_steps_per_frame: DEFAULT_MAX_STEPS_PER_FRAME,
+            steps_this_frame: 0,
+        }
+    }
+    /// Creates a fixed clock that ticks `hz` times per second.
+    pub fn from_hz(hz: f64) -> Self {
+        Self::new(Duration::from_secs_f64(1.0 / hz))
+    }
+    /// Duration of a single fixed tick.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+    /// Duration of a single fixed tick in seconds, use this as delta time inside fixed systems.
+    pub fn step_secs(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+    /// Changes the duration of a single fixed tick, accumulated time is kept.
+    pub fn set_step(&mut self, step: Duration) {
+        assert!(!step.is_zero(), "FixedTime step must be larger than zero");
+        self.step = step;
+    }
+    /// Time that has been accumulated but not yet consumed by a tick.
+    pub fn accumulated(&self) -> Duration {
+        self.accumulator
+    }
+    /// Amount of ticks that ran during the current frame.
+    pub fn steps_this_frame(&self) -> u32 {
+        self.steps_this_frame
+    }
+    /// How far the current frame is between the previous and the next fixed tick, in `0.0..1.0`.
+    ///
+    /// Only final once the fixed stage ran, rendering reads [`FixedTimestepAlpha`] instead.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()) as f32
+    }
+    /// Adds `delta` to the accumulator and starts a new frame.
+    ///
+    /// Called once per frame by [`fixed_time_system`], call it manually to drive the
+    /// fixed stage without a real clock.
+    pub fn accumulate(&mut self, delta: Duration) {
+        self.accumulator += delta;
+        self.steps_this_frame = 0;
+    }
+    /// Consumes a single step from the accumulator if possible.
+    /// Returns `true` when a fixed tick should run.
+    pub fn expend(&mut self) -> bool {
+        if self.accumulator < self.step {
+            return false;
+        }
+        if self.steps_this_frame >= self.max_steps_per_frame {
+            let dropped = self.accumulator - self.accumulator_remainder();
+            warn!(
+                "FixedUpdate can't keep up, dropping {:?} of simulation time",
+                dropped
+            );
+            self.accumulator = self.accumulator_remainder();
+            return false;
+        }
+        self.accumulator -= self.step;
+        self.steps_this_frame += 1;
+        true
+    }
+    fn accumulator_remainder(&self) -> Duration {
+        Duration::from_nanos((self.accumulator.as_nanos() % self.step.as_nanos()) as u64)
+    }
+}
+
+/// How far the current frame is between the previous and the next fixed tick, in `0.0..1.0`.
+///
+/// Rendering uses this to interpolate between the last two simulated states. Updated every frame
+/// in [`CoreStage::Update`](crate::CoreStage::Update), after the fixed stage consumed its steps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource)]
+pub struct FixedTimestepAlpha(pub f32);
+
+/// Copies [`FixedTime::alpha`] into [`FixedTimestepAlpha`].
+pub fn fixed_timestep_alpha_system(
+    fixed_time: Res<FixedTime>,
+    mut alpha: ResMut<FixedTimestepAlpha>,
+) {
+    alpha.0 = fixed_time.alpha();
+}
+
+/// Adds the scaled time passed since the previous frame to [`FixedTime`],
+/// so pausing [`Time`] also pauses the fixed stage.
+/// Runs in [`CoreStage::First`](crate::CoreStage::First) after [`TimeSystem`](crate::time::TimeSystem).
+pub fn fixed_time_system(time: Res<Time>, mut fixed_time: ResMut<FixedTime>) {
+    fixed_time.accumulate(time.delta());
+}
+
+/// Run criteria of [`CoreStage::FixedUpdate`](crate::CoreStage::FixedUpdate),
+/// keeps running the stage as long as there are whole steps in the accumulator.
+/// Never runs the stage without [`TimePlugin`](crate::time::TimePlugin).
+pub fn fixed_timestep_run_criteria(fixed_time: Option<ResMut<FixedTime>>) -> ShouldRun {
+    if fixed_time.is_some_and(|mut fixed_time| fixed_time.expend()) {
+        ShouldRun::YesAndCheckAgain
+    } else {
+        ShouldRun::No
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(fixed_time: &mut FixedTime) -> u32 {
+        let mut steps = 0;
+        while fixed_time.expend() {
+            steps += 1;
+        }
+        steps
+    }
+
+    #[test]
+    fn runs_a_step_for_every_whole_step_accumulated() {
+        let mut fixed_time = FixedTime::new(Duration::from_millis(10));
+        fixed_time.accumulate(Duration::from_millis(25));
+        assert_eq!(steps(&mut fixed_time), 2);
+        assert_eq!(fixed_time.steps_this_frame(), 2);
+        assert_eq!(fixed_time.accumulated(), Duration::from_millis(5));
+        assert!((fixed_time.alpha() - 0.5).abs() < 1e-6);
+
+        // The remainder carries over into the next frame.
+        fixed_time.accumulate(Duration::from_millis(5));
+        assert_eq!(steps(&mut fixed_time), 1);
+        assert_eq!(fixed_time.accumulated(), Duration::ZERO);
+    }
+
+    #[test]
+    fn fast_frames_run_no_step() {
+        let mut fixed_time = FixedTime::new(Duration::from_millis(10));
+        fixed_time.accumulate(Duration::from_millis(4));
+        assert_eq!(steps(&mut fixed_time), 0);
+        fixed_time.accumulate(Duration::from_millis(4));
+        assert_eq!(steps(&mut fixed_time), 0);
+        assert_eq!(fixed_time.accumulated(), Duration::from_millis(8));
+    }
+
+    #[test]
+    fn slow_frames_are_capped() {
+        let mut fixed_time = FixedTime::new(Duration::from_millis(10));
+        fixed_time.max_steps_per_frame = 3;
+        fixed_time.accumulate(Duration::from_millis(105));
+        assert_eq!(steps(&mut fixed_time), 3);
+        // The whole steps that didn't fit are dropped, the partial step is kept.
+        assert_eq!(fixed_time.accumulated(), Duration::from_millis(5));
+        fixed_time.accumulate(Duration::from_millis(5));
+        assert_eq!(steps(&mut fixed_time), 1);
+    }
+}
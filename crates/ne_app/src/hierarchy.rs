@@ -0,0 +1,188 @@
+//! Parent/child relations between entities.
+//!
+//! Only [`Parent`] and [`Children`] are kept in sync, transforms are not propagated: a child's
+//! `Transform` stays in world space.
+//! ```ignore
+//! let door = commands.spawn(Name::new("door")).id();
+//! commands.spawn(Name::new("house")).add_child(door);
+//! commands.entity(house).despawn_recursive();
+//! ```
+#[cfg(feature = "bevy_reflect")]
+use bevy_ecs::reflect::{ReflectComponent, ReflectMapEntities};
+use bevy_ecs::{
+    entity::{EntityMap, MapEntities, MapEntitiesError},
+    prelude::{Component, Entity},
+    system::{Command, EntityCommands},
+    world::{FromWorld, World},
+};
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::Reflect;
+
+/// The entity this entity is a child of.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect), reflect(Component, MapEntities))]
+pub struct Parent(Entity);
+impl Parent {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+/// Only for reflection, which inserts components before setting their fields.
+impl FromWorld for Parent {
+    fn from_world(_world: &mut World) -> Self {
+        Parent(Entity::from_raw(u32::MAX))
+    }
+}
+/// Parents that aren't in the map are kept, like the parent of a copied entity.
+impl MapEntities for Parent {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+        if let Ok(parent) = entity_map.get(self.0) {
+            self.0 = parent;
+        }
+        Ok(())
+    }
+}
+
+/// The children of an entity, in order.
+#[derive(Component, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect), reflect(Component, MapEntities))]
+pub struct Children(Vec<Entity>);
+impl MapEntities for Children {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+        for child in &mut self.0 {
+            if let Ok(mapped) = entity_map.get(*child) {
+                *child = mapped;
+            }
+        }
+        Ok(())
+    }
+}
+impl std::ops::Deref for Children {
+    type Target = [Entity];
+    fn deref(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+/// Makes `child` the last child of `parent`, or a root if `parent` is `None` or despawned.
+///
+/// Does nothing if `parent` is `child` or one of its descendants.
+pub fn set_parent(world: &mut World, child: Entity, parent: Option<Entity>) {
+    if world.get_entity(child).is_none() {
+        return;
+    }
+    if let Some(parent) = parent {
+        if is_descendant(world, parent, child) {
+            tracing::warn!(
+                "{:?} can't be a child of its descendant {:?}",
+                child,
+                parent
+            );
+            return;
+        }
+    }
+    if let Some(old_parent) = world.get::<Parent>(child).map(Parent::get) {
+        if let Some(mut children) = world.get_mut::<Children>(old_parent) {
+            children.0.retain(|&entity| entity != child);
+        }
+    }
+    match parent {
+        Some(parent) if world.get_entity(parent).is_some() => {
+            world.entity_mut(child).insert(Parent(parent));
+            match world.get_mut::<Children>(parent) {
+                Some(mut children) => children.0.push(child),
+                None => {
+                    world.entity_mut(parent).insert(Children(vec![child]));
+                }
+            }
+        }
+        _ => {
+            world.entity_mut(child).remove::<Parent>();
+        }
+    }
+}
+
+/// Despawns `entity` and all of its descendants.
+pub fn despawn_recursive(world: &mut World, entity: Entity) {
+    if let Some(parent) = world.get::<Parent>(entity).map(Parent::get) {
+        if let Some(mut children) = world.get_mut::<Children>(parent) {
+            children.0.retain(|&child| child != entity);
+        }
+    }
+    let mut stack = vec![entity];
+    while let Some(entity) = stack.pop() {
+        if let Some(children) = world.get::<Children>(entity) {
+            stack.extend(children.iter().copied());
+        }
+        world.despawn(entity);
+    }
+}
+
+/// Whether `entity` is `ancestor` or below it.
+pub fn is_descendant(world: &World, entity: Entity, ancestor: Entity) -> bool {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if entity == ancestor {
+            return true;
+        }
+        current = world.get::<Parent>(entity).map(Parent::get);
+    }
+    false
+}
+
+/// [`Command`] version of [`set_parent`].
+pub struct SetParent {
+    pub child: Entity,
+    pub parent: Option<Entity>,
+}
+impl Command for SetParent {
+    fn write(self, world: &mut World) {
+        set_parent(world, self.child, self.parent);
+    }
+}
+
+/// [`Command`] version of [`despawn_recursive`].
+pub struct DespawnRecursive {
+    pub entity: Entity,
+}
+impl Command for DespawnRecursive {
+    fn write(self, world: &mut World) {
+        despawn_recursive(world, self.entity);
+    }
+}
+
+/// Hierarchy operations on [`EntityCommands`].
+pub trait HierarchyCommands {
+    fn add_child(&mut self, child: Entity) -> &mut Self;
+    fn set_parent(&mut self, parent: Entity) -> &mut Self;
+    /// Makes the entity a root.
+    fn remove_parent(&mut self) -> &mut Self;
+    fn despawn_recursive(self);
+}
+impl<'w, 's, 'a> HierarchyCommands for EntityCommands<'w, 's, 'a> {
+    fn add_child(&mut self, child: Entity) -> &mut Self {
+        let parent = Some(self.id());
+        self.commands().add(SetParent { child, parent });
+        self
+    }
+    fn set_parent(&mut self, parent: Entity) -> &mut Self {
+        let child = self.id();
+        self.commands().add(SetParent {
+            child,
+            parent: Some(parent),
+        });
+        self
+    }
+    fn remove_parent(&mut self) -> &mut Self {
+        let child = self.id();
+        self.commands().add(SetParent {
+            child,
+            parent: None,
+        });
+        self
+    }
+    fn despawn_recursive(mut self) {
+        let entity = self.id();
+        self.commands().add(DespawnRecursive { entity });
+    }
+}
@@ -1,4 +1,13 @@
 //Thanks bevy!
+pub mod fixed_timestep;
+pub mod hierarchy;
+pub mod play;
+pub mod plugin_group;
+#[cfg(feature = "bevy_reflect")]
+pub mod reflect;
+pub mod schedule_runner;
+pub mod state;
+pub mod time;
 pub mod types;
 use std::collections::HashMap;
 
@@ -26,9 +35,15 @@ pub enum CoreStage {
     First,
     /// The [`Stage`](bevy_ecs::schedule::Stage) that runs before [`CoreStage::Update`].
     PreUpdate,
+    /// The [`Stage`](bevy_ecs::schedule::Stage) that runs at a fixed rate, zero or more times per frame.
+    /// Deterministic logic like physics belongs here, see [`FixedTime`](fixed_timestep::FixedTime).
+    FixedUpdate,
     /// The [`Stage`](bevy_ecs::schedule::Stage) responsible for doing most app logic. Systems should be registered here by default.
     Update,
-    /// The [`Stage`](bevy_ecs::schedule::Stage) that runs after [`CoreStage::Update`].
+    /// The [`Stage`](bevy_ecs::schedule::Stage) of game logic, it only runs while the game plays.
+    /// The editor stops it while editing, see [`PlayState`](play::PlayState).
+    Gameplay,
+    /// The [`Stage`](bevy_ecs::schedule::Stage) that runs after [`CoreStage::Gameplay`].
     PostUpdate,
     /// The [`Stage`](bevy_ecs::schedule::Stage) that runs after all other app stages.
     Last,
@@ -133,15 +148,6 @@ define_label!(
 //================================================================
 //^^^ Make this my own code^^^
 //================================================================
-#[macro_export]
-macro_rules! get_engine_assets_dir {
-    () => {
-        // find_file!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets")
-        // concat!(env!("CARGO_MANIFEST_DIR"), "/assets")
-        todo!();
-    };
-}
-
 pub struct App {
     /// The main ECS [`World`] of the [`App`].
     /// This stores and provides access to all the main data of the application.
@@ -155,25 +161,34 @@ pub struct App {
     pub runner: Box<dyn Fn(App)>,
     // world: World,
     pub schedule: Schedule,
-    /// The [runner function](Self::set_runner) is primarily responsible for managing
-    /// the application's event loop and advancing the [`Schedule`].
-    /// Typically, it is not configured manually, but set by one of Bevy's built-in plugins.
-    /// See `bevy::winit::WinitPlugin` and [`ScheduleRunnerPlugin`](crate::schedule_runner::ScheduleRunnerPlugin).
+    /// Separate [`World`]-[`Schedule`] pairs that are updated after the main schedule,
+    /// see [`add_sub_app`](App::add_sub_app).
     sub_apps: HashMap<AppLabelId, SubApp>,
+    /// Names of the plugins that have been added, in the order they were added.
+    plugin_registry: Vec<&'static str>,
 }
 /// Each `SubApp` has its own [`Schedule`] and [`World`], enabling a separation of concerns.
 struct SubApp {
     app: App,
-    runner: Box<dyn Fn(&mut World, &mut App)>,
+    /// Copies the data the sub app needs out of the main [`World`], runs right before the sub app updates.
+    extract: ExtractFn,
 }
+type ExtractFn = Box<dyn Fn(&mut World, &mut App)>;
 
 impl Default for App {
     fn default() -> Self {
         let mut app = App::empty();
         #[cfg(feature = "bevy_reflect")]
-        app.init_resource::<bevy_reflect::TypeRegistryArc>();
+        app.init_resource::<reflect::AppTypeRegistry>()
+            .register_type::<String>()
+            .register_type::<bevy_ecs::entity::Entity>()
+            .register_type::<Vec<bevy_ecs::entity::Entity>>()
+            .register_type::<types::Name>()
+            .register_type::<hierarchy::Parent>()
+            .register_type::<hierarchy::Children>();
 
         app.add_default_stages()
+        .add_event::<AppExit>()
         .add_system_to_stage(
             CoreStage::Last, 
             World::clear_trackers);
@@ -185,36 +200,9 @@ impl Default for App {
         app
     }
 }
-#[derive(Debug, Resource)]
-pub struct ProgramStartTime {
-    start_time: instant::Instant,
-}
-#[derive(Resource)]
-pub struct FirstFrameTime {
-    start_time: instant::Instant,
-}
-impl Default for FirstFrameTime {
-    fn default() -> Self {
-        Self {
-            start_time: instant::Instant::now(),
-        }
-    }
-}
-impl FirstFrameTime {
-    pub fn get_time(&self) -> instant::Instant {
-        self.start_time
-    }
-}
 impl App {
     pub fn new() -> App {
-        // App::default()
-        let mut app = App::default();
-
-        app.insert_resource(ProgramStartTime {
-            start_time: instant::Instant::now(),
-        });
-
-        app
+        App::default()
     }
     pub fn empty() -> App {
         Self {
@@ -222,18 +210,86 @@ impl App {
             schedule: Default::default(),
             runner: Box::new(run_once),
             sub_apps: HashMap::default(),
+            plugin_registry: Vec::new(),
         }
     }
     // pub fn add_thread(&mut self, func: fn()) -> &mut Self {
     //     self
     // }
+    /// Adds a [`Plugin`] and runs its setup.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin was already added or if one of its
+    /// [dependencies](Plugin::dependencies) hasn't been added yet,
+    /// see [`try_add_plugin`](Self::try_add_plugin).
     pub fn add_plugin<T>(&mut self, plugin: T) -> &mut Self
     where
         T: Plugin,
     {
+        if let Err(error) = self.try_add_plugin(plugin) {
+            panic!("{}", error);
+        }
+        self
+    }
+    /// Adds a [`Plugin`] and runs its setup, or returns why it can't be added.
+    pub fn try_add_plugin<T>(&mut self, plugin: T) -> Result<&mut Self, plugin_group::PluginError>
+    where
+        T: Plugin,
+    {
+        self.setup_plugin(plugin_group::plugin_name::<T>(), &plugin)?;
+        Ok(self)
+    }
+    /// Adds all enabled plugins of a [`PluginGroup`](plugin_group::PluginGroup) in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`add_plugin`](Self::add_plugin).
+    pub fn add_plugins<G: plugin_group::PluginGroup>(&mut self, group: G) -> &mut Self {
+        let builder = group.build();
+        debug!("Initializing plugin group: {}", builder.group_name());
+        if let Err(error) = builder.finish(self) {
+            panic!("{}", error);
+        }
+        self
+    }
+    /// Returns `true` if a plugin of type `T` has been added.
+    pub fn is_plugin_added<T: Plugin>(&self) -> bool {
+        self.plugin_registry
+            .contains(&plugin_group::plugin_name::<T>())
+    }
+    /// Names of the plugins that have been added, in the order they were added.
+    pub fn added_plugins(&self) -> &[&'static str] {
+        &self.plugin_registry
+    }
+    /// Counts the plugins named `names` as added without setting them up, for an app that
+    /// works on the [`World`] of another app that added them.
+    pub fn assume_plugins_added(&mut self, names: &[&'static str]) -> &mut Self {
+        self.plugin_registry.extend_from_slice(names);
+        self
+    }
+    fn setup_plugin(
+        &mut self,
+        name: &'static str,
+        plugin: &dyn Plugin,
+    ) -> Result<(), plugin_group::PluginError> {
+        if plugin.is_unique() && self.plugin_registry.contains(&name) {
+            return Err(plugin_group::PluginError::Duplicate { plugin: name });
+        }
+        if let Some(dependency) = plugin
+            .dependencies()
+            .into_iter()
+            .find(|dependency| !self.plugin_registry.contains(dependency))
+        {
+            return Err(plugin_group::PluginError::MissingDependency {
+                plugin: name,
+                dependency,
+            });
+        }
         debug!("Initializing: {}", plugin.name());
+        self.plugin_registry.push(name);
         plugin.setup(self);
-        self
+        Ok(())
     }
 
     /// Adds a system to the [startup stage](Self::add_default_stages) of the app's [`Schedule`].
@@ -332,6 +388,74 @@ impl App {
         self
     }
 
+    /// Adds a [`SystemSet`](state::SystemSet) to the [update stage](Self::add_default_stages).
+    ///
+    /// Mostly used with state bound sets, see [`add_state`](Self::add_state).
+    pub fn add_system_set(&mut self, system_set: state::SystemSet) -> &mut Self {
+        self.add_system_set_to_stage(CoreStage::Update, system_set)
+    }
+    /// Adds a [`SystemSet`](state::SystemSet) to the [`Stage`] identified by `stage_label`.
+    pub fn add_system_set_to_stage(
+        &mut self,
+        stage_label: impl StageLabel,
+        system_set: state::SystemSet,
+    ) -> &mut Self {
+        use std::any::TypeId;
+        assert!(
+            stage_label.type_id() != TypeId::of::<StartupStage>(),
+            "add system sets to a startup stage using App::add_startup_system_to_stage"
+        );
+        self.schedule.add_system_set_to_stage(stage_label, system_set);
+        self
+    }
+    /// Adds a [`State`](state::State) that starts at `S::default()` and is driven in [`CoreStage::Update`].
+    ///
+    /// Systems are bound to a state value with the `on_enter`, `on_exit` and `on_update` sets,
+    /// changes are requested through `ResMut<State<S>>` with `set`, `push` and `pop`.
+    /// Every change is also sent as an [`OnStateTransition`](state::OnStateTransition) event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ne_app::{App, state::{State, SystemSet}};
+    /// # use bevy_ecs::system::ResMut;
+    /// #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+    /// enum GameState {
+    ///     #[default]
+    ///     MainMenu,
+    ///     Playing,
+    ///     Paused,
+    /// }
+    /// fn start_game(mut state: ResMut<State<GameState>>) {
+    ///     let _ = state.set(GameState::Playing);
+    /// }
+    /// fn open_pause_menu(mut state: ResMut<State<GameState>>) {
+    ///     let _ = state.push(GameState::Paused);
+    /// }
+    ///
+    /// App::new()
+    ///     .add_state::<GameState>()
+    ///     .add_system_set(SystemSet::on_update(GameState::MainMenu).with_system(start_game))
+    ///     .add_system_set(SystemSet::on_update(GameState::Playing).with_system(open_pause_menu));
+    /// ```
+    pub fn add_state<S: state::StateData + Default>(&mut self) -> &mut Self {
+        self.add_state_to_stage(CoreStage::Update, S::default())
+    }
+    /// Adds a [`State`](state::State) that starts at `initial` and is driven in the [`Stage`] identified by `stage_label`.
+    ///
+    /// State bound system sets only work in a stage that has a driver, to use the state in
+    /// more stages add [`State::get_driver`](state::State::get_driver) to them as well.
+    pub fn add_state_to_stage<S: state::StateData>(
+        &mut self,
+        stage_label: impl StageLabel,
+        initial: S,
+    ) -> &mut Self {
+        self.insert_resource(state::State::new(initial))
+            .add_system_set_to_stage(stage_label, state::State::<S>::get_driver())
+            .add_event::<state::OnStateTransition<S>>()
+            .add_system_to_stage(CoreStage::Last, state::state_transition_system::<S>)
+    }
+
     /// Adds a system to the [startup schedule](Self::add_default_stages), in the stage
     /// identified by `stage_label`.
     ///
@@ -389,18 +513,18 @@ impl App {
         self
     }
 
+    /// Starts the application by calling the app's [runner function](Self::set_runner).
+    ///
+    /// Returns once the runner returns, for a main loop without a window see
+    /// [`ScheduleRunnerPlugin`](schedule_runner::ScheduleRunnerPlugin).
     pub fn run(&mut self) {
-        loop {
-            self.schedule.run(&mut self.world);
+        // #[cfg(feature = "trace")]
+        // let _bevy_app_run_span = info_span!("bevy_app").entered();
 
-            // #[cfg(feature = "trace")]
-            // let _bevy_app_run_span = info_span!("bevy_app").entered();
-
-            //Calls the apps runner funtion! Self::set_runner
-            let mut app = std::mem::replace(self, App::empty());
-            let runner = std::mem::replace(&mut app.runner, Box::new(run_once));
-            (runner)(app);
-        }
+        //Calls the apps runner funtion! Self::set_runner
+        let mut app = std::mem::replace(self, App::empty());
+        let runner = std::mem::replace(&mut app.runner, Box::new(run_once));
+        (runner)(app);
     }
 
     /// Adds a [`Stage`] with the given `label` to the last position of the app's
@@ -441,6 +565,8 @@ impl App {
     ///     - **Post-startup:** Intended for systems that need to run after other startup systems.
     /// - **Pre-update:** Often used by plugins to prepare their internal state before the
     ///   update stage begins.
+    /// - **Fixed-update:** Runs zero or more times per frame at the rate set in
+    ///   [`FixedTime`](fixed_timestep::FixedTime).
     /// - **Update:** Intended for user defined logic. Systems are added here by default.
     /// - **Post-update:** Often used by plugins to finalize their internal state after the
     ///   world changes that happened during the update stage.
@@ -466,7 +592,16 @@ impl App {
                     .with_stage(StartupStage::PostStartup, SystemStage::parallel()),
             )
             .add_stage(CoreStage::PreUpdate, SystemStage::parallel())
+            .add_stage(
+                CoreStage::FixedUpdate,
+                SystemStage::parallel()
+                    .with_run_criteria(fixed_timestep::fixed_timestep_run_criteria),
+            )
             .add_stage(CoreStage::Update, SystemStage::parallel())
+            .add_stage(
+                CoreStage::Gameplay,
+                SystemStage::parallel().with_run_criteria(play::gameplay_run_criteria),
+            )
             .add_stage(CoreStage::PostUpdate, SystemStage::parallel())
             .add_stage(CoreStage::Last, SystemStage::parallel())
     }
@@ -487,9 +622,93 @@ impl App {
         let _bevy_frame_update_span = info_span!("frame").entered();
         self.schedule.run(&mut self.world);
         for sub_app in self.sub_apps.values_mut() {
-            (sub_app.runner)(&mut self.world, &mut sub_app.app);
+            (sub_app.extract)(&mut self.world, &mut sub_app.app);
+            sub_app.app.update();
         }
     }
+    /// Adds an [`App`] with its own [`World`] and [`Schedule`] that is updated every time
+    /// this app updates, after the main schedule has run.
+    ///
+    /// Before every update of the sub app `extract` is called with the main [`World`],
+    /// it should copy the data the sub app needs into the sub app's world. Keeping the
+    /// worlds separate is what allows e.g. render preparation to run alongside game logic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ne_app::App;
+    /// # use bevy_ecs::system::Resource;
+    /// #[derive(Resource, Clone, Default)]
+    /// struct FrameData(u32);
+    ///
+    /// let mut app = App::new();
+    /// app.init_resource::<FrameData>()
+    ///     .add_sub_app("render", App::new(), |main_world, render_app| {
+    ///         let frame_data = main_world.resource::<FrameData>().clone();
+    ///         render_app.insert_resource(frame_data);
+    ///     });
+    /// app.update();
+    /// assert!(app.sub_app("render").world.contains_resource::<FrameData>());
+    /// ```
+    pub fn add_sub_app(
+        &mut self,
+        label: impl AppLabel,
+        app: App,
+        extract: impl Fn(&mut World, &mut App) + 'static,
+    ) -> &mut Self {
+        self.sub_apps.insert(
+            label.as_label(),
+            SubApp {
+                app,
+                extract: Box::new(extract),
+            },
+        );
+        self
+    }
+    /// Removes the sub app with the given `label` and returns it, if it exists.
+    pub fn remove_sub_app(&mut self, label: impl AppLabel) -> Option<App> {
+        self.sub_apps
+            .remove(&label.as_label())
+            .map(|sub_app| sub_app.app)
+    }
+    /// Retrieves a sub app stored inside this [`App`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sub app doesn't exist.
+    pub fn sub_app(&self, label: impl AppLabel) -> &App {
+        match self.get_sub_app(label) {
+            Ok(app) => app,
+            Err(label) => panic!("Sub-App with label '{:?}' does not exist", label),
+        }
+    }
+    /// Retrieves a sub app stored inside this [`App`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sub app doesn't exist.
+    pub fn sub_app_mut(&mut self, label: impl AppLabel) -> &mut App {
+        match self.get_sub_app_mut(label) {
+            Ok(app) => app,
+            Err(label) => panic!("Sub-App with label '{:?}' does not exist", label),
+        }
+    }
+    /// Retrieves a sub app stored inside this [`App`], or the label if it doesn't exist.
+    pub fn get_sub_app(&self, label: impl AppLabel) -> Result<&App, AppLabelId> {
+        let label = label.as_label();
+        self.sub_apps
+            .get(&label)
+            .map(|sub_app| &sub_app.app)
+            .ok_or(label)
+    }
+    /// Retrieves a sub app stored inside this [`App`], or the label if it doesn't exist.
+    pub fn get_sub_app_mut(&mut self, label: impl AppLabel) -> Result<&mut App, AppLabelId> {
+        let label = label.as_label();
+        self.sub_apps
+            .get_mut(&label)
+            .map(|sub_app| &mut sub_app.app)
+            .ok_or(label)
+    }
     /// Initialize a [`Resource`] with standard starting values by adding it to the [`World`].
     ///
     /// If the [`Resource`] already exists, nothing happens.
@@ -576,20 +795,43 @@ impl App {
     where
         T: Event,
     {
-        if !self.world.contains_resource::<Events<T>>() {
+        let newly_registered = self
+            .world
+            .get_resource_or_insert_with(RegisteredEvents::default)
+            .0
+            .insert(std::any::TypeId::of::<T>());
+        if newly_registered {
             self.init_resource::<Events<T>>()
                 .add_system_to_stage(CoreStage::First, Events::<T>::update_system);
         }
         self
     }
 }
-pub trait Plugin /* Any + Send + Sync */ {
+/// Event types whose [`update_system`](Events::update_system) has been added by [`App::add_event`].
+///
+/// Lives in the [`World`] rather than the [`Schedule`], so code that rebuilds a schedule for an
+/// existing world (like hot reloading) can forget registrations and add the update systems again.
+#[derive(Debug, Default, Resource)]
+pub struct RegisteredEvents(pub std::collections::HashSet<std::any::TypeId>);
+/// An event that asks the [`App`] to exit, the runner stops after the update it was sent in.
+#[derive(Debug, Clone, Default)]
+pub struct AppExit;
+
+pub trait Plugin: 'static /* Any + Send + Sync */ {
     /// Configures the [`App`] to which this plugin is added.
     fn setup(&self, app: &mut App);
     /// Configures a name for the [`Plugin`] which is primarily used for debugging.
     fn name(&self) -> &str {
         std::any::type_name::<Self>()
     }
+    /// Plugins that have to be added before this one, named with [`plugin_name`](plugin_group::plugin_name).
+    fn dependencies(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+    /// Returns `false` if this plugin may be added more than once.
+    fn is_unique(&self) -> bool {
+        true
+    }
 }
 fn run_once(mut app: App) {
     app.update();
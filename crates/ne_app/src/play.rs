@@ -0,0 +1,33 @@
+//! Whether the game is running, for [`CoreStage::Gameplay`](crate::CoreStage::Gameplay).
+//!
+//! A runtime never inserts [`PlayState`], its gameplay always runs. The editor inserts it to
+//! stop gameplay while the scene is being edited.
+use bevy_ecs::{
+    schedule::ShouldRun,
+    system::{Res, Resource},
+};
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PlayState {
+    /// Gameplay systems don't run, the scene is edited.
+    #[default]
+    Editing,
+    Playing,
+    /// Gameplay systems don't run, the played scene is kept as it is.
+    Paused,
+}
+impl PlayState {
+    pub fn is_playing(&self) -> bool {
+        *self == PlayState::Playing
+    }
+}
+
+/// Run criteria of [`CoreStage::Gameplay`](crate::CoreStage::Gameplay),
+/// runs the stage without a [`PlayState`] or while it is [`PlayState::Playing`].
+pub fn gameplay_run_criteria(play_state: Option<Res<PlayState>>) -> ShouldRun {
+    if play_state.is_none_or(|state| state.is_playing()) {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
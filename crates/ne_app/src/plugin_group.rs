@@ -0,0 +1,185 @@
+//! Bundles of plugins that are added together, see [`App::add_plugins`](crate::App::add_plugins).
+use std::fmt;
+
+use crate::{App, Plugin};
+
+/// Returns the name a plugin is registered under, use it to declare [`Plugin::dependencies`].
+pub fn plugin_name<T: Plugin>() -> &'static str {
+    std::any::type_name::<T>()
+}
+
+/// Reasons a plugin can't be added to an [`App`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginError {
+    /// The plugin was already added and is [unique](Plugin::is_unique).
+    Duplicate { plugin: &'static str },
+    /// A plugin that this plugin depends on hasn't been added before it.
+    MissingDependency {
+        plugin: &'static str,
+        dependency: &'static str,
+    },
+}
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::Duplicate { plugin } => write!(
+                f,
+                "plugin {} was added twice, it may only be added once",
+                plugin
+            ),
+            PluginError::MissingDependency { plugin, dependency } => write!(
+                f,
+                "plugin {} depends on {}, add {} before it",
+                plugin, dependency, dependency
+            ),
+        }
+    }
+}
+impl std::error::Error for PluginError {}
+
+/// A set of plugins that are added in order by [`App::add_plugins`](crate::App::add_plugins).
+pub trait PluginGroup {
+    /// Lists the plugins of this group.
+    fn build(self) -> PluginGroupBuilder;
+    /// Adds or replaces a plugin of this group, handy for configuring it.
+    fn set<T: Plugin>(self, plugin: T) -> PluginGroupBuilder
+    where
+        Self: Sized,
+    {
+        self.build().set(plugin)
+    }
+}
+impl PluginGroup for PluginGroupBuilder {
+    fn build(self) -> PluginGroupBuilder {
+        self
+    }
+}
+
+struct PluginEntry {
+    name: &'static str,
+    plugin: Box<dyn Plugin>,
+    enabled: bool,
+}
+
+/// Ordered list of plugins of a [`PluginGroup`], plugins can be disabled or replaced.
+///
+/// ```
+/// # use ne_app::{App, Plugin, plugin_group::{PluginGroup, PluginGroupBuilder}};
+/// # struct PhysicsPlugin;
+/// # impl Plugin for PhysicsPlugin { fn setup(&self, app: &mut App) {} }
+/// # struct AudioPlugin;
+/// # impl Plugin for AudioPlugin { fn setup(&self, app: &mut App) {} }
+/// struct GamePlugins;
+/// impl PluginGroup for GamePlugins {
+///     fn build(self) -> PluginGroupBuilder {
+///         PluginGroupBuilder::start::<Self>()
+///             .add(PhysicsPlugin)
+///             .add(AudioPlugin)
+///     }
+/// }
+///
+/// App::new().add_plugins(GamePlugins.build().disable::<AudioPlugin>());
+/// ```
+pub struct PluginGroupBuilder {
+    group_name: &'static str,
+    plugins: Vec<PluginEntry>,
+}
+impl PluginGroupBuilder {
+    /// Starts an empty group named after `G`.
+    pub fn start<G: PluginGroup>() -> Self {
+        Self {
+            group_name: std::any::type_name::<G>(),
+            plugins: Vec::new(),
+        }
+    }
+    /// Name of the group this builder was started for.
+    pub fn group_name(&self) -> &'static str {
+        self.group_name
+    }
+    fn index_of<T: Plugin>(&self) -> Option<usize> {
+        let name = plugin_name::<T>();
+        self.plugins.iter().position(|entry| entry.name == name)
+    }
+    fn expect_index_of<T: Plugin>(&self) -> usize {
+        self.index_of::<T>().unwrap_or_else(|| {
+            panic!(
+                "plugin {} is not part of {}",
+                plugin_name::<T>(),
+                self.group_name
+            )
+        })
+    }
+    fn entry<T: Plugin>(plugin: T) -> PluginEntry {
+        PluginEntry {
+            name: plugin_name::<T>(),
+            plugin: Box::new(plugin),
+            enabled: true,
+        }
+    }
+    /// Adds a plugin at the end of the group, an earlier entry of the same plugin is removed.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add<T: Plugin>(mut self, plugin: T) -> Self {
+        if let Some(index) = self.index_of::<T>() {
+            self.plugins.remove(index);
+        }
+        self.plugins.push(Self::entry(plugin));
+        self
+    }
+    /// Adds a plugin right before `Target`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Target` isn't part of the group.
+    pub fn add_before<Target: Plugin, T: Plugin>(mut self, plugin: T) -> Self {
+        let index = self.expect_index_of::<Target>();
+        self.plugins.insert(index, Self::entry(plugin));
+        self
+    }
+    /// Adds a plugin right after `Target`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Target` isn't part of the group.
+    pub fn add_after<Target: Plugin, T: Plugin>(mut self, plugin: T) -> Self {
+        let index = self.expect_index_of::<Target>();
+        self.plugins.insert(index + 1, Self::entry(plugin));
+        self
+    }
+    /// Replaces a plugin of the group with a differently configured one, keeping its position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` isn't part of the group.
+    pub fn set<T: Plugin>(mut self, plugin: T) -> Self {
+        let index = self.expect_index_of::<T>();
+        self.plugins[index] = Self::entry(plugin);
+        self
+    }
+    /// Skips a plugin of the group when it is added to the app.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` isn't part of the group.
+    pub fn disable<T: Plugin>(mut self) -> Self {
+        let index = self.expect_index_of::<T>();
+        self.plugins[index].enabled = false;
+        self
+    }
+    /// Undoes [`disable`](Self::disable).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` isn't part of the group.
+    pub fn enable<T: Plugin>(mut self) -> Self {
+        let index = self.expect_index_of::<T>();
+        self.plugins[index].enabled = true;
+        self
+    }
+    /// Adds the enabled plugins of the group to `app` in order.
+    pub(crate) fn finish(self, app: &mut App) -> Result<(), PluginError> {
+        for entry in self.plugins.into_iter().filter(|entry| entry.enabled) {
+            app.setup_plugin(entry.name, entry.plugin.as_ref())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+//! Type information of components at runtime, the editor inspects and scenes serialize the
+//! registered types without knowing them.
+//! ```ignore
+//! #[derive(Component, Reflect, Default)]
+//! #[reflect(Component)]
+//! struct Health(f32);
+//! app.register_type::<Health>();
+//! ```
+use bevy_derive::{Deref, DerefMut};
+pub use bevy_ecs::reflect::{ReflectComponent, ReflectMapEntities, ReflectResource};
+use bevy_ecs::system::Resource;
+pub use bevy_reflect::{self, GetTypeRegistration, Reflect, TypeRegistryArc};
+
+use crate::App;
+
+/// The types registered with [`App::register_type`].
+#[derive(Resource, Clone, Default, Deref, DerefMut)]
+pub struct AppTypeRegistry(pub TypeRegistryArc);
+
+impl App {
+    /// Registers `T` with the type data its `#[reflect(..)]` attribute lists, a component
+    /// needs `#[reflect(Component)]` to be found in the world.
+    pub fn register_type<T: GetTypeRegistration>(&mut self) -> &mut Self {
+        self.world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<T>();
+        self
+    }
+}
@@ -0,0 +1,164 @@
+//! A main loop without a window, for dedicated servers and tests.
+use bevy_ecs::{
+    event::{Events, ManualEventReader},
+    system::Resource,
+};
+use instant::{Duration, Instant};
+use tracing::debug;
+
+use crate::{App, AppExit, Plugin};
+
+/// How often [`ScheduleRunnerPlugin`] updates the [`App`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunMode {
+    /// Updates in a loop until an [`AppExit`] event is sent.
+    Loop {
+        /// Minimum time between the start of two updates, [`None`] runs as fast as possible.
+        wait: Option<Duration>,
+    },
+    /// Updates a single time.
+    Once,
+}
+impl Default for RunMode {
+    fn default() -> Self {
+        RunMode::Loop { wait: None }
+    }
+}
+
+/// Configures [`ScheduleRunnerPlugin`], insert it before adding the plugin.
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct ScheduleRunnerSettings {
+    pub run_mode: RunMode,
+    /// Stops the loop after this many updates, [`None`] runs until [`AppExit`].
+    pub max_frames: Option<u64>,
+}
+impl ScheduleRunnerSettings {
+    /// Updates a single time.
+    pub fn run_once() -> Self {
+        Self {
+            run_mode: RunMode::Once,
+            max_frames: None,
+        }
+    }
+    /// Updates in a loop, waiting at least `wait` between the start of two updates.
+    pub fn run_loop(wait: Duration) -> Self {
+        Self {
+            run_mode: RunMode::Loop { wait: Some(wait) },
+            max_frames: None,
+        }
+    }
+    /// Updates in a loop `hz` times per second.
+    pub fn from_hz(hz: f64) -> Self {
+        Self::run_loop(Duration::from_secs_f64(1.0 / hz))
+    }
+    /// Stops after `frames` updates.
+    #[must_use]
+    pub fn with_max_frames(mut self, frames: u64) -> Self {
+        self.max_frames = Some(frames);
+        self
+    }
+}
+
+/// Sets a runner that updates the [`App`] without a window or event loop.
+///
+/// Reads [`ScheduleRunnerSettings`] on setup, by default it loops as fast as possible until
+/// an [`AppExit`] event is sent.
+///
+/// ```
+/// # use ne_app::{App, schedule_runner::{ScheduleRunnerPlugin, ScheduleRunnerSettings}};
+/// App::new()
+///     .insert_resource(ScheduleRunnerSettings::from_hz(60.0).with_max_frames(3))
+///     .add_plugin(ScheduleRunnerPlugin)
+///     .run();
+/// ```
+pub struct ScheduleRunnerPlugin;
+impl Plugin for ScheduleRunnerPlugin {
+    fn setup(&self, app: &mut App) {
+        let settings = app
+            .world
+            .get_resource_or_insert_with(ScheduleRunnerSettings::default)
+            .to_owned();
+        app.set_runner(move |app| schedule_runner(app, settings));
+    }
+}
+
+fn schedule_runner(mut app: App, settings: ScheduleRunnerSettings) {
+    let mut app_exit_event_reader = ManualEventReader::<AppExit>::default();
+    let mut frame_count: u64 = 0;
+    match settings.run_mode {
+        RunMode::Once => app.update(),
+        RunMode::Loop { wait } => loop {
+            let start = Instant::now();
+            app.update();
+            frame_count += 1;
+
+            if let Some(app_exit_events) = app.world.get_resource::<Events<AppExit>>() {
+                if app_exit_event_reader.iter(app_exit_events).last().is_some() {
+                    debug!("AppExit received after {} frames", frame_count);
+                    break;
+                }
+            }
+            if settings.max_frames.is_some_and(|max| frame_count >= max) {
+                debug!("Reached max frames: {}", frame_count);
+                break;
+            }
+            if let Some(wait) = wait {
+                let elapsed = start.elapsed();
+                if elapsed < wait {
+                    std::thread::sleep(wait - elapsed);
+                }
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    };
+
+    use bevy_ecs::event::EventWriter;
+
+    use super::*;
+
+    /// Runs `app` and returns how many times it updated.
+    fn count_frames(app: &mut App, settings: ScheduleRunnerSettings) -> u64 {
+        let frames = Arc::new(AtomicU64::new(0));
+        let counter = frames.clone();
+        app.insert_resource(settings)
+            .add_plugin(ScheduleRunnerPlugin)
+            .add_system(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+            })
+            .run();
+        frames.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn runs_once() {
+        let frames = count_frames(&mut App::new(), ScheduleRunnerSettings::run_once());
+        assert_eq!(frames, 1);
+    }
+
+    #[test]
+    fn stops_after_max_frames() {
+        let settings = ScheduleRunnerSettings::default().with_max_frames(5);
+        assert_eq!(count_frames(&mut App::new(), settings), 5);
+    }
+
+    #[test]
+    fn stops_on_app_exit() {
+        let mut app = App::new();
+        let mut frame = 0;
+        app.add_system(move |mut app_exit: EventWriter<AppExit>| {
+            frame += 1;
+            if frame == 3 {
+                app_exit.send(AppExit);
+            }
+        });
+        let settings = ScheduleRunnerSettings::default().with_max_frames(100);
+        assert_eq!(count_frames(&mut app, settings), 3);
+    }
+}
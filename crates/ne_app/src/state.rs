@@ -0,0 +1,63 @@
+//! Game states like main menu, loading, playing and paused.
+//!
+//! Built on the stack based [`State`] of bevy_ecs, see [`App::add_state`](crate::App::add_state).
+//! Systems are attached to a state value with [`SystemSet::on_enter`], [`SystemSet::on_exit`]
+//! and [`SystemSet::on_update`]. [`State::push`] and [`State::pop`] are meant for overlays like
+//! a pause menu, the state underneath is paused instead of exited.
+pub use bevy_ecs::schedule::{State, StateData, StateError, SystemSet};
+use bevy_ecs::{
+    event::EventWriter,
+    system::{Local, Res},
+};
+
+/// An event that is sent after the current value of `State<S>` has changed.
+///
+/// Sent from [`CoreStage::Last`](crate::CoreStage::Last) of the frame the transition happened in.
+/// When a state changes several times in a single frame only the overall change is reported.
+#[derive(Debug, Clone)]
+pub struct OnStateTransition<S: StateData> {
+    /// The previously active state, [`None`] for the initial state.
+    pub from: Option<S>,
+    /// The newly active state.
+    pub to: S,
+    /// States that were paused underneath `from` by a push.
+    pub paused_from: Vec<S>,
+    /// States that are now paused underneath `to` by a push.
+    pub paused_to: Vec<S>,
+}
+impl<S: StateData> OnStateTransition<S> {
+    /// Returns `true` if `to` was pushed on top of `from`.
+    pub fn is_push(&self) -> bool {
+        self.paused_to.len() > self.paused_from.len()
+    }
+    /// Returns `true` if `from` was popped off of `to`.
+    pub fn is_pop(&self) -> bool {
+        self.paused_to.len() < self.paused_from.len()
+    }
+}
+
+/// Compares the state stack to the one of the previous frame and sends [`OnStateTransition`] on change.
+pub fn state_transition_system<S: StateData>(
+    state: Res<State<S>>,
+    mut last: Local<Option<(S, Vec<S>)>>,
+    mut transitions: EventWriter<OnStateTransition<S>>,
+) {
+    let current = state.current();
+    let inactives = state.inactives();
+    let (from, paused_from) = match last.as_ref() {
+        Some((last_current, last_inactives)) => {
+            if last_current == current && last_inactives.as_slice() == inactives {
+                return;
+            }
+            (Some(last_current.clone()), last_inactives.clone())
+        }
+        None => (None, Vec::new()),
+    };
+    transitions.send(OnStateTransition {
+        from,
+        to: current.clone(),
+        paused_from,
+        paused_to: inactives.to_vec(),
+    });
+    *last = Some((current.clone(), inactives.to_vec()));
+}
@@ -0,0 +1,431 @@
+//! The [`Time`] resource and the [`Timer`]/[`Stopwatch`] helpers that tick from it.
+use bevy_ecs::{
+    schedule::{IntoSystemDescriptor, SystemLabel},
+    system::{ResMut, Resource},
+};
+use instant::{Duration, Instant};
+
+use crate::{
+    fixed_timestep::{
+        fixed_time_system, fixed_timestep_alpha_system, FixedTime, FixedTimestepAlpha,
+    },
+    App, CoreStage, Plugin,
+};
+
+/// Adds [`Time`], [`FixedTime`] and [`FixedTimestepAlpha`] and keeps them up to date.
+pub struct TimePlugin;
+impl Plugin for TimePlugin {
+    fn setup(&self, app: &mut App) {
+        app.init_resource::<Time>()
+            .init_resource::<FixedTime>()
+            .init_resource::<FixedTimestepAlpha>()
+            .add_system_to_stage(CoreStage::First, time_system.label(TimeSystem))
+            .add_system_to_stage(CoreStage::First, fixed_time_system.after(TimeSystem))
+            .add_system_to_stage(CoreStage::Update, fixed_timestep_alpha_system);
+    }
+}
+
+/// Tracks how much time has passed since the app started and since the previous update.
+///
+/// There are two clocks:
+/// - the *raw* clock follows real time, it never pauses and ignores the time scale.
+/// - the *scaled* clock is what game logic should read, it stands still while paused
+///   and runs at [`relative_speed`](Time::relative_speed).
+///
+/// Added by [`TimePlugin`], updated at the start of every frame by [`time_system`] in [`CoreStage::First`](crate::CoreStage::First).
+/// Outside of an app (or in tests) call [`update_with_instant`](Time::update_with_instant) to step it manually.
+#[derive(Debug, Clone, Resource)]
+pub struct Time {
+    startup: Instant,
+    first_update: Option<Instant>,
+    last_update: Option<Instant>,
+    frame_count: u64,
+    paused: bool,
+    relative_speed: f64,
+    delta: Duration,
+    elapsed: Duration,
+    raw_delta: Duration,
+    raw_elapsed: Duration,
+}
+impl Default for Time {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+impl Time {
+    /// Creates a new clock that started at `startup`.
+    pub fn new(startup: Instant) -> Self {
+        Self {
+            startup,
+            first_update: None,
+            last_update: None,
+            frame_count: 0,
+            paused: false,
+            relative_speed: 1.0,
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            raw_delta: Duration::ZERO,
+            raw_elapsed: Duration::ZERO,
+        }
+    }
+    /// Advances the clocks to now.
+    pub fn update(&mut self) {
+        self.update_with_instant(Instant::now());
+    }
+    /// Advances the clocks to `instant`.
+    ///
+    /// The first update has a delta of zero, following updates measure from the previous one.
+    pub fn update_with_instant(&mut self, instant: Instant) {
+        let raw_delta = match self.last_update {
+            Some(last_update) => instant - last_update,
+            None => Duration::ZERO,
+        };
+        let delta = if self.paused {
+            Duration::ZERO
+        } else if self.relative_speed == 1.0 {
+            raw_delta
+        } else {
+            raw_delta.mul_f64(self.relative_speed)
+        };
+        if self.first_update.is_none() {
+            self.first_update = Some(instant);
+        }
+        self.last_update = Some(instant);
+        self.frame_count = self.frame_count.wrapping_add(1);
+        self.raw_delta = raw_delta;
+        self.raw_elapsed += raw_delta;
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+
+    /// The moment the clock was created.
+    pub fn startup(&self) -> Instant {
+        self.startup
+    }
+    /// The moment of the first update, [`None`] before the first frame.
+    pub fn first_update(&self) -> Option<Instant> {
+        self.first_update
+    }
+    /// The moment of the most recent update, [`None`] before the first frame.
+    pub fn last_update(&self) -> Option<Instant> {
+        self.last_update
+    }
+    /// Amount of updates so far, including the current one.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Scaled time since the previous update, zero while paused.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+    /// Scaled time since the previous update in seconds, zero while paused.
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+    /// Scaled time since the previous update in seconds, zero while paused.
+    pub fn delta_seconds_f64(&self) -> f64 {
+        self.delta.as_secs_f64()
+    }
+    /// Scaled time since the first update.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    /// Scaled time since the first update in seconds.
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+    /// Scaled time since the first update in seconds.
+    pub fn elapsed_seconds_f64(&self) -> f64 {
+        self.elapsed.as_secs_f64()
+    }
+
+    /// Real time since the previous update, ignores pausing and time scale.
+    pub fn raw_delta(&self) -> Duration {
+        self.raw_delta
+    }
+    /// Real time since the previous update in seconds, ignores pausing and time scale.
+    pub fn raw_delta_seconds(&self) -> f32 {
+        self.raw_delta.as_secs_f32()
+    }
+    /// Real time since the first update, ignores pausing and time scale.
+    pub fn raw_elapsed(&self) -> Duration {
+        self.raw_elapsed
+    }
+    /// Real time since the first update in seconds, ignores pausing and time scale.
+    pub fn raw_elapsed_seconds(&self) -> f32 {
+        self.raw_elapsed.as_secs_f32()
+    }
+    /// Real time since the first update in seconds, ignores pausing and time scale.
+    pub fn raw_elapsed_seconds_f64(&self) -> f64 {
+        self.raw_elapsed.as_secs_f64()
+    }
+
+    /// Stops the scaled clock, starting with the next update.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    /// Resumes the scaled clock, starting with the next update.
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+    /// Returns `true` if the scaled clock is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    /// Speed of the scaled clock relative to real time, `1.0` by default.
+    pub fn relative_speed(&self) -> f64 {
+        self.relative_speed
+    }
+    /// Sets the speed of the scaled clock relative to real time.
+    /// `0.5` runs the game at half speed, `2.0` at double speed.
+    pub fn set_relative_speed(&mut self, ratio: f64) {
+        assert!(ratio.is_finite(), "tried to go infinitely fast");
+        assert!(ratio >= 0.0, "tried to go back in time");
+        self.relative_speed = ratio;
+    }
+}
+
+/// Label of [`time_system`], systems in [`CoreStage::First`](crate::CoreStage::First)
+/// that read [`Time`] should run after it.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub struct TimeSystem;
+
+/// Advances [`Time`] to now, runs at the start of [`CoreStage::First`](crate::CoreStage::First).
+pub fn time_system(mut time: ResMut<Time>) {
+    time.update();
+}
+
+/// Keeps track of elapsed time, tick it with [`Time::delta`].
+///
+/// ```
+/// # use ne_app::time::{Stopwatch, Time};
+/// # let time = Time::default();
+/// let mut stopwatch = Stopwatch::new();
+/// stopwatch.tick(time.delta());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stopwatch {
+    elapsed: Duration,
+    paused: bool,
+}
+impl Stopwatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds `delta` to the elapsed time, unless the stopwatch is paused.
+    pub fn tick(&mut self, delta: Duration) -> &Self {
+        if !self.paused {
+            self.elapsed += delta;
+        }
+        self
+    }
+    /// Time that has been ticked so far.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    /// Time that has been ticked so far in seconds.
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    /// Sets the elapsed time back to zero, the paused state is kept.
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+/// Whether a [`Timer`] stops after finishing or starts over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimerMode {
+    /// Finishes once and stays finished until reset.
+    #[default]
+    Once,
+    /// Starts over each time it finishes.
+    Repeating,
+}
+
+/// Counts down a duration, tick it with [`Time::delta`].
+///
+/// ```
+/// # use ne_app::time::{Timer, TimerMode};
+/// # use instant::Duration;
+/// let mut timer = Timer::from_seconds(1.0, TimerMode::Repeating);
+/// timer.tick(Duration::from_millis(2500));
+/// assert_eq!(timer.times_finished_this_tick(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timer {
+    stopwatch: Stopwatch,
+    duration: Duration,
+    mode: TimerMode,
+    finished: bool,
+    times_finished_this_tick: u32,
+}
+impl Timer {
+    pub fn new(duration: Duration, mode: TimerMode) -> Self {
+        Self {
+            duration,
+            mode,
+            ..Default::default()
+        }
+    }
+    pub fn from_seconds(duration: f32, mode: TimerMode) -> Self {
+        Self::new(Duration::from_secs_f32(duration), mode)
+    }
+    /// Advances the timer by `delta`, unless it is paused.
+    ///
+    /// A repeating timer wraps around and may finish several times in one tick.
+    pub fn tick(&mut self, delta: Duration) -> &Self {
+        if self.paused() {
+            self.times_finished_this_tick = 0;
+            return self;
+        }
+        if self.mode == TimerMode::Once && self.finished {
+            self.times_finished_this_tick = 0;
+            return self;
+        }
+        self.stopwatch.tick(delta);
+        self.finished = self.elapsed() >= self.duration;
+
+        if !self.finished {
+            self.times_finished_this_tick = 0;
+        } else if self.mode == TimerMode::Repeating && !self.duration.is_zero() {
+            let elapsed = self.elapsed().as_nanos();
+            let duration = self.duration.as_nanos();
+            self.times_finished_this_tick = (elapsed / duration) as u32;
+            self.stopwatch
+                .set_elapsed(Duration::from_nanos((elapsed % duration) as u64));
+        } else {
+            self.times_finished_this_tick = 1;
+            self.stopwatch.set_elapsed(self.duration);
+        }
+        self
+    }
+    /// Returns `true` if the timer finished during the last tick,
+    /// a [`TimerMode::Once`] timer stays finished until reset.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+    /// Returns `true` only on the tick the timer finished.
+    pub fn just_finished(&self) -> bool {
+        self.times_finished_this_tick > 0
+    }
+    /// How many times the timer finished during the last tick.
+    pub fn times_finished_this_tick(&self) -> u32 {
+        self.times_finished_this_tick
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.stopwatch.elapsed()
+    }
+    pub fn elapsed_secs(&self) -> f32 {
+        self.stopwatch.elapsed_secs()
+    }
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+    /// Time left until the timer finishes.
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed())
+    }
+    /// Fraction of the duration that has elapsed, in `0.0..=1.0`.
+    pub fn percent(&self) -> f32 {
+        if self.duration.is_zero() {
+            1.0
+        } else {
+            self.elapsed().as_secs_f32() / self.duration.as_secs_f32()
+        }
+    }
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+    pub fn set_mode(&mut self, mode: TimerMode) {
+        self.mode = mode;
+    }
+    pub fn pause(&mut self) {
+        self.stopwatch.pause();
+    }
+    pub fn unpause(&mut self) {
+        self.stopwatch.unpause();
+    }
+    pub fn paused(&self) -> bool {
+        self.stopwatch.is_paused()
+    }
+    /// Starts the timer over, the paused state is kept.
+    pub fn reset(&mut self) {
+        self.stopwatch.reset();
+        self.finished = false;
+        self.times_finished_this_tick = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_update_has_no_delta() {
+        let startup = Instant::now();
+        let mut time = Time::new(startup);
+        time.update_with_instant(startup + Duration::from_secs(1));
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.frame_count(), 1);
+        time.update_with_instant(startup + Duration::from_secs(3));
+        assert_eq!(time.delta(), Duration::from_secs(2));
+        assert_eq!(time.elapsed(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn paused_clock_stands_still() {
+        let startup = Instant::now();
+        let mut time = Time::new(startup);
+        time.update_with_instant(startup);
+        time.pause();
+        time.update_with_instant(startup + Duration::from_secs(1));
+        assert_eq!(time.delta(), Duration::ZERO);
+        assert_eq!(time.elapsed(), Duration::ZERO);
+        assert_eq!(time.raw_delta(), Duration::from_secs(1));
+
+        time.unpause();
+        time.update_with_instant(startup + Duration::from_secs(2));
+        assert_eq!(time.delta(), Duration::from_secs(1));
+        assert_eq!(time.elapsed(), Duration::from_secs(1));
+        assert_eq!(time.raw_elapsed(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn relative_speed_scales_the_clock() {
+        let startup = Instant::now();
+        let mut time = Time::new(startup);
+        time.update_with_instant(startup);
+        time.set_relative_speed(0.5);
+        time.update_with_instant(startup + Duration::from_secs(2));
+        assert_eq!(time.delta(), Duration::from_secs(1));
+        assert_eq!(time.raw_delta(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn repeating_timer_finishes_every_duration() {
+        let mut timer = Timer::new(Duration::from_secs(1), TimerMode::Repeating);
+        timer.tick(Duration::from_millis(2500));
+        assert!(timer.just_finished());
+        assert_eq!(timer.times_finished_this_tick(), 2);
+        assert_eq!(timer.elapsed(), Duration::from_millis(500));
+        timer.tick(Duration::from_millis(100));
+        assert!(!timer.just_finished());
+    }
+}
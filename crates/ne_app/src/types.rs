@@ -1,6 +1,10 @@
 use ahash::AHasher;
 //stolen from bevy.
 use bevy_ecs::{prelude::Component};
+#[cfg(feature = "bevy_reflect")]
+use bevy_ecs::reflect::ReflectComponent;
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::{std_traits::ReflectDefault, Reflect, ReflectDeserialize, ReflectSerialize};
 use std::{
     borrow::Cow,
     hash::{Hash, Hasher},
@@ -10,6 +14,11 @@ use std::{
 /// The hash is eagerly re-computed upon each update to the name.
 /// [`Name`] should not be treated as a globally unique identifier for entities.
 #[derive(Component, Debug, Clone)]
+#[cfg_attr(
+    feature = "bevy_reflect",
+    derive(Reflect),
+    reflect_value(Component, Default, Hash, PartialEq, Serialize, Deserialize)
+)]
 pub struct Name {
     hash: u64, // TODO: Shouldn't be serialized
     name: Cow<'static, str>,
@@ -126,4 +135,17 @@ impl Deref for Name {
     fn deref(&self) -> &Self::Target {
         self.name.as_ref()
     }
-}
\ No newline at end of file
+}
+/// Serialized as its string, the hash is computed again when deserializing.
+#[cfg(feature = "bevy_reflect")]
+impl serde::Serialize for Name {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+#[cfg(feature = "bevy_reflect")]
+impl<'de> serde::Deserialize<'de> for Name {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Name::new)
+    }
+}
@@ -0,0 +1,127 @@
+//! Handles and the storage of loaded assets.
+use std::{
+    any::TypeId,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use bevy_ecs::{component::Component, component::TableStorage, system::Resource};
+
+/// Types that can be stored in [`Assets`].
+pub trait Asset: Send + Sync + 'static {}
+impl<T: Send + Sync + 'static> Asset for T {}
+
+/// Identifies an asset, the same path always gets the same id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HandleId(u64);
+impl HandleId {
+    /// Ids of assets added at runtime have this bit set, ids of loaded assets don't.
+    const RUNTIME_BIT: u64 = 1 << 63;
+
+    pub(crate) fn from_path<T: Asset>(path: &Path) -> Self {
+        let mut hasher = DefaultHasher::new();
+        TypeId::of::<T>().hash(&mut hasher);
+        path.hash(&mut hasher);
+        HandleId(hasher.finish() & !Self::RUNTIME_BIT)
+    }
+    fn unique() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        HandleId(NEXT.fetch_add(1, Ordering::Relaxed) | Self::RUNTIME_BIT)
+    }
+}
+
+/// Refers to an asset of type `T` in [`Assets<T>`], which may still be loading.
+///
+/// Handles are plain ids: cheap to copy and store in components, they don't keep the asset alive.
+pub struct Handle<T: Asset> {
+    id: HandleId,
+    marker: PhantomData<fn() -> T>,
+}
+impl<T: Asset> Handle<T> {
+    pub(crate) fn new(id: HandleId) -> Self {
+        Self {
+            id,
+            marker: PhantomData,
+        }
+    }
+    pub fn id(&self) -> HandleId {
+        self.id
+    }
+}
+impl<T: Asset> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T: Asset> Copy for Handle<T> {}
+impl<T: Asset> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+impl<T: Asset> Eq for Handle<T> {}
+impl<T: Asset> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+impl<T: Asset> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle<{}>({:?})", std::any::type_name::<T>(), self.id)
+    }
+}
+impl<T: Asset> Component for Handle<T> {
+    type Storage = TableStorage;
+}
+
+/// The loaded assets of type `T`, added by [`AssetApp::add_asset`](crate::AssetApp::add_asset).
+#[derive(Resource)]
+pub struct Assets<T: Asset> {
+    assets: HashMap<HandleId, T>,
+}
+impl<T: Asset> Default for Assets<T> {
+    fn default() -> Self {
+        Self {
+            assets: HashMap::new(),
+        }
+    }
+}
+impl<T: Asset> Assets<T> {
+    /// Adds an asset that wasn't loaded from a file.
+    pub fn add(&mut self, asset: T) -> Handle<T> {
+        let handle = Handle::new(HandleId::unique());
+        self.assets.insert(handle.id, asset);
+        handle
+    }
+    /// Replaces the asset of `handle`, returns the previous one.
+    pub fn set(&mut self, handle: Handle<T>, asset: T) -> Option<T> {
+        self.assets.insert(handle.id, asset)
+    }
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.assets.get(&handle.id)
+    }
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.assets.get_mut(&handle.id)
+    }
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.assets.contains_key(&handle.id)
+    }
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        self.assets.remove(&handle.id)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.assets
+            .iter()
+            .map(|(id, asset)| (Handle::new(*id), asset))
+    }
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+}
@@ -0,0 +1,46 @@
+//! Turning file contents into assets.
+use std::{
+    any::{Any, TypeId},
+    path::Path,
+};
+
+use crate::handle::Asset;
+
+/// Loads the files with one of its [`extensions`](Self::extensions), on a background thread.
+///
+/// ```ignore
+/// struct TextLoader;
+/// impl AssetLoader for TextLoader {
+///     type Asset = String;
+///     fn extensions(&self) -> &[&str] {
+///         &["txt"]
+///     }
+///     fn load(&self, bytes: &[u8], _path: &Path) -> anyhow::Result<String> {
+///         Ok(String::from_utf8(bytes.to_vec())?)
+///     }
+/// }
+/// app.add_asset::<String>().add_asset_loader(TextLoader);
+/// ```
+pub trait AssetLoader: Send + Sync + 'static {
+    type Asset: Asset;
+    /// File extensions without the dot, matched case-insensitively.
+    fn extensions(&self) -> &[&str];
+    /// `path` is relative to the asset root, for error messages and finding neighbouring files.
+    fn load(&self, bytes: &[u8], path: &Path) -> anyhow::Result<Self::Asset>;
+}
+
+pub(crate) type BoxedAsset = Box<dyn Any + Send + Sync>;
+
+/// [`AssetLoader`] without its asset type, so loaders of all types fit in one map.
+pub(crate) trait ErasedLoader: Send + Sync + 'static {
+    fn load(&self, bytes: &[u8], path: &Path) -> anyhow::Result<BoxedAsset>;
+    fn asset_type(&self) -> TypeId;
+}
+impl<L: AssetLoader> ErasedLoader for L {
+    fn load(&self, bytes: &[u8], path: &Path) -> anyhow::Result<BoxedAsset> {
+        Ok(Box::new(AssetLoader::load(self, bytes, path)?))
+    }
+    fn asset_type(&self) -> TypeId {
+        TypeId::of::<L::Asset>()
+    }
+}
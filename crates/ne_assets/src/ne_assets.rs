@@ -0,0 +1,157 @@
+//! Loads assets by path on background threads and hot reloads them when their files change.
+//!
+//! ```ignore
+//! app.add_plugin(AssetPlugin)
+//!     .add_asset_loader(TextLoader);
+//!
+//! fn setup(asset_server: Res<AssetServer>) {
+//!     let notes: Handle<String> = asset_server.load("notes.txt");
+//! }
+//! fn print(mut loaded: EventReader<OnAssetLoaded<String>>, texts: Res<Assets<String>>) {
+//!     for event in loaded.iter() {
+//!         info!("{}", texts.get(event.handle).unwrap());
+//!     }
+//! }
+//! ```
+pub mod handle;
+pub mod loader;
+pub mod server;
+
+use std::{any::TypeId, path::PathBuf};
+
+use bevy_ecs::{
+    event::EventWriter,
+    schedule::{IntoSystemDescriptor, SystemLabel},
+    system::{Res, ResMut, Resource},
+};
+use ne_app::{App, CoreStage, Plugin};
+use ne_files::{AssetRoots, Vfs};
+
+pub use handle::{Asset, Assets, Handle, HandleId};
+pub use loader::AssetLoader;
+pub use server::{AssetServer, LoadState};
+
+/// Adds the [`AssetServer`], configured by the [`AssetSettings`] inserted before the plugin.
+pub struct AssetPlugin;
+impl Plugin for AssetPlugin {
+    fn setup(&self, app: &mut App) {
+        app.init_resource::<AssetSettings>();
+        let settings = app.world.resource::<AssetSettings>();
+        let server = AssetServer::new(Vfs::from_roots(&settings.roots));
+        if settings.watch_for_changes {
+            if let Err(error) = server.watch_for_changes() {
+                tracing::warn!("can't watch the asset roots for changes: {}", error);
+            }
+        }
+        app.insert_resource(server)
+            .add_event::<OnAssetLoadFailed>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                asset_server_system.label(AssetSystem::Receive),
+            );
+    }
+}
+
+/// The systems of [`AssetPlugin`] in the order they run in [`CoreStage::PreUpdate`].
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum AssetSystem {
+    /// Starts reloading changed files and collects finished loads.
+    Receive,
+    /// Moves loaded assets into their [`Assets<T>`].
+    Update,
+}
+
+/// Configures the [`AssetServer`], read once when [`AssetPlugin`] is added.
+#[derive(Debug, Clone, Resource)]
+pub struct AssetSettings {
+    /// Directories or archives asset paths are relative to, mounted with [`Vfs::from_roots`].
+    pub roots: AssetRoots,
+    /// Reloads assets when their files change, on by default in debug builds.
+    pub watch_for_changes: bool,
+}
+impl Default for AssetSettings {
+    fn default() -> Self {
+        Self {
+            roots: AssetRoots::discover(),
+            watch_for_changes: cfg!(debug_assertions),
+        }
+    }
+}
+
+/// Sent when an asset was loaded into [`Assets<T>`], or replaced by a reload.
+pub struct OnAssetLoaded<T: Asset> {
+    pub handle: Handle<T>,
+    pub reloaded: bool,
+}
+
+/// Sent when a file couldn't be read or its loader failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnAssetLoadFailed {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Registers asset types and loaders on an [`App`] with the [`AssetPlugin`].
+pub trait AssetApp {
+    /// Adds [`Assets<T>`] and [`OnAssetLoaded<T>`], does nothing if they were added before.
+    fn add_asset<T: Asset>(&mut self) -> &mut Self;
+    /// Adds `loader` to the [`AssetServer`] and the asset type it loads.
+    fn add_asset_loader<L: AssetLoader>(&mut self, loader: L) -> &mut Self;
+}
+impl AssetApp for App {
+    fn add_asset<T: Asset>(&mut self) -> &mut Self {
+        if self.world.contains_resource::<Assets<T>>() {
+            return self;
+        }
+        self.init_resource::<Assets<T>>()
+            .add_event::<OnAssetLoaded<T>>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_assets_system::<T>
+                    .label(AssetSystem::Update)
+                    .after(AssetSystem::Receive),
+            )
+    }
+    fn add_asset_loader<L: AssetLoader>(&mut self, loader: L) -> &mut Self {
+        self.world
+            .get_resource::<AssetServer>()
+            .expect("add the AssetPlugin before adding asset loaders")
+            .add_loader(loader);
+        self.add_asset::<L::Asset>()
+    }
+}
+
+/// Reloads changed files and reports the loads that failed.
+pub fn asset_server_system(
+    server: Res<AssetServer>,
+    mut failed_events: EventWriter<OnAssetLoadFailed>,
+) {
+    server.process_changes();
+    for result in server.receive_loaded() {
+        let error = result.asset.err().unwrap_or_default();
+        tracing::error!("failed to load {}: {}", result.path.display(), error);
+        failed_events.send(OnAssetLoadFailed {
+            path: result.path,
+            error,
+        });
+    }
+}
+
+/// Moves the loaded assets of type `T` into [`Assets<T>`].
+pub fn update_assets_system<T: Asset>(
+    server: Res<AssetServer>,
+    mut assets: ResMut<Assets<T>>,
+    mut loaded_events: EventWriter<OnAssetLoaded<T>>,
+) {
+    for result in server.take_loaded(TypeId::of::<T>()) {
+        let Ok(Ok(asset)) = result.asset.map(|asset| asset.downcast::<T>()) else {
+            continue;
+        };
+        let handle = Handle::new(result.id);
+        assets.set(handle, *asset);
+        loaded_events.send(OnAssetLoaded {
+            handle,
+            reloaded: result.reload,
+        });
+    }
+}
@@ -0,0 +1,366 @@
+//! Loading assets on background threads and reloading them when their files change.
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
+
+use bevy_ecs::system::Resource;
+use bevy_tasks::{IoTaskPool, TaskPoolBuilder};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use ne_files::Vfs;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    handle::{Asset, Handle, HandleId},
+    loader::{AssetLoader, BoxedAsset, ErasedLoader},
+};
+
+/// Where an asset is in the loading process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadState {
+    /// [`AssetServer::load`] was never called for this handle.
+    NotLoaded,
+    Loading,
+    Loaded,
+    /// Loading failed, an [`OnAssetLoadFailed`](crate::OnAssetLoadFailed) was sent.
+    Failed,
+}
+
+struct AssetInfo {
+    path: PathBuf,
+    asset_type: TypeId,
+    state: LoadState,
+}
+
+/// A finished load, sent from the loading threads.
+pub(crate) struct LoadResult {
+    pub id: HandleId,
+    pub asset_type: TypeId,
+    pub path: PathBuf,
+    pub reload: bool,
+    pub asset: Result<BoxedAsset, String>,
+}
+
+struct Watch {
+    roots: Vec<PathBuf>,
+    // Dropping the watcher stops watching.
+    _watcher: RecommendedWatcher,
+    changes: Receiver<notify::Result<notify::Event>>,
+}
+
+/// Loads assets by path on the IO thread pool and returns handles to them right away.
+///
+/// The assets show up in [`Assets<T>`](crate::Assets) a few frames later, followed by an
+/// [`OnAssetLoaded<T>`](crate::OnAssetLoaded) event.
+/// ```ignore
+/// fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+///     let model: Handle<Model> = asset_server.load("3D/double_cube.obj");
+///     commands.spawn(model);
+/// }
+/// ```
+/// Cloning the server is cheap, clones share their state.
+#[derive(Clone, Resource)]
+pub struct AssetServer {
+    inner: Arc<ServerInner>,
+}
+struct ServerInner {
+    vfs: Arc<Vfs>,
+    loaders: RwLock<HashMap<String, Vec<Arc<dyn ErasedLoader>>>>,
+    /// Source extension to the extension its cooked files have on top.
+    cooked_extensions: RwLock<HashMap<String, String>>,
+    infos: RwLock<HashMap<HandleId, AssetInfo>>,
+    sender: Sender<LoadResult>,
+    receiver: Receiver<LoadResult>,
+    /// Loaded assets waiting to be moved into their [`Assets<T>`](crate::Assets).
+    loaded: Mutex<HashMap<TypeId, Vec<LoadResult>>>,
+    watch: Mutex<Option<Watch>>,
+}
+impl AssetServer {
+    /// Loads assets from the directories and archives mounted in `vfs`.
+    pub fn new(vfs: Vfs) -> Self {
+        IoTaskPool::init(|| {
+            TaskPoolBuilder::new()
+                .thread_name("Asset IO".to_string())
+                .build()
+        });
+        let (sender, receiver) = unbounded();
+        Self {
+            inner: Arc::new(ServerInner {
+                vfs: Arc::new(vfs),
+                loaders: RwLock::new(HashMap::new()),
+                cooked_extensions: RwLock::new(HashMap::new()),
+                infos: RwLock::new(HashMap::new()),
+                sender,
+                receiver,
+                loaded: Mutex::new(HashMap::new()),
+                watch: Mutex::new(None),
+            }),
+        }
+    }
+    pub fn vfs(&self) -> &Vfs {
+        &self.inner.vfs
+    }
+    /// Prefer [`AssetApp::add_asset_loader`](crate::AssetApp::add_asset_loader), which also
+    /// adds the [`Assets<T>`](crate::Assets) the loader's assets go into.
+    pub fn add_loader<L: AssetLoader>(&self, loader: L) {
+        let extensions: Vec<String> = loader
+            .extensions()
+            .iter()
+            .map(|extension| extension.to_lowercase())
+            .collect();
+        let loader: Arc<dyn ErasedLoader> = Arc::new(loader);
+        let mut loaders = self.inner.loaders.write().unwrap();
+        for extension in extensions {
+            loaders.entry(extension).or_default().push(loader.clone());
+        }
+    }
+
+    /// Loads `<path>.<cooked>` instead of a `path` ending in `.<source>` if the vfs has it,
+    /// so cooked files replace their sources without changing the paths passed to
+    /// [`load`](Self::load).
+    pub fn add_cooked_extension(&self, source: &str, cooked: &str) {
+        self.inner
+            .cooked_extensions
+            .write()
+            .unwrap()
+            .insert(source.to_lowercase(), cooked.to_lowercase());
+    }
+
+    /// Starts loading the asset at `path`, relative to the mounts of the [`vfs`](Self::vfs).
+    ///
+    /// Loading a path again returns the same handle without loading it twice, unless the
+    /// previous load failed. Cooked files are loaded in place of their sources, see
+    /// [`add_cooked_extension`](Self::add_cooked_extension).
+    pub fn load<T: Asset>(&self, path: impl AsRef<Path>) -> Handle<T> {
+        let path = self.cooked_path(normalize(path.as_ref()));
+        let id = HandleId::from_path::<T>(&path);
+        {
+            let mut infos = self.inner.infos.write().unwrap();
+            match infos.get(&id) {
+                Some(info) if info.state != LoadState::Failed => return Handle::new(id),
+                _ => {}
+            }
+            infos.insert(
+                id,
+                AssetInfo {
+                    path: path.clone(),
+                    asset_type: TypeId::of::<T>(),
+                    state: LoadState::Loading,
+                },
+            );
+        }
+        self.start_load(id, TypeId::of::<T>(), path, false);
+        Handle::new(id)
+    }
+    /// Loads the asset of `handle` again, the old asset stays in place until the new one is loaded.
+    pub fn reload<T: Asset>(&self, handle: Handle<T>) {
+        let info = self
+            .inner
+            .infos
+            .read()
+            .unwrap()
+            .get(&handle.id())
+            .map(|info| info.path.clone());
+        if let Some(path) = info {
+            self.start_load(handle.id(), TypeId::of::<T>(), path, true);
+        }
+    }
+    pub fn load_state<T: Asset>(&self, handle: Handle<T>) -> LoadState {
+        self.inner
+            .infos
+            .read()
+            .unwrap()
+            .get(&handle.id())
+            .map_or(LoadState::NotLoaded, |info| info.state)
+    }
+    /// The path `handle` was loaded from, the cooked one if it was cooked. `None` for assets
+    /// added at runtime.
+    pub fn path<T: Asset>(&self, handle: Handle<T>) -> Option<PathBuf> {
+        self.inner
+            .infos
+            .read()
+            .unwrap()
+            .get(&handle.id())
+            .map(|info| info.path.clone())
+    }
+
+    /// Reloads loaded assets when their files change, until the server is dropped.
+    /// Only files in loose directories are watched, not the ones in archives.
+    pub fn watch_for_changes(&self) -> notify::Result<()> {
+        let (sender, changes) = unbounded();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        let mut roots = Vec::new();
+        for root in self.inner.vfs.dirs() {
+            let root = std::fs::canonicalize(root)?;
+            watcher.watch(&root, RecursiveMode::Recursive)?;
+            roots.push(root);
+        }
+        *self.inner.watch.lock().unwrap() = Some(Watch {
+            roots,
+            _watcher: watcher,
+            changes,
+        });
+        Ok(())
+    }
+
+    /// `path` with the cooked extension added if that file exists.
+    fn cooked_path(&self, path: PathBuf) -> PathBuf {
+        let cooked = path.extension().and_then(|extension| {
+            let extension = extension.to_string_lossy().to_lowercase();
+            self.inner
+                .cooked_extensions
+                .read()
+                .unwrap()
+                .get(&extension)
+                .cloned()
+        });
+        if let Some(cooked) = cooked {
+            let mut cooked_path = path.clone().into_os_string();
+            cooked_path.push(".");
+            cooked_path.push(cooked);
+            let cooked_path = PathBuf::from(cooked_path);
+            if self.inner.vfs.contains(&cooked_path) {
+                return cooked_path;
+            }
+        }
+        path
+    }
+
+    fn start_load(&self, id: HandleId, asset_type: TypeId, path: PathBuf, reload: bool) {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let loader = self
+            .inner
+            .loaders
+            .read()
+            .unwrap()
+            .get(&extension)
+            .and_then(|loaders| {
+                loaders
+                    .iter()
+                    .find(|loader| loader.asset_type() == asset_type)
+                    .cloned()
+            });
+        let sender = self.inner.sender.clone();
+        let Some(loader) = loader else {
+            let _ = sender.send(LoadResult {
+                id,
+                asset_type,
+                asset: Err(format!("no loader for '.{}' files of this type", extension)),
+                path,
+                reload,
+            });
+            return;
+        };
+        let vfs = self.inner.vfs.clone();
+        IoTaskPool::get()
+            .spawn(async move {
+                let asset = vfs
+                    .read(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|bytes| loader.load(&bytes, &path))
+                    .map_err(|error| format!("{:#}", error));
+                let _ = sender.send(LoadResult {
+                    id,
+                    asset_type,
+                    path,
+                    reload,
+                    asset,
+                });
+            })
+            .detach();
+    }
+
+    /// Reloads the assets whose files changed since the last call.
+    pub(crate) fn process_changes(&self) {
+        let mut changed = HashSet::new();
+        if let Some(watch) = &*self.inner.watch.lock().unwrap() {
+            for event in watch.changes.try_iter() {
+                match event {
+                    Ok(event)
+                        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) =>
+                    {
+                        changed.extend(
+                            event
+                                .paths
+                                .iter()
+                                .filter_map(|path| {
+                                    watch
+                                        .roots
+                                        .iter()
+                                        .find_map(|root| path.strip_prefix(root).ok())
+                                })
+                                .map(normalize),
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(error) => tracing::warn!("asset watcher: {}", error),
+                }
+            }
+        }
+        if changed.is_empty() {
+            return;
+        }
+        let reloads: Vec<_> = self
+            .inner
+            .infos
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, info)| info.state != LoadState::Loading && changed.contains(&info.path))
+            .map(|(id, info)| (*id, info.asset_type, info.path.clone()))
+            .collect();
+        for (id, asset_type, path) in reloads {
+            tracing::info!("reloading {}", path.display());
+            self.start_load(id, asset_type, path, true);
+        }
+    }
+    /// Sorts the finished loads by asset type and returns the failed ones.
+    pub(crate) fn receive_loaded(&self) -> Vec<LoadResult> {
+        let mut failed = Vec::new();
+        let mut loaded = self.inner.loaded.lock().unwrap();
+        for result in self.inner.receiver.try_iter() {
+            if result.asset.is_ok() {
+                loaded.entry(result.asset_type).or_default().push(result);
+                continue;
+            }
+            // A failed reload keeps the asset that was loaded before.
+            if !result.reload {
+                if let Some(info) = self.inner.infos.write().unwrap().get_mut(&result.id) {
+                    info.state = LoadState::Failed;
+                }
+            }
+            failed.push(result);
+        }
+        failed
+    }
+    pub(crate) fn take_loaded(&self, asset_type: TypeId) -> Vec<LoadResult> {
+        let loaded = self
+            .inner
+            .loaded
+            .lock()
+            .unwrap()
+            .remove(&asset_type)
+            .unwrap_or_default();
+        if !loaded.is_empty() {
+            let mut infos = self.inner.infos.write().unwrap();
+            for result in &loaded {
+                if let Some(info) = infos.get_mut(&result.id) {
+                    info.state = LoadState::Loaded;
+                }
+            }
+        }
+        loaded
+    }
+}
+
+/// Drops `.` components so `./a.png` and `a.png` are the same asset.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
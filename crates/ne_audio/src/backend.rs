@@ -0,0 +1,96 @@
+//! Where mixed audio goes.
+use std::{fs::File, io::BufWriter, path::Path};
+
+use crate::source::AudioError;
+
+/// Receives the mixed audio as interleaved stereo `f32` samples.
+///
+/// Implement this to play audio through a sound card, the engine ships [`NullBackend`] for
+/// headless runs and [`WavFileBackend`] to record what would be heard.
+pub trait AudioBackend: Send + Sync + 'static {
+    /// The rate the mixer renders at.
+    fn sample_rate(&self) -> u32;
+    fn write(&mut self, samples: &[f32]);
+}
+
+/// Discards all audio, only counting how much was written.
+#[derive(Debug, Clone)]
+pub struct NullBackend {
+    sample_rate: u32,
+    frames_written: u64,
+}
+impl NullBackend {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            frames_written: 0,
+        }
+    }
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+}
+impl Default for NullBackend {
+    fn default() -> Self {
+        Self::new(48000)
+    }
+}
+impl AudioBackend for NullBackend {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    fn write(&mut self, samples: &[f32]) {
+        self.frames_written += samples.len() as u64 / 2;
+    }
+}
+
+/// Writes all audio into a 16 bit stereo wav file, finished when the backend is dropped.
+pub struct WavFileBackend {
+    sample_rate: u32,
+    writer: Option<hound::WavWriter<BufWriter<File>>>,
+}
+impl WavFileBackend {
+    pub fn create(path: impl AsRef<Path>, sample_rate: u32) -> Result<Self, AudioError> {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        Ok(Self {
+            sample_rate,
+            writer: Some(hound::WavWriter::create(path, spec)?),
+        })
+    }
+    /// Finishes the file now instead of when the backend is dropped.
+    pub fn finalize(&mut self) -> Result<(), AudioError> {
+        if let Some(writer) = self.writer.take() {
+            writer.finalize()?;
+        }
+        Ok(())
+    }
+}
+impl AudioBackend for WavFileBackend {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    fn write(&mut self, samples: &[f32]) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        for sample in samples {
+            if let Err(error) = writer.write_sample((sample * i16::MAX as f32) as i16) {
+                tracing::error!("failed to write audio: {}", error);
+                self.writer = None;
+                return;
+            }
+        }
+    }
+}
+impl Drop for WavFileBackend {
+    fn drop(&mut self) {
+        if let Err(error) = self.finalize() {
+            tracing::error!("failed to finish wav file: {}", error);
+        }
+    }
+}
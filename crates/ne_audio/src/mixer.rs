@@ -0,0 +1,215 @@
+//! Volume buses and the software mixer that renders playing voices into stereo frames.
+use std::collections::HashMap;
+
+use bevy_ecs::{entity::Entity, system::Resource};
+use bevy_reflect::Reflect;
+
+use crate::source::AudioSource;
+
+/// A volume bus of the [`AudioMixer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct BusId(usize);
+impl BusId {
+    /// Every other bus feeds into the master bus.
+    pub const MASTER: BusId = BusId(0);
+}
+impl Default for BusId {
+    fn default() -> Self {
+        BusId::MASTER
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Bus {
+    name: String,
+    parent: Option<BusId>,
+    volume: f32,
+    muted: bool,
+}
+
+/// The volume buses sounds play through, e.g. `music` and `sfx` under `master`, and the voices
+/// of the [`AudioEmitter`](crate::AudioEmitter)s currently playing.
+///
+/// ```ignore
+/// let mut mixer = app.world.resource_mut::<AudioMixer>();
+/// let music = mixer.add_bus("music", BusId::MASTER);
+/// mixer.set_volume(music, 0.5);
+/// ```
+#[derive(Resource)]
+pub struct AudioMixer {
+    buses: Vec<Bus>,
+    pub(crate) voices: HashMap<Entity, Voice>,
+}
+impl Default for AudioMixer {
+    fn default() -> Self {
+        Self {
+            buses: vec![Bus {
+                name: "master".to_string(),
+                parent: None,
+                volume: 1.0,
+                muted: false,
+            }],
+            voices: HashMap::new(),
+        }
+    }
+}
+impl AudioMixer {
+    /// Adds a bus that feeds into `parent`, or returns the bus already named `name`.
+    pub fn add_bus(&mut self, name: &str, parent: BusId) -> BusId {
+        if let Some(bus) = self.bus(name) {
+            return bus;
+        }
+        self.buses.push(Bus {
+            name: name.to_string(),
+            parent: Some(parent),
+            volume: 1.0,
+            muted: false,
+        });
+        BusId(self.buses.len() - 1)
+    }
+    /// Looks up a bus by name.
+    pub fn bus(&self, name: &str) -> Option<BusId> {
+        self.buses
+            .iter()
+            .position(|bus| bus.name == name)
+            .map(BusId)
+    }
+    pub fn bus_name(&self, bus: BusId) -> &str {
+        &self.buses[bus.0].name
+    }
+    pub fn volume(&self, bus: BusId) -> f32 {
+        self.buses[bus.0].volume
+    }
+    pub fn set_volume(&mut self, bus: BusId, volume: f32) {
+        self.buses[bus.0].volume = volume.max(0.0);
+    }
+    pub fn is_muted(&self, bus: BusId) -> bool {
+        self.buses[bus.0].muted
+    }
+    pub fn set_muted(&mut self, bus: BusId, muted: bool) {
+        self.buses[bus.0].muted = muted;
+    }
+    /// The volume of `bus` multiplied by the volumes of all buses it feeds into.
+    pub fn effective_volume(&self, bus: BusId) -> f32 {
+        let mut volume = 1.0;
+        let mut current = Some(bus);
+        while let Some(id) = current {
+            let bus = &self.buses[id.0];
+            if bus.muted {
+                return 0.0;
+            }
+            volume *= bus.volume;
+            current = bus.parent;
+        }
+        volume
+    }
+    /// Number of voices currently playing.
+    pub fn playing_voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Mixes `frames` stereo frames at `sample_rate` into `output` and returns the entities whose
+    /// voices reached the end of their sound.
+    pub(crate) fn mix(
+        &mut self,
+        output: &mut Vec<f32>,
+        frames: usize,
+        sample_rate: u32,
+    ) -> Vec<Entity> {
+        output.clear();
+        output.resize(frames * 2, 0.0);
+        let bus_gains: Vec<f32> = (0..self.buses.len())
+            .map(|bus| self.effective_volume(BusId(bus)))
+            .collect();
+        let mut finished = Vec::new();
+        for (entity, voice) in &mut self.voices {
+            let bus_gain = bus_gains.get(voice.bus.0).copied().unwrap_or(0.0);
+            if voice.render(output, frames, sample_rate, bus_gain) {
+                finished.push(*entity);
+            }
+        }
+        for entity in &finished {
+            self.voices.remove(entity);
+        }
+        for sample in output.iter_mut() {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+        finished
+    }
+}
+
+/// A sound being played by the mixer.
+pub(crate) struct Voice {
+    pub source: AudioSource,
+    pub bus: BusId,
+    pub looping: bool,
+    pub speed: f32,
+    /// Mixes the sound down to mono before panning it.
+    pub spatial: bool,
+    /// Left and right gain the voice should reach by the end of the next block.
+    pub target_gain: (f32, f32),
+    gain: Option<(f32, f32)>,
+    /// Position in source frames.
+    position: f64,
+}
+impl Voice {
+    pub fn new(source: AudioSource) -> Self {
+        Self {
+            source,
+            bus: BusId::MASTER,
+            looping: false,
+            speed: 1.0,
+            spatial: false,
+            target_gain: (1.0, 1.0),
+            gain: None,
+            position: 0.0,
+        }
+    }
+
+    /// Adds the voice to `output`, returns whether it finished.
+    fn render(
+        &mut self,
+        output: &mut [f32],
+        frames: usize,
+        sample_rate: u32,
+        bus_gain: f32,
+    ) -> bool {
+        let source_frames = self.source.frames();
+        let step =
+            self.source.sample_rate() as f64 / sample_rate as f64 * self.speed.max(0.0) as f64;
+        let target = (self.target_gain.0 * bus_gain, self.target_gain.1 * bus_gain);
+        // A new voice starts at its gain, afterwards gains ramp over the block to avoid clicks.
+        let start = self.gain.unwrap_or(target);
+        self.gain = Some(target);
+        for frame in 0..frames {
+            if self.position >= source_frames as f64 {
+                if !self.looping {
+                    return true;
+                }
+                self.position %= source_frames as f64;
+            }
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            let next = if index + 1 < source_frames {
+                index + 1
+            } else if self.looping {
+                0
+            } else {
+                index
+            };
+            let (left_a, right_a) = self.source.stereo_frame(index);
+            let (left_b, right_b) = self.source.stereo_frame(next);
+            let mut left = left_a + (left_b - left_a) * fraction;
+            let mut right = right_a + (right_b - right_a) * fraction;
+            if self.spatial {
+                left = (left + right) * 0.5;
+                right = left;
+            }
+            let t = (frame + 1) as f32 / frames as f32;
+            output[frame * 2] += left * (start.0 + (target.0 - start.0) * t);
+            output[frame * 2 + 1] += right * (start.1 + (target.1 - start.1) * t);
+            self.position += step;
+        }
+        !self.looping && self.position >= source_frames as f64
+    }
+}
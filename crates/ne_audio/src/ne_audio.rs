@@ -0,0 +1,433 @@
+//! Sound playback with spatial emitters, volume buses and a software mixer.
+//!
+//! Entities with an [`AudioEmitter`] play an [`AudioSource`] through a bus of the
+//! [`AudioMixer`]. Spatial emitters are attenuated and panned by their [`Transform`] relative to
+//! the entity with the [`AudioListener`], usually the camera or the player.
+//! ```ignore
+//! app.add_plugin(AudioPlugin);
+//! let source = AudioSource::load("engine_assets/sounds/hum.ogg")?;
+//! app.world.spawn((Transform::default(), AudioEmitter::new(source).spatial(1.0, 30.0)));
+//! app.world.spawn((Transform::default(), AudioListener));
+//! ```
+//! The mixed audio is written to the [`AudioBackend`] of [`AudioOutput`], without one inserted
+//! before the plugin audio goes to a [`NullBackend`], so apps and tests run fine headless.
+pub mod backend;
+pub mod mixer;
+pub mod source;
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    event::EventWriter,
+    query::With,
+    reflect::ReflectComponent,
+    schedule::{IntoSystemDescriptor, SystemLabel},
+    system::{Query, RemovedComponents, Res, ResMut, Resource},
+};
+use bevy_reflect::{FromReflect, Reflect};
+use ne_app::{
+    plugin_group::plugin_name,
+    time::{Time, TimePlugin},
+    App, CoreStage, Plugin,
+};
+use ne_math::{Transform, Vec3};
+
+pub use backend::{AudioBackend, NullBackend, WavFileBackend};
+pub use mixer::{AudioMixer, BusId};
+pub use source::{AudioError, AudioSource};
+
+use mixer::Voice;
+
+/// Adds the [`AudioMixer`] and mixes the playing [`AudioEmitter`]s into the [`AudioOutput`]
+/// every frame in [`CoreStage::PostUpdate`].
+pub struct AudioPlugin;
+impl Plugin for AudioPlugin {
+    fn setup(&self, app: &mut App) {
+        if !app.world.contains_resource::<AudioOutput>() {
+            app.insert_resource(AudioOutput::new(NullBackend::default()));
+        }
+        app.register_type::<AudioEmitter>()
+            .register_type::<AudioListener>()
+            .register_type::<BusId>()
+            .register_type::<Attenuation>()
+            .register_type::<Option<Attenuation>>()
+            .init_resource::<AudioMixer>()
+            .add_event::<OnAudioFinished>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_voices_system.label(AudioSystem::UpdateVoices),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                mix_audio_system
+                    .label(AudioSystem::Mix)
+                    .after(AudioSystem::UpdateVoices),
+            );
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![plugin_name::<TimePlugin>()]
+    }
+}
+
+/// The systems of [`AudioPlugin`] in the order they run.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum AudioSystem {
+    /// Starts and stops voices and updates their gains from the emitters and the listener.
+    UpdateVoices,
+    /// Mixes the time passed since the last frame into the [`AudioOutput`].
+    Mix,
+}
+
+/// Sent when a non-looping [`AudioEmitter`] played its sound to the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OnAudioFinished {
+    pub entity: Entity,
+}
+
+/// The backend the mixed audio is written to.
+#[derive(Resource)]
+pub struct AudioOutput {
+    backend: Box<dyn AudioBackend>,
+    buffer: Vec<f32>,
+    /// Fraction of a frame left over from the previous update.
+    remainder: f64,
+    frames_written: u64,
+}
+impl AudioOutput {
+    pub fn new(backend: impl AudioBackend) -> Self {
+        Self {
+            backend: Box::new(backend),
+            buffer: Vec::new(),
+            remainder: 0.0,
+            frames_written: 0,
+        }
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.backend.sample_rate()
+    }
+    /// Number of stereo frames written to the backend so far.
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+    /// The samples written by the last update, interleaved stereo.
+    pub fn last_samples(&self) -> &[f32] {
+        &self.buffer
+    }
+}
+
+/// Makes the entity play a sound.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct AudioEmitter {
+    /// Not reflected, an emitter spawned from a scene is silent until it gets a source.
+    #[reflect(ignore)]
+    pub source: AudioSource,
+    pub volume: f32,
+    /// Playback speed, also changes the pitch.
+    pub speed: f32,
+    pub looping: bool,
+    pub bus: BusId,
+    /// Attenuates and pans the sound by its position relative to the [`AudioListener`].
+    pub spatial: Option<Attenuation>,
+    /// Set to `false` to stop the sound, setting it back to `true` plays it from the start.
+    /// Cleared when a non-looping sound finishes.
+    pub playing: bool,
+}
+impl AudioEmitter {
+    /// Plays `source` once on the master bus.
+    pub fn new(source: AudioSource) -> Self {
+        Self {
+            source,
+            volume: 1.0,
+            speed: 1.0,
+            looping: false,
+            bus: BusId::MASTER,
+            spatial: None,
+            playing: true,
+        }
+    }
+    #[must_use]
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+    #[must_use]
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+    #[must_use]
+    pub fn looped(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+    #[must_use]
+    pub fn on_bus(mut self, bus: BusId) -> Self {
+        self.bus = bus;
+        self
+    }
+    /// Full volume up to `min_distance` from the listener, silent from `max_distance` on.
+    #[must_use]
+    pub fn spatial(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.spatial = Some(Attenuation {
+            min_distance,
+            max_distance,
+        });
+        self
+    }
+    /// Doesn't start playing until [`playing`](Self::playing) is set.
+    #[must_use]
+    pub fn paused(mut self) -> Self {
+        self.playing = false;
+        self
+    }
+}
+impl Default for AudioEmitter {
+    fn default() -> Self {
+        Self::new(AudioSource::default())
+    }
+}
+
+/// How the volume of a spatial sound falls off with distance.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, FromReflect)]
+pub struct Attenuation {
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+impl Attenuation {
+    /// Inverse distance falloff, shifted so it reaches zero at `max_distance`.
+    pub fn gain(&self, distance: f32) -> f32 {
+        let min = self.min_distance.max(f32::EPSILON);
+        let max = self.max_distance.max(min);
+        if distance <= min {
+            return 1.0;
+        }
+        if distance >= max {
+            return 0.0;
+        }
+        let floor = min / max;
+        (min / distance - floor) / (1.0 - floor)
+    }
+}
+
+/// Marks the entity spatial sounds are heard from, its rotation decides what is left and right.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+#[reflect(Component)]
+pub struct AudioListener;
+
+/// Left and right gain of a sound at `position` heard by a listener at `listener`.
+fn spatial_gain(attenuation: &Attenuation, position: Vec3, listener: &Transform) -> (f32, f32) {
+    let offset = position - listener.pos;
+    let gain = attenuation.gain(offset.length());
+    // Equal-power panning keeps the loudness constant while the sound moves around the listener.
+    let pan = offset
+        .normalize_or_zero()
+        .dot(listener.rot * Vec3::X)
+        .clamp(-1.0, 1.0);
+    let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+    let center = std::f32::consts::FRAC_1_SQRT_2;
+    (angle.cos() / center * gain, angle.sin() / center * gain)
+}
+
+/// Starts and stops voices to match the emitters and updates their gains.
+pub fn update_voices_system(
+    mut mixer: ResMut<AudioMixer>,
+    emitters: Query<(Entity, &AudioEmitter, Option<&Transform>)>,
+    listeners: Query<&Transform, With<AudioListener>>,
+    removed: RemovedComponents<AudioEmitter>,
+) {
+    for entity in removed.iter() {
+        mixer.voices.remove(&entity);
+    }
+    let listener = listeners.iter().next();
+    for (entity, emitter, transform) in &emitters {
+        if !emitter.playing {
+            mixer.voices.remove(&entity);
+            continue;
+        }
+        let voice = mixer
+            .voices
+            .entry(entity)
+            .or_insert_with(|| Voice::new(emitter.source.clone()));
+        voice.bus = emitter.bus;
+        voice.looping = emitter.looping;
+        voice.speed = emitter.speed;
+        voice.spatial = emitter.spatial.is_some();
+        let (left, right) = match (&emitter.spatial, transform, listener) {
+            (Some(attenuation), Some(transform), Some(listener)) => {
+                spatial_gain(attenuation, transform.pos, listener)
+            }
+            _ => (1.0, 1.0),
+        };
+        voice.target_gain = (left * emitter.volume, right * emitter.volume);
+    }
+}
+
+/// Mixes as many frames as real time has passed since the last frame and writes them to the
+/// backend.
+pub fn mix_audio_system(
+    time: Res<Time>,
+    mut mixer: ResMut<AudioMixer>,
+    mut output: ResMut<AudioOutput>,
+    mut emitters: Query<&mut AudioEmitter>,
+    mut finished_events: EventWriter<OnAudioFinished>,
+) {
+    let output = &mut *output;
+    let sample_rate = output.backend.sample_rate();
+    let frames = time.raw_delta().as_secs_f64() * sample_rate as f64 + output.remainder;
+    output.remainder = frames.fract();
+    let frames = frames as usize;
+    if frames == 0 {
+        return;
+    }
+    let finished = mixer.mix(&mut output.buffer, frames, sample_rate);
+    output.backend.write(&output.buffer);
+    output.frames_written += frames as u64;
+    for entity in finished {
+        if let Ok(mut emitter) = emitters.get_mut(entity) {
+            emitter.playing = false;
+        }
+        finished_events.send(OnAudioFinished { entity });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy_ecs::{
+        event::Events,
+        schedule::{Stage, SystemStage},
+        world::World,
+    };
+
+    use super::*;
+
+    /// The audio systems on a world that mixes 10 frames at 1 kHz per [`TestAudio::mix`].
+    struct TestAudio {
+        world: World,
+        stage: SystemStage,
+        now: Instant,
+    }
+    impl TestAudio {
+        fn new() -> Self {
+            let now = Instant::now();
+            let mut time = Time::new(now);
+            time.update_with_instant(now);
+            let mut world = World::new();
+            world.insert_resource(time);
+            world.insert_resource(AudioOutput::new(NullBackend::new(1000)));
+            world.init_resource::<AudioMixer>();
+            world.init_resource::<Events<OnAudioFinished>>();
+            let stage = SystemStage::parallel()
+                .with_system(update_voices_system.label(AudioSystem::UpdateVoices))
+                .with_system(mix_audio_system.after(AudioSystem::UpdateVoices));
+            Self { world, stage, now }
+        }
+        fn mix(&mut self) -> &[f32] {
+            self.now += Duration::from_millis(10);
+            self.world
+                .resource_mut::<Time>()
+                .update_with_instant(self.now);
+            self.stage.run(&mut self.world);
+            self.world.resource::<AudioOutput>().last_samples()
+        }
+    }
+
+    /// A mono sound at half amplitude lasting `frames` frames at 1 kHz.
+    fn half_amplitude(frames: usize) -> AudioSource {
+        AudioSource::from_samples(vec![0.5; frames], 1, 1000).unwrap()
+    }
+
+    fn at(x: f32) -> Transform {
+        Transform {
+            pos: Vec3::new(x, 0.0, 0.0),
+            ..Default::default()
+        }
+    }
+
+    fn assert_frames(samples: &[f32], left: f32, right: f32) {
+        assert_eq!(samples.len(), 20);
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        for frame in samples.chunks_exact(2) {
+            let matches = close(frame[0], left) && close(frame[1], right);
+            assert!(matches, "{frame:?} != [{left}, {right}]");
+        }
+    }
+
+    #[test]
+    fn attenuation_falls_off_between_min_and_max_distance() {
+        let attenuation = Attenuation {
+            min_distance: 1.0,
+            max_distance: 10.0,
+        };
+        assert_eq!(attenuation.gain(0.5), 1.0);
+        assert_eq!(attenuation.gain(1.0), 1.0);
+        assert_eq!(attenuation.gain(10.0), 0.0);
+        let near = attenuation.gain(2.0);
+        let far = attenuation.gain(5.0);
+        assert!(1.0 > near && near > far && far > 0.0);
+    }
+
+    #[test]
+    fn buses_scale_the_volume() {
+        let mut audio = TestAudio::new();
+        let sfx = {
+            let mut mixer = audio.world.resource_mut::<AudioMixer>();
+            let sfx = mixer.add_bus("sfx", BusId::MASTER);
+            mixer.set_volume(sfx, 0.5);
+            mixer.set_volume(BusId::MASTER, 0.5);
+            sfx
+        };
+        audio
+            .world
+            .spawn(AudioEmitter::new(half_amplitude(100)).on_bus(sfx));
+        assert_frames(audio.mix(), 0.125, 0.125);
+        assert_eq!(audio.world.resource::<AudioOutput>().frames_written(), 10);
+    }
+
+    #[test]
+    fn spatial_sounds_are_panned_to_the_listener_side() {
+        let mut audio = TestAudio::new();
+        audio.world.spawn((at(0.0), AudioListener));
+        let emitter = audio
+            .world
+            .spawn((
+                at(0.5),
+                AudioEmitter::new(half_amplitude(100)).spatial(1.0, 10.0),
+            ))
+            .id();
+        // Equal-power panning, all of it on the right at the full power of both sides.
+        assert_frames(audio.mix(), 0.0, 0.5 * std::f32::consts::SQRT_2);
+
+        audio.world.entity_mut(emitter).insert(at(-0.5));
+        audio.mix();
+        // The gain ramps over a block when the emitter moves.
+        assert_frames(audio.mix(), 0.5 * std::f32::consts::SQRT_2, 0.0);
+    }
+
+    #[test]
+    fn spatial_sounds_beyond_max_distance_are_silent() {
+        let mut audio = TestAudio::new();
+        audio.world.spawn((at(0.0), AudioListener));
+        audio.world.spawn((
+            at(20.0),
+            AudioEmitter::new(half_amplitude(100)).spatial(1.0, 10.0),
+        ));
+        assert_frames(audio.mix(), 0.0, 0.0);
+    }
+
+    #[test]
+    fn finished_sounds_stop_and_send_an_event() {
+        let mut audio = TestAudio::new();
+        let emitter = audio.world.spawn(AudioEmitter::new(half_amplitude(5))).id();
+        let samples = audio.mix().to_vec();
+        assert_eq!(samples[8], 0.5);
+        assert_eq!(samples[10], 0.0);
+        assert!(!audio.world.get::<AudioEmitter>(emitter).unwrap().playing);
+        let events = audio.world.resource::<Events<OnAudioFinished>>();
+        let finished: Vec<_> = events.get_reader().iter(events).copied().collect();
+        assert_eq!(finished, [OnAudioFinished { entity: emitter }]);
+        assert_eq!(audio.world.resource::<AudioMixer>().playing_voices(), 0);
+    }
+}
@@ -0,0 +1,158 @@
+//! Decoded sounds.
+use std::{fmt, io::Cursor, path::Path, sync::Arc};
+
+use lewton::{inside_ogg::OggStreamReader, samples::InterleavedSamples};
+
+/// Reasons a sound can't be loaded.
+#[derive(Debug)]
+pub enum AudioError {
+    Io(std::io::Error),
+    Wav(hound::Error),
+    Ogg(lewton::VorbisError),
+    /// The file extension isn't `wav` or `ogg`.
+    UnsupportedFormat(String),
+    /// The file decoded to nothing, or has no channels.
+    Empty,
+}
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::Io(error) => write!(f, "{}", error),
+            AudioError::Wav(error) => write!(f, "invalid wav: {}", error),
+            AudioError::Ogg(error) => write!(f, "invalid ogg: {}", error),
+            AudioError::UnsupportedFormat(extension) => {
+                write!(
+                    f,
+                    "unsupported audio format '{}', use wav or ogg",
+                    extension
+                )
+            }
+            AudioError::Empty => write!(f, "audio contains no samples"),
+        }
+    }
+}
+impl std::error::Error for AudioError {}
+impl From<std::io::Error> for AudioError {
+    fn from(error: std::io::Error) -> Self {
+        AudioError::Io(error)
+    }
+}
+impl From<hound::Error> for AudioError {
+    fn from(error: hound::Error) -> Self {
+        AudioError::Wav(error)
+    }
+}
+impl From<lewton::VorbisError> for AudioError {
+    fn from(error: lewton::VorbisError) -> Self {
+        AudioError::Ogg(error)
+    }
+}
+
+/// A decoded sound, cheap to clone since the samples are shared.
+#[derive(Clone)]
+pub struct AudioSource {
+    /// Interleaved samples in `-1.0..=1.0`.
+    samples: Arc<[f32]>,
+    channels: u16,
+    sample_rate: u32,
+}
+impl fmt::Debug for AudioSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioSource")
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .field("frames", &self.frames())
+            .finish()
+    }
+}
+impl AudioSource {
+    /// A sound from interleaved samples in `-1.0..=1.0`.
+    pub fn from_samples(
+        samples: Vec<f32>,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Result<Self, AudioError> {
+        if channels == 0 || samples.len() < channels as usize {
+            return Err(AudioError::Empty);
+        }
+        Ok(Self {
+            samples: samples.into(),
+            channels,
+            sample_rate,
+        })
+    }
+    /// Loads a `.wav` or `.ogg` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AudioError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let bytes = std::fs::read(path)?;
+        match extension.as_str() {
+            "wav" => Self::from_wav(&bytes),
+            "ogg" => Self::from_ogg(&bytes),
+            _ => Err(AudioError::UnsupportedFormat(extension)),
+        }
+    }
+    /// Decodes a wav file with integer or float samples.
+    pub fn from_wav(bytes: &[u8]) -> Result<Self, AudioError> {
+        let mut reader = hound::WavReader::new(Cursor::new(bytes))?;
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 * scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        Self::from_samples(samples, spec.channels, spec.sample_rate)
+    }
+    /// Decodes an ogg vorbis file.
+    pub fn from_ogg(bytes: &[u8]) -> Result<Self, AudioError> {
+        let mut reader = OggStreamReader::new(Cursor::new(bytes))?;
+        let mut samples = Vec::new();
+        while let Some(packet) = reader.read_dec_packet_generic::<InterleavedSamples<f32>>()? {
+            samples.extend(packet.samples);
+        }
+        Self::from_samples(
+            samples,
+            reader.ident_hdr.audio_channels as u16,
+            reader.ident_hdr.audio_sample_rate,
+        )
+    }
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// Number of samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+    pub fn duration_secs(&self) -> f32 {
+        self.frames() as f32 / self.sample_rate as f32
+    }
+    /// The left and right sample of `frame`, mono sounds play on both sides.
+    pub(crate) fn stereo_frame(&self, frame: usize) -> (f32, f32) {
+        let start = frame * self.channels as usize;
+        match self.channels {
+            1 => (self.samples[start], self.samples[start]),
+            _ => (self.samples[start], self.samples[start + 1]),
+        }
+    }
+}
+/// A single silent frame.
+impl Default for AudioSource {
+    fn default() -> Self {
+        Self {
+            samples: Arc::from([0.0]),
+            channels: 1,
+            sample_rate: 44_100,
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Drives a [`CharacterController`] with player input and places a first- or third-person
+//! camera rig on it.
+//!
+//! ```ignore
+//! app.add_plugin(PhysicsPlugin).add_plugin(CharacterInputPlugin);
+//! app.world.spawn((
+//!     Transform::default(),
+//!     RigidBody::KinematicPositionBased,
+//!     Collider::capsule(1.8, 0.3),
+//!     CharacterController::default(),
+//!     PlayerCharacter::default(),
+//!     CameraRig::first_person(1.6),
+//! ));
+//! ```
+//! The rig only computes where the camera should be, see [`CameraRig::eye`], [`CameraRig::yaw`]
+//! and [`CameraRig::pitch`], copy those into the camera that renders the scene. Characters only
+//! move and look around while the game plays.
+use std::f32::consts::FRAC_PI_2;
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    query::With,
+    reflect::ReflectComponent,
+    schedule::{IntoSystemDescriptor, SystemLabel},
+    system::{Query, Res},
+};
+use bevy_reflect::{FromReflect, Reflect};
+use ne_app::{play, plugin_group::plugin_name, App, CoreStage, Plugin};
+use ne_math::{Quat, Ray, Transform, Vec3};
+use ne_physics::{CharacterController, PhysicsPlugin, SpatialQuery, SpatialQueryFilter};
+use ne_window::{
+    action::{ActionPlugin, InputMap},
+    events::VirtualKeyCode,
+    input::{Input, InputPlugin, MouseMotion},
+};
+
+/// Moves entities with a [`PlayerCharacter`] by the [`CharacterAction`]s and turns their
+/// [`CameraRig`] with the mouse.
+///
+/// Uses the [`InputMap<CharacterAction>`] inserted before the plugin, or
+/// [`CharacterAction::default_input_map`].
+pub struct CharacterInputPlugin;
+impl Plugin for CharacterInputPlugin {
+    fn setup(&self, app: &mut App) {
+        if !app.world.contains_resource::<InputMap<CharacterAction>>() {
+            app.insert_resource(CharacterAction::default_input_map());
+        }
+        app.register_type::<PlayerCharacter>()
+            .register_type::<CameraRig>()
+            .register_type::<RigMode>()
+            .add_plugin(ActionPlugin::<CharacterAction>::default())
+            .add_system_to_stage(
+                CoreStage::Gameplay,
+                camera_rig_look_system.label(CharacterSystem::Look),
+            )
+            .add_system_to_stage(
+                CoreStage::Gameplay,
+                player_movement_system
+                    .label(CharacterSystem::Move)
+                    .after(CharacterSystem::Look),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                camera_rig_system
+                    .label(CharacterSystem::PlaceCamera)
+                    .with_run_criteria(play::gameplay_run_criteria),
+            );
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![plugin_name::<InputPlugin>(), plugin_name::<PhysicsPlugin>()]
+    }
+}
+
+/// The systems of [`CharacterInputPlugin`], a system that copies the rig into the camera
+/// should run after [`CharacterSystem::PlaceCamera`].
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum CharacterSystem {
+    Look,
+    Move,
+    PlaceCamera,
+}
+
+/// What a player can make a character do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharacterAction {
+    Forward,
+    Back,
+    Left,
+    Right,
+    Jump,
+    Sprint,
+}
+impl CharacterAction {
+    /// WASD or the arrow keys to walk, space to jump and left shift to sprint.
+    pub fn default_input_map() -> InputMap<Self> {
+        InputMap::default()
+            .with(CharacterAction::Forward, VirtualKeyCode::W)
+            .with(CharacterAction::Forward, VirtualKeyCode::Up)
+            .with(CharacterAction::Back, VirtualKeyCode::S)
+            .with(CharacterAction::Back, VirtualKeyCode::Down)
+            .with(CharacterAction::Left, VirtualKeyCode::A)
+            .with(CharacterAction::Left, VirtualKeyCode::Left)
+            .with(CharacterAction::Right, VirtualKeyCode::D)
+            .with(CharacterAction::Right, VirtualKeyCode::Right)
+            .with(CharacterAction::Jump, VirtualKeyCode::Space)
+            .with(CharacterAction::Sprint, VirtualKeyCode::LShift)
+    }
+}
+
+/// Marks the character the player controls.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct PlayerCharacter {
+    pub walk_speed: f32,
+    pub sprint_speed: f32,
+}
+impl Default for PlayerCharacter {
+    fn default() -> Self {
+        Self {
+            walk_speed: 4.0,
+            sprint_speed: 7.0,
+        }
+    }
+}
+
+/// Where the camera sits relative to the character.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, FromReflect)]
+pub enum RigMode {
+    /// Looks out of the character's eyes, `eye_height` above its center.
+    FirstPerson { eye_height: f32 },
+    /// Orbits a point `height` above the character's center at `distance`,
+    /// moving closer when a collider is in the way.
+    ThirdPerson { distance: f32, height: f32 },
+}
+
+/// A camera attached to a character.
+///
+/// `yaw` and `pitch` are in radians and follow the camera of `ne_render`: at a yaw of zero the
+/// camera looks along +x, a positive yaw turns it towards +z.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct CameraRig {
+    pub mode: RigMode,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Radians turned per unit of mouse movement.
+    pub sensitivity: f32,
+    eye: Vec3,
+}
+impl CameraRig {
+    pub fn new(mode: RigMode) -> Self {
+        Self {
+            mode,
+            yaw: -FRAC_PI_2,
+            pitch: 0.0,
+            sensitivity: 0.002,
+            eye: Vec3::ZERO,
+        }
+    }
+    pub fn first_person(eye_height: f32) -> Self {
+        Self::new(RigMode::FirstPerson { eye_height })
+    }
+    pub fn third_person(distance: f32, height: f32) -> Self {
+        Self::new(RigMode::ThirdPerson { distance, height })
+    }
+    /// Position of the camera, updated in [`CoreStage::PostUpdate`].
+    pub fn eye(&self) -> Vec3 {
+        self.eye
+    }
+    pub fn yaw(&self) -> f32 {
+        self.yaw
+    }
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+    /// The direction the camera looks in.
+    pub fn look_direction(&self) -> Vec3 {
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        Vec3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw)
+    }
+    /// The look direction flattened onto the ground, the way "forward" walks.
+    pub fn forward(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        Vec3::new(cos_yaw, 0.0, sin_yaw)
+    }
+    pub fn right(&self) -> Vec3 {
+        self.forward().cross(Vec3::Y)
+    }
+}
+/// A first-person rig at the eyes of a 1.8 units tall character.
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self::first_person(0.7)
+    }
+}
+
+/// Rotation that turns the -z axis of an entity towards `direction` around the y axis.
+fn facing(direction: Vec3) -> Quat {
+    Quat::from_rotation_y(f32::atan2(-direction.x, -direction.z))
+}
+
+/// Turns the camera rigs of player characters with the mouse.
+pub fn camera_rig_look_system(
+    mouse_motion: Res<MouseMotion>,
+    mut rigs: Query<&mut CameraRig, With<PlayerCharacter>>,
+) {
+    if mouse_motion.delta == ne_math::Vec2::ZERO {
+        return;
+    }
+    const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+    for mut rig in &mut rigs {
+        rig.yaw += mouse_motion.delta.x * rig.sensitivity;
+        rig.pitch =
+            (rig.pitch - mouse_motion.delta.y * rig.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+}
+
+/// Sets the movement of player characters from the [`CharacterAction`]s, relative to where
+/// their rig looks or, without a rig, to where the character faces.
+pub fn player_movement_system(
+    actions: Res<Input<CharacterAction>>,
+    mut players: Query<(
+        &PlayerCharacter,
+        &mut CharacterController,
+        &mut Transform,
+        Option<&CameraRig>,
+    )>,
+) {
+    for (player, mut controller, mut transform, rig) in &mut players {
+        let (forward, right) = match rig {
+            Some(rig) => (rig.forward(), rig.right()),
+            None => (transform.rot * Vec3::NEG_Z, transform.rot * Vec3::X),
+        };
+        let input = forward * actions.axis(CharacterAction::Back, CharacterAction::Forward)
+            + right * actions.axis(CharacterAction::Left, CharacterAction::Right);
+        let speed = if actions.pressed(CharacterAction::Sprint) {
+            player.sprint_speed
+        } else {
+            player.walk_speed
+        };
+        controller.movement = input.normalize_or_zero() * speed;
+        if actions.just_pressed(CharacterAction::Jump) {
+            controller.jump = true;
+        }
+
+        match rig.map(|rig| (rig.mode, rig.forward())) {
+            Some((RigMode::FirstPerson { .. }, forward)) => transform.rot = facing(forward),
+            // A third-person character turns to where it walks, the camera orbits freely.
+            Some((RigMode::ThirdPerson { .. }, _)) if input != Vec3::ZERO => {
+                transform.rot = facing(input);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Places the camera rigs on their characters.
+pub fn camera_rig_system(
+    spatial_query: SpatialQuery,
+    mut rigs: Query<(Entity, &Transform, &mut CameraRig)>,
+) {
+    for (entity, transform, mut rig) in &mut rigs {
+        rig.eye = match rig.mode {
+            RigMode::FirstPerson { eye_height } => transform.pos + Vec3::Y * eye_height,
+            RigMode::ThirdPerson { distance, height } => {
+                let pivot = transform.pos + Vec3::Y * height;
+                let ray = Ray::new(pivot, -rig.look_direction());
+                // Stay a bit in front of walls so the near plane doesn't cut into them.
+                let distance = spatial_query
+                    .cast_ray(
+                        ray,
+                        distance,
+                        &SpatialQueryFilter::new().exclude(entity).without_sensors(),
+                    )
+                    .map_or(distance, |hit| (hit.distance - 0.2).max(0.0));
+                ray.get_point(distance)
+            }
+        };
+    }
+}
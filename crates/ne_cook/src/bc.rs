@@ -0,0 +1,209 @@
+//! BC1 and BC3 block compression, a fast endpoint fit on the color bounding box that trades
+//! some quality for speed.
+use crate::texture::TextureFormat;
+
+type Block = [[u8; 4]; 16];
+
+/// Compresses rgba8 pixels, edge blocks are padded by repeating the last row and column.
+pub(crate) fn compress(rgba: &[u8], width: u32, height: u32, format: TextureFormat) -> Vec<u8> {
+    let mut out = Vec::with_capacity(format.level_size(width, height));
+    for block_y in 0..blocks(height) {
+        for block_x in 0..blocks(width) {
+            let mut block = [[0; 4]; 16];
+            for (i, pixel) in block.iter_mut().enumerate() {
+                let x = (block_x * 4 + i as u32 % 4).min(width - 1);
+                let y = (block_y * 4 + i as u32 / 4).min(height - 1);
+                let start = ((y * width + x) * 4) as usize;
+                pixel.copy_from_slice(&rgba[start..start + 4]);
+            }
+            match format {
+                TextureFormat::Bc1 => out.extend_from_slice(&encode_color(&block)),
+                TextureFormat::Bc3 => {
+                    out.extend_from_slice(&encode_alpha(&block));
+                    out.extend_from_slice(&encode_color(&block));
+                }
+                TextureFormat::Rgba8 => unreachable!("rgba8 is not block compressed"),
+            }
+        }
+    }
+    out
+}
+
+/// Decompresses blocks into rgba8 pixels.
+pub(crate) fn decompress(data: &[u8], width: u32, height: u32, format: TextureFormat) -> Vec<u8> {
+    let block_size = format.block_size();
+    let mut rgba = vec![0; (width * height * 4) as usize];
+    for (i, block_data) in data.chunks_exact(block_size).enumerate() {
+        let block_x = i as u32 % blocks(width);
+        let block_y = i as u32 / blocks(width);
+        let block = match format {
+            TextureFormat::Bc1 => decode_color(block_data.try_into().unwrap(), true),
+            TextureFormat::Bc3 => {
+                let mut block = decode_color(block_data[8..].try_into().unwrap(), false);
+                let alpha = decode_alpha(block_data[..8].try_into().unwrap());
+                for (pixel, alpha) in block.iter_mut().zip(alpha) {
+                    pixel[3] = alpha;
+                }
+                block
+            }
+            TextureFormat::Rgba8 => unreachable!("rgba8 is not block compressed"),
+        };
+        for (i, pixel) in block.iter().enumerate() {
+            let x = block_x * 4 + i as u32 % 4;
+            let y = block_y * 4 + i as u32 / 4;
+            if x < width && y < height {
+                let start = ((y * width + x) * 4) as usize;
+                rgba[start..start + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+    rgba
+}
+
+/// Number of blocks along an edge of `size` pixels.
+pub(crate) fn blocks(size: u32) -> u32 {
+    size.div_ceil(4)
+}
+
+fn to_565(color: [u8; 4]) -> u16 {
+    let r = (color[0] as u16 * 31 + 127) / 255;
+    let g = (color[1] as u16 * 63 + 127) / 255;
+    let b = (color[2] as u16 * 31 + 127) / 255;
+    (r << 11) | (g << 5) | b
+}
+fn from_565(color: u16) -> [i32; 3] {
+    let r = (color >> 11) & 31;
+    let g = (color >> 5) & 63;
+    let b = color & 31;
+    [
+        (r * 255 / 31) as i32,
+        (g * 255 / 63) as i32,
+        (b * 255 / 31) as i32,
+    ]
+}
+fn lerp(a: [i32; 3], b: [i32; 3], weight_a: i32, weight_b: i32) -> [i32; 3] {
+    let total = weight_a + weight_b;
+    [
+        (a[0] * weight_a + b[0] * weight_b) / total,
+        (a[1] * weight_a + b[1] * weight_b) / total,
+        (a[2] * weight_a + b[2] * weight_b) / total,
+    ]
+}
+
+/// A BC1 color block in four color mode.
+fn encode_color(block: &Block) -> [u8; 8] {
+    let mut min = [255; 4];
+    let mut max = [0; 4];
+    for pixel in block {
+        for channel in 0..3 {
+            min[channel] = min[channel].min(pixel[channel]);
+            max[channel] = max[channel].max(pixel[channel]);
+        }
+    }
+    let (mut color0, mut color1) = (to_565(max), to_565(min));
+    if color0 < color1 {
+        std::mem::swap(&mut color0, &mut color1);
+    }
+    let mut indices = 0_u32;
+    // Equal endpoints would switch to three color mode, index 0 is right for every pixel then.
+    if color0 != color1 {
+        let (c0, c1) = (from_565(color0), from_565(color1));
+        let palette = [c0, c1, lerp(c0, c1, 2, 1), lerp(c0, c1, 1, 2)];
+        for (i, pixel) in block.iter().enumerate() {
+            let index = nearest(&palette, |color| {
+                (0..3)
+                    .map(|channel| (color[channel] - pixel[channel] as i32).pow(2))
+                    .sum()
+            });
+            indices |= (index as u32) << (i * 2);
+        }
+    }
+    let mut out = [0; 8];
+    out[0..2].copy_from_slice(&color0.to_le_bytes());
+    out[2..4].copy_from_slice(&color1.to_le_bytes());
+    out[4..8].copy_from_slice(&indices.to_le_bytes());
+    out
+}
+
+fn decode_color(data: &[u8; 8], three_color_mode: bool) -> Block {
+    let color0 = u16::from_le_bytes([data[0], data[1]]);
+    let color1 = u16::from_le_bytes([data[2], data[3]]);
+    let indices = u32::from_le_bytes(data[4..8].try_into().unwrap());
+    let (c0, c1) = (from_565(color0), from_565(color1));
+    let palette = if color0 > color1 || !three_color_mode {
+        [c0, c1, lerp(c0, c1, 2, 1), lerp(c0, c1, 1, 2)]
+    } else {
+        [c0, c1, lerp(c0, c1, 1, 1), [0, 0, 0]]
+    };
+    let mut block = [[0; 4]; 16];
+    for (i, pixel) in block.iter_mut().enumerate() {
+        let index = (indices >> (i * 2)) as usize & 3;
+        let color = palette[index];
+        let transparent = three_color_mode && color0 <= color1 && index == 3;
+        *pixel = [
+            color[0] as u8,
+            color[1] as u8,
+            color[2] as u8,
+            if transparent { 0 } else { 255 },
+        ];
+    }
+    block
+}
+
+fn alpha_palette(alpha0: u8, alpha1: u8) -> [i32; 8] {
+    let (a0, a1) = (alpha0 as i32, alpha1 as i32);
+    let mut palette = [a0, a1, 0, 0, 0, 0, 0, 0];
+    if alpha0 > alpha1 {
+        for (i, value) in palette[2..].iter_mut().enumerate() {
+            let i = i as i32 + 1;
+            *value = ((7 - i) * a0 + i * a1) / 7;
+        }
+    } else {
+        for (i, value) in palette[2..6].iter_mut().enumerate() {
+            let i = i as i32 + 1;
+            *value = ((5 - i) * a0 + i * a1) / 5;
+        }
+        palette[7] = 255;
+    }
+    palette
+}
+
+/// A BC4 alpha block in eight value mode.
+fn encode_alpha(block: &Block) -> [u8; 8] {
+    let alpha0 = block.iter().map(|pixel| pixel[3]).max().unwrap();
+    let alpha1 = block.iter().map(|pixel| pixel[3]).min().unwrap();
+    let palette = alpha_palette(alpha0, alpha1);
+    let mut indices = 0_u64;
+    if alpha0 != alpha1 {
+        for (i, pixel) in block.iter().enumerate() {
+            let index = nearest(&palette, |&alpha| (alpha - pixel[3] as i32).abs());
+            indices |= (index as u64) << (i * 3);
+        }
+    }
+    let mut out = [0; 8];
+    out[0] = alpha0;
+    out[1] = alpha1;
+    out[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+    out
+}
+
+fn decode_alpha(data: &[u8; 8]) -> [u8; 16] {
+    let palette = alpha_palette(data[0], data[1]);
+    let mut index_bytes = [0; 8];
+    index_bytes[..6].copy_from_slice(&data[2..8]);
+    let indices = u64::from_le_bytes(index_bytes);
+    let mut alphas = [0; 16];
+    for (i, alpha) in alphas.iter_mut().enumerate() {
+        *alpha = palette[(indices >> (i * 3)) as usize & 7] as u8;
+    }
+    alphas
+}
+
+fn nearest<T>(palette: &[T], distance: impl Fn(&T) -> i32) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, color)| distance(color))
+        .map(|(i, _)| i)
+        .unwrap()
+}
@@ -0,0 +1,89 @@
+//! Reading and writing the cooked formats. Buffers are stored in memory layout so they can be
+//! copied straight into place, which makes the formats little endian only.
+use anyhow::{bail, Result};
+use bytemuck::Pod;
+
+#[cfg(target_endian = "big")]
+compile_error!("the cooked formats store buffers as little endian");
+
+pub(crate) struct Writer {
+    bytes: Vec<u8>,
+}
+impl Writer {
+    pub fn new(magic: &[u8; 4], version: u32) -> Self {
+        let mut writer = Self { bytes: Vec::new() };
+        writer.bytes.extend_from_slice(magic);
+        writer.u32(version);
+        writer
+    }
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    pub fn f32s(&mut self, values: &[f32; 3]) {
+        self.pod_slice(values);
+    }
+    pub fn pod_slice<T: Pod>(&mut self, values: &[T]) {
+        self.bytes.extend_from_slice(bytemuck::cast_slice(values));
+    }
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> Reader<'a> {
+    /// Fails if `bytes` don't start with `magic` and `version`.
+    pub fn new(bytes: &'a [u8], magic: &[u8; 4], version: u32) -> Result<Self> {
+        let mut reader = Self { bytes };
+        if reader.take(4)? != magic {
+            bail!("not a {} file", String::from_utf8_lossy(magic));
+        }
+        let found = reader.u32()?;
+        if found != version {
+            bail!(
+                "version {} is not supported, recook with version {}",
+                found,
+                version
+            );
+        }
+        Ok(reader)
+    }
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            bail!("the file is truncated");
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    pub fn f32s(&mut self) -> Result<[f32; 3]> {
+        let mut values = [0.0; 3];
+        bytemuck::cast_slice_mut(&mut values).copy_from_slice(self.take(12)?);
+        Ok(values)
+    }
+    pub fn bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        Ok(self.take(len)?.to_vec())
+    }
+    pub fn pod_vec<T: Pod>(&mut self, count: usize) -> Result<Vec<T>> {
+        let len = count
+            .checked_mul(std::mem::size_of::<T>())
+            .filter(|&len| len <= self.bytes.len());
+        let Some(len) = len else {
+            bail!("the file is truncated");
+        };
+        let mut values = vec![T::zeroed(); count];
+        bytemuck::cast_slice_mut(&mut values).copy_from_slice(self.take(len)?);
+        Ok(values)
+    }
+}
@@ -0,0 +1,280 @@
+//! Cooks a whole asset directory, skipping the sources that didn't change since the last run.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mesh::{self, MESH_EXTENSION},
+    texture::{self, TextureSettings, TEXTURE_EXTENSION},
+};
+
+/// Bumped when a cooked format changes, so everything is cooked again.
+const COOK_VERSION: u32 = 1;
+
+/// What a source file is cooked into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    /// `.obj`, `.gltf` and `.glb` files, cooked into a `.nemesh`.
+    Mesh,
+    /// Image files, cooked into a `.netex`.
+    Texture,
+    /// Anything else, copied as it is.
+    Other,
+}
+impl AssetKind {
+    pub fn of(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "obj" | "gltf" | "glb" => AssetKind::Mesh,
+            "png" | "jpg" | "jpeg" | "bmp" | "tga" => AssetKind::Texture,
+            _ => AssetKind::Other,
+        }
+    }
+    /// Where the cooked file of `source` goes relative to the output directory, the cooked
+    /// extension is appended: `grid.png` becomes `grid.png.netex`.
+    pub fn output_path(self, source: &Path) -> PathBuf {
+        let extension = match self {
+            AssetKind::Mesh => MESH_EXTENSION,
+            AssetKind::Texture => TEXTURE_EXTENSION,
+            AssetKind::Other => return source.to_path_buf(),
+        };
+        let mut path = source.as_os_str().to_os_string();
+        path.push(".");
+        path.push(extension);
+        PathBuf::from(path)
+    }
+}
+
+/// What the cache remembers about a cooked source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// blake3 hash of the source.
+    hash: String,
+    /// Relative to the output directory.
+    output: String,
+    /// Other files the source was cooked from and their hashes, relative to the source
+    /// directory if they are in it.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CookCache {
+    version: u32,
+    /// The settings the entries were cooked with.
+    settings: String,
+    /// Keyed by the source path relative to the source directory.
+    #[serde(default)]
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+/// What [`Cooker::run`] did.
+#[derive(Debug, Default)]
+pub struct CookReport {
+    /// Sources that were cooked or copied.
+    pub cooked: Vec<String>,
+    /// Sources whose output was up to date.
+    pub up_to_date: usize,
+    /// Outputs of sources that were deleted.
+    pub removed: Vec<String>,
+    /// Sources that failed to cook and the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Cooks every file below `source` into `output`, mirroring the directory layout.
+///
+/// Hashes of the sources and their dependencies are kept in a cache file, only changed
+/// sources are cooked again.
+pub struct Cooker {
+    source: PathBuf,
+    output: PathBuf,
+    cache_path: PathBuf,
+    textures: TextureSettings,
+    force: bool,
+}
+impl Cooker {
+    /// The cache is kept next to `output`, as `<output>.cook_cache.toml`.
+    pub fn new(source: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Self {
+        let output = output.into();
+        let mut cache_name = output.file_name().unwrap_or_default().to_os_string();
+        cache_name.push(".cook_cache.toml");
+        Self {
+            source: source.into(),
+            cache_path: output.with_file_name(cache_name),
+            output,
+            textures: TextureSettings::default(),
+            force: false,
+        }
+    }
+    pub fn with_texture_settings(mut self, textures: TextureSettings) -> Self {
+        self.textures = textures;
+        self
+    }
+    pub fn with_cache_path(mut self, cache_path: impl Into<PathBuf>) -> Self {
+        self.cache_path = cache_path.into();
+        self
+    }
+    /// Cooks every source, even the ones that are up to date.
+    pub fn forced(mut self) -> Self {
+        self.force = true;
+        self
+    }
+
+    /// Fails only if the source directory can't be read or the cache can't be written,
+    /// sources that fail to cook are listed in the report.
+    pub fn run(&self) -> Result<CookReport> {
+        if !self.source.is_dir() {
+            bail!("{} is not a directory", self.source.display());
+        }
+        let settings = format!("{:?}", self.textures);
+        let mut cache = self.load_cache();
+        if self.force || cache.version != COOK_VERSION || cache.settings != settings {
+            cache = CookCache {
+                version: COOK_VERSION,
+                settings,
+                entries: BTreeMap::new(),
+            };
+        }
+
+        let mut sources = Vec::new();
+        for entry in walkdir::WalkDir::new(&self.source).follow_links(true) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                sources.push(entry.into_path());
+            }
+        }
+        sources.sort();
+
+        let mut report = CookReport::default();
+        // Output path to the source that produced it.
+        let mut outputs: HashMap<String, String> = HashMap::new();
+        let mut entries = BTreeMap::new();
+        for path in sources {
+            let relative = to_slash(path.strip_prefix(&self.source).unwrap());
+            let kind = AssetKind::of(&path);
+            let output = to_slash(&kind.output_path(Path::new(&relative)));
+            if let Some(other) = outputs.insert(output.clone(), relative.clone()) {
+                report.failed.push((
+                    relative,
+                    format!("{} is also cooked from {}", output, other),
+                ));
+                continue;
+            }
+            let result = fs::read(&path)
+                .with_context(|| format!("can't read {}", path.display()))
+                .and_then(|bytes| {
+                    let hash = blake3::hash(&bytes).to_hex().to_string();
+                    match cache.entries.remove(&relative) {
+                        Some(entry) if self.is_up_to_date(&entry, &hash) => Ok((entry, false)),
+                        _ => self.cook(kind, &path, &bytes, &output).map(|dependencies| {
+                            let entry = CacheEntry {
+                                hash,
+                                output,
+                                dependencies,
+                            };
+                            (entry, true)
+                        }),
+                    }
+                });
+            match result {
+                Ok((entry, cooked)) => {
+                    if cooked {
+                        report.cooked.push(relative.clone());
+                    } else {
+                        report.up_to_date += 1;
+                    }
+                    entries.insert(relative, entry);
+                }
+                Err(error) => report.failed.push((relative, format!("{:#}", error))),
+            }
+        }
+        // What is left in the cache are sources that were deleted.
+        for entry in cache.entries.into_values() {
+            if !outputs.contains_key(&entry.output) {
+                let _ = fs::remove_file(self.output.join(&entry.output));
+                report.removed.push(entry.output);
+            }
+        }
+        cache.entries = entries;
+        self.save_cache(&cache)?;
+        Ok(report)
+    }
+
+    fn is_up_to_date(&self, entry: &CacheEntry, hash: &str) -> bool {
+        entry.hash == hash
+            && self.output.join(&entry.output).is_file()
+            && entry.dependencies.iter().all(|(path, hash)| {
+                fs::read(self.source.join(path))
+                    .map(|bytes| blake3::hash(&bytes).to_hex().as_str() == hash)
+                    .unwrap_or(false)
+            })
+    }
+
+    /// Writes the output and returns the hashes of the dependencies.
+    fn cook(
+        &self,
+        kind: AssetKind,
+        path: &Path,
+        bytes: &[u8],
+        output: &str,
+    ) -> Result<BTreeMap<String, String>> {
+        let mut dependencies = BTreeMap::new();
+        let cooked = match kind {
+            AssetKind::Mesh if is_obj(path) => mesh::cook_obj(bytes)?.to_bytes(),
+            AssetKind::Mesh => {
+                let (model, files) = mesh::cook_gltf(path)?;
+                for file in files {
+                    let hash = blake3::hash(&fs::read(&file)?).to_hex().to_string();
+                    let file = match file.strip_prefix(&self.source) {
+                        Ok(relative) => to_slash(relative),
+                        Err(_) => file.to_string_lossy().into_owned(),
+                    };
+                    dependencies.insert(file, hash);
+                }
+                model.to_bytes()
+            }
+            AssetKind::Texture => texture::cook_texture(bytes, &self.textures)?.to_bytes(),
+            AssetKind::Other => bytes.to_vec(),
+        };
+        let output = self.output.join(output);
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&output, cooked).with_context(|| format!("can't write {}", output.display()))?;
+        Ok(dependencies)
+    }
+
+    fn load_cache(&self) -> CookCache {
+        let Ok(text) = fs::read_to_string(&self.cache_path) else {
+            return CookCache::default();
+        };
+        toml::from_str(&text).unwrap_or_else(|error| {
+            tracing::warn!("ignoring {}: {}", self.cache_path.display(), error);
+            CookCache::default()
+        })
+    }
+    fn save_cache(&self, cache: &CookCache) -> Result<()> {
+        let text = toml::to_string(cache)?;
+        fs::write(&self.cache_path, text)
+            .with_context(|| format!("can't write {}", self.cache_path.display()))
+    }
+}
+fn is_obj(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("obj"))
+}
+
+fn to_slash(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
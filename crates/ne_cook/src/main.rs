@@ -0,0 +1,81 @@
+//! Cooks an asset directory, only the sources that changed since the last run.
+//!
+//! ```text
+//! ne_cook engine_assets engine_assets_cooked --compress
+//! ne_pack build engine_assets_cooked engine_assets.nepak
+//! ```
+use std::process::ExitCode;
+
+use ne_cook::{Cooker, TextureSettings};
+
+const USAGE: &str = "usage: ne_cook <source dir> <output dir> [options]
+    --compress        block compress textures
+    --no-mipmaps      don't generate mip levels
+    --force           cook everything, even if it is up to date
+    --cache <file>    where to keep the hashes, <output dir>.cook_cache.toml by default";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (Some(source), Some(output)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let mut textures = TextureSettings::default();
+    let mut force = false;
+    let mut cache = None;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--compress" => textures.compress = true,
+            "--no-mipmaps" => textures.mipmaps = false,
+            "--force" => force = true,
+            "--cache" => {
+                cache = options.next();
+                if cache.is_none() {
+                    eprintln!("--cache needs a file\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            }
+            _ => {
+                eprintln!("unknown option {}\n{}", option, USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let mut cooker = Cooker::new(source, output).with_texture_settings(textures);
+    if force {
+        cooker = cooker.forced();
+    }
+    if let Some(cache) = cache {
+        cooker = cooker.with_cache_path(cache);
+    }
+
+    let report = match cooker.run() {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("error: {:#}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    for path in &report.cooked {
+        println!("cooked   {}", path);
+    }
+    for path in &report.removed {
+        println!("removed  {}", path);
+    }
+    for (path, error) in &report.failed {
+        eprintln!("failed   {}: {}", path, error);
+    }
+    println!(
+        "{} cooked, {} up to date, {} removed, {} failed",
+        report.cooked.len(),
+        report.up_to_date,
+        report.removed.len(),
+        report.failed.len()
+    );
+    if report.failed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,265 @@
+//! Meshes cooked into vertex and index buffers that are uploaded without any parsing.
+//!
+//! Layout of a `.nemesh` file, all values little endian:
+//! ```text
+//! header      magic "NMSH", version: u32, mesh_count: u32
+//! per mesh    aabb_min: [f32; 3], aabb_max: [f32; 3], vertex_count: u32, index_count: u32,
+//!             vertices: [CookedVertex; vertex_count], indices: [u32; index_count]
+//! ```
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use bytemuck::{Pod, Zeroable};
+
+use crate::binary::{Reader, Writer};
+
+/// Extension of cooked meshes.
+pub const MESH_EXTENSION: &str = "nemesh";
+const MAGIC: &[u8; 4] = b"NMSH";
+const VERSION: u32 = 1;
+
+/// A vertex with the same layout as `ne_render::mesh::Vertex`, y is up.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct CookedVertex {
+    pub pos: [f32; 4],
+    pub tex_coord: [f32; 2],
+}
+impl CookedVertex {
+    pub fn new(pos: [f32; 3], tex_coord: [f32; 2]) -> Self {
+        Self {
+            pos: [pos[0], pos[1], pos[2], 1.0],
+            tex_coord,
+        }
+    }
+}
+
+/// Axis aligned bounding box.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+impl Aabb {
+    /// The box around the positions of `vertices`, empty at the origin if there are none.
+    pub fn from_vertices(vertices: &[CookedVertex]) -> Self {
+        let Some(first) = vertices.first() else {
+            return Self::default();
+        };
+        let mut aabb = Self {
+            min: [first.pos[0], first.pos[1], first.pos[2]],
+            max: [first.pos[0], first.pos[1], first.pos[2]],
+        };
+        for vertex in vertices {
+            for axis in 0..3 {
+                aabb.min[axis] = aabb.min[axis].min(vertex.pos[axis]);
+                aabb.max[axis] = aabb.max[axis].max(vertex.pos[axis]);
+            }
+        }
+        aabb
+    }
+    /// The box around both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut aabb = *self;
+        for axis in 0..3 {
+            aabb.min[axis] = aabb.min[axis].min(other.min[axis]);
+            aabb.max[axis] = aabb.max[axis].max(other.max[axis]);
+        }
+        aabb
+    }
+}
+
+/// A triangle list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookedMesh {
+    pub vertices: Vec<CookedVertex>,
+    pub indices: Vec<u32>,
+    pub aabb: Aabb,
+}
+impl CookedMesh {
+    pub fn new(vertices: Vec<CookedVertex>, indices: Vec<u32>) -> Self {
+        let aabb = Aabb::from_vertices(&vertices);
+        Self {
+            vertices,
+            indices,
+            aabb,
+        }
+    }
+}
+
+/// The meshes of one source file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookedModel {
+    pub meshes: Vec<CookedMesh>,
+}
+impl CookedModel {
+    /// The box around all meshes.
+    pub fn aabb(&self) -> Aabb {
+        self.meshes
+            .iter()
+            .map(|mesh| mesh.aabb)
+            .reduce(|a, b| a.union(&b))
+            .unwrap_or_default()
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new(MAGIC, VERSION);
+        writer.u32(self.meshes.len() as u32);
+        for mesh in &self.meshes {
+            writer.f32s(&mesh.aabb.min);
+            writer.f32s(&mesh.aabb.max);
+            writer.u32(mesh.vertices.len() as u32);
+            writer.u32(mesh.indices.len() as u32);
+            writer.pod_slice(&mesh.vertices);
+            writer.pod_slice(&mesh.indices);
+        }
+        writer.finish()
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes, MAGIC, VERSION)?;
+        let mesh_count = reader.u32()?;
+        let mut meshes = Vec::new();
+        for _ in 0..mesh_count {
+            let min = reader.f32s()?;
+            let max = reader.f32s()?;
+            let vertex_count = reader.u32()? as usize;
+            let index_count = reader.u32()? as usize;
+            let vertices: Vec<CookedVertex> = reader.pod_vec(vertex_count)?;
+            let indices: Vec<u32> = reader.pod_vec(index_count)?;
+            if let Some(index) = indices
+                .iter()
+                .find(|&&index| index as usize >= vertex_count)
+            {
+                bail!(
+                    "index {} is out of bounds of {} vertices",
+                    index,
+                    vertex_count
+                );
+            }
+            meshes.push(CookedMesh {
+                vertices,
+                indices,
+                aabb: Aabb { min, max },
+            });
+        }
+        Ok(Self { meshes })
+    }
+}
+
+/// Triangulates the meshes of an obj file, its materials are ignored.
+pub fn cook_obj(bytes: &[u8]) -> Result<CookedModel> {
+    let load_options = tobj::LoadOptions {
+        triangulate: true,
+        single_index: true,
+        ..Default::default()
+    };
+    let (models, _) = tobj::load_obj_buf(&mut std::io::Cursor::new(bytes), &load_options, |_| {
+        Ok(Default::default())
+    })?;
+    let meshes = models
+        .into_iter()
+        .map(|model| {
+            let mesh = model.mesh;
+            let vertices = (0..mesh.positions.len() / 3)
+                .map(|i| {
+                    let tex_coord = mesh.texcoords.get(i * 2..i * 2 + 2).unwrap_or(&[0.0, 0.0]);
+                    CookedVertex::new(
+                        [
+                            mesh.positions[i * 3],
+                            mesh.positions[i * 3 + 1],
+                            mesh.positions[i * 3 + 2],
+                        ],
+                        [tex_coord[0], tex_coord[1]],
+                    )
+                })
+                .collect();
+            CookedMesh::new(vertices, mesh.indices)
+        })
+        .collect();
+    Ok(CookedModel { meshes })
+}
+
+/// Reads the triangle primitives of the default scene of a `.gltf` or `.glb` file, with the
+/// node transforms applied. Also returns the external buffer files it read.
+pub fn cook_gltf(path: &Path) -> Result<(CookedModel, Vec<PathBuf>)> {
+    let gltf = gltf::Gltf::open(path)?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let dependencies = gltf
+        .buffers()
+        .filter_map(|buffer| match buffer.source() {
+            gltf::buffer::Source::Uri(uri) if !uri.starts_with("data:") => Some(base.join(uri)),
+            _ => None,
+        })
+        .collect();
+    let buffers = gltf::import_buffers(&gltf, Some(base), gltf.blob.clone())
+        .context("can't read the buffers")?;
+    let scene = gltf
+        .default_scene()
+        .or_else(|| gltf.scenes().next())
+        .context("the file has no scene")?;
+
+    let mut model = CookedModel::default();
+    let mut nodes: Vec<_> = scene.nodes().map(|node| (node, IDENTITY)).collect();
+    while let Some((node, parent)) = nodes.pop() {
+        let transform = mul(&parent, &node.transform().matrix());
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    tracing::warn!(
+                        "skipping a {:?} primitive of {:?}",
+                        primitive.mode(),
+                        mesh.name()
+                    );
+                    continue;
+                }
+                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                let Some(positions) = reader.read_positions() else {
+                    continue;
+                };
+                let mut tex_coords = reader
+                    .read_tex_coords(0)
+                    .map(|tex_coords| tex_coords.into_f32());
+                let vertices: Vec<_> = positions
+                    .map(|pos| {
+                        let tex_coord = tex_coords
+                            .as_mut()
+                            .and_then(Iterator::next)
+                            .unwrap_or([0.0, 0.0]);
+                        CookedVertex::new(transform_point(&transform, pos), tex_coord)
+                    })
+                    .collect();
+                let indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..vertices.len() as u32).collect(),
+                };
+                model.meshes.push(CookedMesh::new(vertices, indices));
+            }
+        }
+        nodes.extend(node.children().map(|child| (child, transform)));
+    }
+    Ok((model, dependencies))
+}
+
+type Mat4 = [[f32; 4]; 4];
+const IDENTITY: Mat4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+/// `a * b` of column major matrices.
+fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [[0.0; 4]; 4];
+    for (column, out_column) in out.iter_mut().enumerate() {
+        for (row, value) in out_column.iter_mut().enumerate() {
+            *value = (0..4).map(|i| a[i][row] * b[column][i]).sum();
+        }
+    }
+    out
+}
+fn transform_point(m: &Mat4, p: [f32; 3]) -> [f32; 3] {
+    let mut out = [0.0; 3];
+    for (row, value) in out.iter_mut().enumerate() {
+        *value = m[0][row] * p[0] + m[1][row] * p[1] + m[2][row] * p[2] + m[3][row];
+    }
+    out
+}
@@ -0,0 +1,18 @@
+//! Cooks meshes and textures offline into formats the renderer uploads without parsing:
+//! meshes into ready made vertex and index buffers, textures into mip chains that are
+//! optionally block compressed.
+//!
+//! ```ignore
+//! let report = Cooker::new("engine_assets", "engine_assets_cooked").run()?;
+//! let bytes = std::fs::read("engine_assets_cooked/3D/cube.obj.nemesh")?;
+//! let model = CookedModel::from_bytes(&bytes)?;
+//! ```
+mod bc;
+mod binary;
+pub mod cache;
+pub mod mesh;
+pub mod texture;
+
+pub use cache::{AssetKind, CookReport, Cooker};
+pub use mesh::{Aabb, CookedMesh, CookedModel, CookedVertex, MESH_EXTENSION};
+pub use texture::{CookedTexture, TextureFormat, TextureSettings, TEXTURE_EXTENSION};
@@ -0,0 +1,240 @@
+//! Textures cooked into a full mip chain, optionally block compressed.
+//!
+//! Layout of a `.netex` file, all values little endian:
+//! ```text
+//! header      magic "NTEX", version: u32, format: u8, width: u32, height: u32, mip_count: u32
+//! per mip     len: u32, data: [u8; len], the largest level first
+//! ```
+use anyhow::{bail, Result};
+use image::{imageops::FilterType, RgbaImage};
+
+use crate::{
+    bc,
+    binary::{Reader, Writer},
+};
+
+/// Extension of cooked textures.
+pub const TEXTURE_EXTENSION: &str = "netex";
+const MAGIC: &[u8; 4] = b"NTEX";
+const VERSION: u32 = 1;
+
+/// How the pixels of a [`CookedTexture`] are stored, all formats are srgb.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    Rgba8,
+    /// 4x4 blocks of 8 bytes, without alpha.
+    Bc1,
+    /// 4x4 blocks of 16 bytes, with alpha.
+    Bc3,
+}
+impl TextureFormat {
+    fn from_u8(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => TextureFormat::Rgba8,
+            1 => TextureFormat::Bc1,
+            2 => TextureFormat::Bc3,
+            _ => bail!("unknown texture format {}", value),
+        })
+    }
+    fn to_u8(self) -> u8 {
+        match self {
+            TextureFormat::Rgba8 => 0,
+            TextureFormat::Bc1 => 1,
+            TextureFormat::Bc3 => 2,
+        }
+    }
+    pub fn is_compressed(self) -> bool {
+        self != TextureFormat::Rgba8
+    }
+    /// Bytes per pixel for rgba8, bytes per 4x4 block otherwise.
+    pub fn block_size(self) -> usize {
+        match self {
+            TextureFormat::Rgba8 => 4,
+            TextureFormat::Bc1 => 8,
+            TextureFormat::Bc3 => 16,
+        }
+    }
+    /// Bytes per row of pixels for rgba8, per row of blocks otherwise.
+    pub fn bytes_per_row(self, width: u32) -> u32 {
+        match self {
+            TextureFormat::Rgba8 => width * 4,
+            _ => bc::blocks(width) * self.block_size() as u32,
+        }
+    }
+    /// Bytes of a mip level of this size.
+    pub fn level_size(self, width: u32, height: u32) -> usize {
+        let rows = match self {
+            TextureFormat::Rgba8 => height,
+            _ => bc::blocks(height),
+        };
+        self.bytes_per_row(width) as usize * rows as usize
+    }
+}
+
+/// How textures are cooked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureSettings {
+    /// Generates every mip level down to 1x1.
+    pub mipmaps: bool,
+    /// Block compresses textures whose size is a multiple of 4, with [`TextureFormat::Bc3`] if
+    /// they have transparent pixels and [`TextureFormat::Bc1`] otherwise.
+    pub compress: bool,
+}
+impl Default for TextureSettings {
+    fn default() -> Self {
+        Self {
+            mipmaps: true,
+            compress: false,
+        }
+    }
+}
+
+/// A texture ready for upload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookedTexture {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    /// The data of every mip level, the largest first.
+    pub mips: Vec<Vec<u8>>,
+}
+impl CookedTexture {
+    /// A single level texture of rgba8 pixels.
+    pub fn from_rgba(image: RgbaImage) -> Self {
+        Self {
+            width: image.width(),
+            height: image.height(),
+            format: TextureFormat::Rgba8,
+            mips: vec![image.into_raw()],
+        }
+    }
+    /// Size in pixels of mip `level`.
+    pub fn mip_size(&self, level: usize) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+    /// Size of mip `level` rounded up to whole blocks, the size its data covers.
+    pub fn copy_size(&self, level: usize) -> (u32, u32) {
+        let (width, height) = self.mip_size(level);
+        match self.format {
+            TextureFormat::Rgba8 => (width, height),
+            _ => (bc::blocks(width) * 4, bc::blocks(height) * 4),
+        }
+    }
+    /// The texture with every level decompressed to rgba8, for devices without block
+    /// compression.
+    pub fn to_rgba8(&self) -> Self {
+        if !self.format.is_compressed() {
+            return self.clone();
+        }
+        let mips = self
+            .mips
+            .iter()
+            .enumerate()
+            .map(|(level, data)| {
+                let (width, height) = self.mip_size(level);
+                bc::decompress(data, width, height, self.format)
+            })
+            .collect();
+        Self {
+            format: TextureFormat::Rgba8,
+            mips,
+            ..*self
+        }
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new(MAGIC, VERSION);
+        writer.u8(self.format.to_u8());
+        writer.u32(self.width);
+        writer.u32(self.height);
+        writer.u32(self.mips.len() as u32);
+        for mip in &self.mips {
+            writer.u32(mip.len() as u32);
+            writer.pod_slice(mip);
+        }
+        writer.finish()
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes, MAGIC, VERSION)?;
+        let format = TextureFormat::from_u8(reader.u8()?)?;
+        let width = reader.u32()?;
+        let height = reader.u32()?;
+        let mip_count = reader.u32()? as usize;
+        if width == 0 || height == 0 || mip_count == 0 {
+            bail!("the texture is empty");
+        }
+        let mut texture = Self {
+            width,
+            height,
+            format,
+            mips: Vec::with_capacity(mip_count),
+        };
+        for level in 0..mip_count {
+            let len = reader.u32()? as usize;
+            let (width, height) = texture.mip_size(level);
+            if len != format.level_size(width, height) {
+                bail!(
+                    "mip {} has {} bytes, expected {}",
+                    level,
+                    len,
+                    format.level_size(width, height)
+                );
+            }
+            texture.mips.push(reader.bytes(len)?);
+        }
+        Ok(texture)
+    }
+}
+
+/// Decodes an image file into a [`CookedTexture`].
+pub fn cook_texture(bytes: &[u8], settings: &TextureSettings) -> Result<CookedTexture> {
+    let image = image::load_from_memory(bytes)?.to_rgba8();
+    let (width, height) = image.dimensions();
+    let mut levels = vec![image];
+    if settings.mipmaps {
+        while levels.len() < mip_count(width, height) {
+            let previous = levels.last().unwrap();
+            let (width, height) = (
+                (previous.width() / 2).max(1),
+                (previous.height() / 2).max(1),
+            );
+            levels.push(image::imageops::resize(
+                previous,
+                width,
+                height,
+                FilterType::Triangle,
+            ));
+        }
+    }
+    let format = if !settings.compress {
+        TextureFormat::Rgba8
+    } else if width % 4 != 0 || height % 4 != 0 {
+        tracing::warn!(
+            "{}x{} is not a multiple of 4, not compressing",
+            width,
+            height
+        );
+        TextureFormat::Rgba8
+    } else if levels[0].pixels().any(|pixel| pixel[3] < 255) {
+        TextureFormat::Bc3
+    } else {
+        TextureFormat::Bc1
+    };
+    let mips = levels
+        .into_iter()
+        .map(|level| match format {
+            TextureFormat::Rgba8 => level.into_raw(),
+            _ => bc::compress(&level, level.width(), level.height(), format),
+        })
+        .collect();
+    Ok(CookedTexture {
+        width,
+        height,
+        format,
+        mips,
+    })
+}
+
+/// Levels of a full mip chain of a texture of this size.
+pub fn mip_count(width: u32, height: u32) -> usize {
+    (32 - width.max(height).max(1).leading_zeros()) as usize
+}
@@ -0,0 +1,341 @@
+//! Loads gameplay [`Plugin`]s from a dynamic library and reloads them when the library is rebuilt,
+//! so game logic can be changed without restarting the editor.
+//!
+//! The game crate is built as a `cdylib` and exposes its plugin with [`declare_plugin!`]:
+//! ```ignore
+//! // game/Cargo.toml: [lib] crate-type = ["cdylib"]
+//! struct GamePlugin;
+//! impl Plugin for GamePlugin {
+//!     fn setup(&self, app: &mut App) {
+//!         app.add_system(move_player);
+//!     }
+//! }
+//! ne_dylib::declare_plugin!(GamePlugin);
+//! ```
+//! The host adds [`DylibPlugin`] pointing at the built library.
+//!
+//! On reload the systems of the old library are dropped and the plugin is set up again against
+//! the same [`World`], entities and resources are kept. Because of that:
+//! - resources should be added with `init_resource`, `insert_resource` overwrites the kept value.
+//! - component and resource *types* should live in a crate that is linked statically into both
+//!   the host and the game, their drop code must not be unloaded while the world still holds them.
+//! - startup systems only run for the first load.
+//! - host and game have to be built by the same compiler with the same engine version, the
+//!   plugin crosses the library boundary as a Rust trait object.
+//! - the game's plugins may depend on the plugins the host added before [`DylibPlugin`].
+use std::{
+    any::TypeId,
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use bevy_ecs::{
+    schedule::{IntoSystemDescriptor, Schedule, Stage, SystemLabel, SystemStage},
+    system::Resource,
+    world::{Mut, World},
+};
+use libloading::{Library, Symbol};
+pub use ne_app::Plugin;
+use ne_app::{App, CoreStage, RegisteredEvents, StartupSchedule, StartupStage};
+use tracing::{error, info};
+
+/// Name of the function [`declare_plugin!`] exports.
+pub const CREATE_PLUGIN_SYMBOL: &[u8] = b"_ne_create_plugin";
+#[allow(improper_ctypes_definitions)]
+type CreatePlugin = extern "C" fn() -> *mut dyn Plugin;
+
+/// Exports a plugin from a game library so [`DylibPlugin`] can load it.
+///
+/// The exported function uses the C calling convention, but the plugin it returns is a Rust
+/// trait object: its layout is only the same for a host and game built by the same compiler.
+#[macro_export]
+macro_rules! declare_plugin {
+    ($plugin:expr) => {
+        #[no_mangle]
+        #[allow(improper_ctypes_definitions)]
+        pub extern "C" fn _ne_create_plugin() -> *mut dyn $crate::Plugin {
+            let plugin: ::std::boxed::Box<dyn $crate::Plugin> = ::std::boxed::Box::new($plugin);
+            ::std::boxed::Box::into_raw(plugin)
+        }
+    };
+}
+
+/// Reasons a game library can't be (re)loaded.
+#[derive(Debug)]
+pub enum DylibError {
+    Io(std::io::Error),
+    Library(libloading::Error),
+}
+impl fmt::Display for DylibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DylibError::Io(error) => write!(f, "{}", error),
+            DylibError::Library(error) => write!(f, "{}", error),
+        }
+    }
+}
+impl std::error::Error for DylibError {}
+impl From<std::io::Error> for DylibError {
+    fn from(error: std::io::Error) -> Self {
+        DylibError::Io(error)
+    }
+}
+impl From<libloading::Error> for DylibError {
+    fn from(error: libloading::Error) -> Self {
+        DylibError::Library(error)
+    }
+}
+
+/// Loads the game library at `path` and reloads it whenever the file changes.
+///
+/// Add it after the plugins the game's plugins depend on.
+pub struct DylibPlugin {
+    /// Path of the built library, e.g. `target/debug/libgame.so`.
+    pub path: PathBuf,
+    /// How often the library file is checked for changes.
+    pub poll_interval: Duration,
+}
+impl DylibPlugin {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+impl Plugin for DylibPlugin {
+    fn setup(&self, app: &mut App) {
+        let mut game_library = GameLibrary::new(
+            self.path.clone(),
+            self.poll_interval,
+            app.added_plugins().to_vec(),
+        );
+        if let Err(error) = game_library.load(&mut app.world) {
+            panic!(
+                "failed to load game library {}: {}",
+                self.path.display(),
+                error
+            );
+        }
+        app.insert_resource(game_library)
+            .add_system_to_stage(CoreStage::First, hot_reload_system.label(HotReloadSystem));
+        for stage in GAME_STAGES {
+            app.add_system_to_stage(
+                stage.clone(),
+                run_game_stage(stage).after(HotReloadSystem),
+            );
+        }
+    }
+}
+
+/// Label of the system that checks the game library for changes.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub struct HotReloadSystem;
+
+/// Stages of the game library, each one runs inside the main stage of the same name.
+const GAME_STAGES: [CoreStage; 7] = [
+    CoreStage::First,
+    CoreStage::PreUpdate,
+    CoreStage::FixedUpdate,
+    CoreStage::Update,
+    CoreStage::Gameplay,
+    CoreStage::PostUpdate,
+    CoreStage::Last,
+];
+
+/// The currently loaded game library and the schedule its plugin built.
+#[derive(Resource)]
+pub struct GameLibrary {
+    path: PathBuf,
+    poll_interval: Duration,
+    last_poll: Instant,
+    loaded_modified: Option<SystemTime>,
+    pending_modified: Option<SystemTime>,
+    reload_count: u32,
+    startup_pending: bool,
+    /// Events registered by the library, forgotten before a reload so their update systems are added again.
+    library_events: HashSet<TypeId>,
+    /// The plugins the host added before the library, the game's plugins may depend on them.
+    host_plugins: Vec<&'static str>,
+    /// Systems point into `library`, so the schedule is declared (and dropped) first.
+    schedule: Schedule,
+    library: Option<Library>,
+    library_copy: Option<PathBuf>,
+}
+impl GameLibrary {
+    fn new(path: PathBuf, poll_interval: Duration, host_plugins: Vec<&'static str>) -> Self {
+        Self {
+            path,
+            poll_interval,
+            last_poll: Instant::now(),
+            loaded_modified: None,
+            pending_modified: None,
+            reload_count: 0,
+            startup_pending: true,
+            library_events: HashSet::new(),
+            host_plugins,
+            schedule: Schedule::default(),
+            library: None,
+            library_copy: None,
+        }
+    }
+    /// Path of the library that is watched.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// How many times the library has been loaded, including the first load.
+    pub fn reload_count(&self) -> u32 {
+        self.reload_count
+    }
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+    /// Loads the current version of the library and swaps out the systems of the previous one.
+    fn load(&mut self, world: &mut World) -> Result<(), DylibError> {
+        let modified = self.modified();
+        // The library is loaded from a copy: the original stays writable for the compiler and
+        // the loader won't hand back the cached previous version for the same path.
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let copy = std::env::temp_dir().join(format!(
+            "ne_dylib-{}-{}-{}",
+            std::process::id(),
+            self.reload_count,
+            file_name
+        ));
+        std::fs::copy(&self.path, &copy)?;
+
+        let library = unsafe { Library::new(&copy)? };
+        let plugin = unsafe {
+            let create_plugin: Symbol<CreatePlugin> = library.get(CREATE_PLUGIN_SYMBOL)?;
+            Box::from_raw(create_plugin())
+        };
+
+        if let Some(mut registered) = world.get_resource_mut::<RegisteredEvents>() {
+            for event in self.library_events.drain() {
+                registered.0.remove(&event);
+            }
+        }
+        let registered_before = world
+            .get_resource::<RegisteredEvents>()
+            .map(|registered| registered.0.clone())
+            .unwrap_or_default();
+
+        // The plugin is set up against the real world with an empty schedule of its own.
+        let mut game_app = game_app(&self.host_plugins);
+        std::mem::swap(world, &mut game_app.world);
+        plugin.setup(&mut game_app);
+        std::mem::swap(world, &mut game_app.world);
+        drop(plugin);
+
+        if let Some(registered) = world.get_resource::<RegisteredEvents>() {
+            self.library_events = registered
+                .0
+                .difference(&registered_before)
+                .copied()
+                .collect();
+        }
+        // Old systems go before the old library they point into.
+        self.schedule = game_app.schedule;
+        self.library = Some(library);
+        if let Some(old_copy) = self.library_copy.replace(copy) {
+            let _ = std::fs::remove_file(old_copy);
+        }
+        self.loaded_modified = modified;
+        self.reload_count += 1;
+        info!(
+            "Loaded game library {} (version {})",
+            self.path.display(),
+            self.reload_count
+        );
+        Ok(())
+    }
+}
+impl Drop for GameLibrary {
+    fn drop(&mut self) {
+        self.schedule = Schedule::default();
+        self.library = None;
+        if let Some(copy) = self.library_copy.take() {
+            let _ = std::fs::remove_file(copy);
+        }
+    }
+}
+
+/// An app with the stages of [`App::add_default_stages`] but without run criteria,
+/// the matching main stages already decide when they run. The plugins of the host count as
+/// added.
+fn game_app(host_plugins: &[&'static str]) -> App {
+    let mut app = App::empty();
+    app.assume_plugins_added(host_plugins)
+        .add_stage(CoreStage::First, SystemStage::parallel())
+        .add_stage(
+            StartupSchedule,
+            Schedule::default()
+                .with_stage(StartupStage::PreStartup, SystemStage::parallel())
+                .with_stage(StartupStage::Startup, SystemStage::parallel())
+                .with_stage(StartupStage::PostStartup, SystemStage::parallel()),
+        )
+        .add_stage(CoreStage::PreUpdate, SystemStage::parallel())
+        .add_stage(CoreStage::FixedUpdate, SystemStage::parallel())
+        .add_stage(CoreStage::Update, SystemStage::parallel())
+        .add_stage(CoreStage::Gameplay, SystemStage::parallel())
+        .add_stage(CoreStage::PostUpdate, SystemStage::parallel())
+        .add_stage(CoreStage::Last, SystemStage::parallel());
+    app
+}
+
+/// Runs the startup systems of the first load and reloads the library once a change has settled.
+pub fn hot_reload_system(world: &mut World) {
+    world.resource_scope(|world, mut game_library: Mut<GameLibrary>| {
+        if game_library.startup_pending {
+            game_library.startup_pending = false;
+            if let Some(startup) = game_library
+                .schedule
+                .get_stage_mut::<Schedule>(StartupSchedule)
+            {
+                startup.run(world);
+            }
+        }
+        if game_library.last_poll.elapsed() < game_library.poll_interval {
+            return;
+        }
+        game_library.last_poll = Instant::now();
+        let modified = game_library.modified();
+        if modified.is_none() || modified == game_library.loaded_modified {
+            return;
+        }
+        // Wait until the file stayed the same for a whole poll, the compiler may still be writing it.
+        if modified != game_library.pending_modified {
+            game_library.pending_modified = modified;
+            return;
+        }
+        if let Err(error) = game_library.load(world) {
+            error!(
+                "Failed to reload game library {}, keeping the previous version: {}",
+                game_library.path.display(),
+                error
+            );
+            game_library.loaded_modified = modified;
+        }
+    });
+}
+
+fn run_game_stage(stage: CoreStage) -> impl FnMut(&mut World) {
+    move |world: &mut World| {
+        world.resource_scope(|world, mut game_library: Mut<GameLibrary>| {
+            if let Some(game_stage) = game_library
+                .schedule
+                .get_stage_mut::<SystemStage>(stage.clone())
+            {
+                game_stage.run(world);
+            }
+        });
+    }
+}
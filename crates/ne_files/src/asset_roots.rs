@@ -0,0 +1,140 @@
+//! Finds the asset directories at runtime, from any working directory.
+//!
+//! Every root is resolved in this order, the first match wins:
+//! 1. an environment variable: `NE_ENGINE_ASSETS`, `NE_PROJECT_ASSETS` or `NE_USER_DATA`.
+//! 2. the [`CONFIG_FILE`], either at the path in `NE_CONFIG`, next to the executable or in the
+//!    working directory:
+//!    ```toml
+//!    engine_assets = "../engine_assets"
+//!    project_assets = "assets"
+//!    user_data = "/home/me/.local/share/my_game"
+//!    ```
+//!    Relative paths are relative to the config file.
+//! 3. a directory named `engine_assets` or `assets`, or an archive named `engine_assets.nepak`
+//!    or `assets.nepak`, next to the executable, in the working directory, or in one of their
+//!    parents. For the user data the platform's data directory, e.g. `~/.local/share/<app name>`.
+//!
+//! The engine and project roots are read through a [`Vfs`](crate::vfs::Vfs), see
+//! [`Vfs::from_roots`](crate::vfs::Vfs::from_roots).
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+/// Name of the config file that overrides the roots.
+pub const CONFIG_FILE: &str = "ne_paths.toml";
+
+const ENGINE_ENV: &str = "NE_ENGINE_ASSETS";
+const PROJECT_ENV: &str = "NE_PROJECT_ASSETS";
+const USER_ENV: &str = "NE_USER_DATA";
+const CONFIG_ENV: &str = "NE_CONFIG";
+
+const ENGINE_DIR: &str = "engine_assets";
+const PROJECT_DIR: &str = "assets";
+
+#[derive(Debug, Default, Deserialize)]
+struct RootsConfig {
+    engine_assets: Option<PathBuf>,
+    project_assets: Option<PathBuf>,
+    user_data: Option<PathBuf>,
+}
+
+/// The directories the engine reads and writes files in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetRoots {
+    /// The assets that come with the engine, a directory or a `.nepak` archive.
+    pub engine: Option<PathBuf>,
+    /// The assets of the game or project, a directory or a `.nepak` archive.
+    /// They override engine assets with the same path.
+    pub project: Option<PathBuf>,
+    /// Writable directory for saves, settings and logs, may not exist yet.
+    pub user: Option<PathBuf>,
+}
+impl AssetRoots {
+    /// Resolves the roots of the running executable, named after its file name.
+    pub fn discover() -> Self {
+        let app_name = env::current_exe()
+            .ok()
+            .and_then(|exe| {
+                exe.file_stem()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "nightmare_engine".to_string());
+        Self::discover_for(&app_name)
+    }
+    /// Resolves the roots, the user data directory is named `app_name`.
+    pub fn discover_for(app_name: &str) -> Self {
+        let (config, config_dir) = load_config();
+        let from_config = |path: Option<PathBuf>| path.map(|path| config_dir.join(path));
+        let roots = Self {
+            engine: env_path(ENGINE_ENV)
+                .or_else(|| from_config(config.engine_assets))
+                .or_else(|| search_dir(ENGINE_DIR)),
+            project: env_path(PROJECT_ENV)
+                .or_else(|| from_config(config.project_assets))
+                .or_else(|| search_dir(PROJECT_DIR)),
+            user: env_path(USER_ENV)
+                .or_else(|| from_config(config.user_data))
+                .or_else(|| dirs::data_dir().map(|dir| dir.join(app_name))),
+        };
+        tracing::debug!("asset roots: {:?}", roots);
+        roots
+    }
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The directory of the executable, then the working directory.
+fn base_dirs() -> Vec<PathBuf> {
+    let exe_dir = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    exe_dir.into_iter().chain(env::current_dir().ok()).collect()
+}
+
+/// Looks for a directory `name` or an archive `name.nepak` in the base dirs and their parents.
+fn search_dir(name: &str) -> Option<PathBuf> {
+    base_dirs().iter().find_map(|base| {
+        base.ancestors().find_map(|dir| {
+            let loose = dir.join(name);
+            let packed = dir.join(format!("{}.nepak", name));
+            if loose.is_dir() {
+                Some(loose)
+            } else if packed.is_file() {
+                Some(packed)
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// The config and the directory its relative paths start from.
+fn load_config() -> (RootsConfig, PathBuf) {
+    let path = env_path(CONFIG_ENV).or_else(|| {
+        base_dirs()
+            .into_iter()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+    });
+    let Some(path) = path else {
+        return (RootsConfig::default(), PathBuf::new());
+    };
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let config = fs::read_to_string(&path)
+        .map_err(|error| error.to_string())
+        .and_then(|text| toml::from_str(&text).map_err(|error| error.to_string()));
+    match config {
+        Ok(config) => (config, dir),
+        Err(error) => {
+            tracing::warn!("ignoring {}: {}", path.display(), error);
+            (RootsConfig::default(), dir)
+        }
+    }
+}
@@ -0,0 +1,445 @@
+//! The `.nepak` archive format: many files in one, each compressed on its own so single files
+//! can be read without unpacking the rest.
+//!
+//! Layout, all integers little endian:
+//! ```text
+//! header      magic "NPAK", version: u32, entry_count: u32, reserved: u32, index_offset: u64
+//! data        the stored bytes of every file, back to back
+//! index       per entry, sorted by path:
+//!             path_len: u16, path: utf-8 with '/' separators, offset: u64, stored_size: u64,
+//!             size: u64, compression: u8, hash: [u8; 32]
+//! ```
+//! The hash is the blake3 hash of the uncompressed file, checked on every read.
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+};
+
+const MAGIC: &[u8; 4] = b"NPAK";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 24;
+/// Compressed files are only stored compressed if that saves at least this fraction.
+const MIN_SAVING: f64 = 0.05;
+
+/// How a file is stored in an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Lz4,
+}
+impl Compression {
+    fn from_u8(value: u8) -> io::Result<Self> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Lz4),
+            _ => Err(invalid_data(format!("unknown compression {}", value))),
+        }
+    }
+    fn to_u8(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Lz4 => 1,
+        }
+    }
+}
+
+/// A file in an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PakEntry {
+    /// Relative path with `/` separators.
+    pub path: String,
+    pub offset: u64,
+    /// Size in the archive.
+    pub stored_size: u64,
+    /// Size after decompressing.
+    pub size: u64,
+    pub compression: Compression,
+    /// blake3 hash of the uncompressed contents.
+    pub hash: [u8; 32],
+}
+
+/// Writes an archive, call [`finish`](Self::finish) after adding all files.
+pub struct PakWriter<W: Write + Seek> {
+    writer: W,
+    entries: Vec<PakEntry>,
+    offset: u64,
+}
+impl PakWriter<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+impl<W: Write + Seek> PakWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        // The header is written again by `finish` once the index offset is known.
+        writer.write_all(&[0; HEADER_SIZE as usize])?;
+        Ok(Self {
+            writer,
+            entries: Vec::new(),
+            offset: HEADER_SIZE,
+        })
+    }
+    /// Adds a file at `path`, compressed if `compress` is set and that makes it smaller.
+    pub fn add(&mut self, path: &str, data: &[u8], compress: bool) -> io::Result<&PakEntry> {
+        let path = normalize(Path::new(path))?;
+        if self.entries.iter().any(|entry| entry.path == path) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} was added twice", path),
+            ));
+        }
+        let compressed = compress
+            .then(|| lz4_flex::compress(data))
+            .filter(|compressed| {
+                (compressed.len() as f64) < data.len() as f64 * (1.0 - MIN_SAVING)
+            });
+        let (stored, compression) = match &compressed {
+            Some(compressed) => (compressed.as_slice(), Compression::Lz4),
+            None => (data, Compression::None),
+        };
+        self.writer.write_all(stored)?;
+        self.entries.push(PakEntry {
+            path,
+            offset: self.offset,
+            stored_size: stored.len() as u64,
+            size: data.len() as u64,
+            compression,
+            hash: *blake3::hash(data).as_bytes(),
+        });
+        self.offset += stored.len() as u64;
+        Ok(self.entries.last().unwrap())
+    }
+    /// Writes the index and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let index_offset = self.offset;
+        for entry in &self.entries {
+            let path = entry.path.as_bytes();
+            let path_len = u16::try_from(path.len())
+                .map_err(|_| invalid_data(format!("path too long: {}", entry.path)))?;
+            self.writer.write_all(&path_len.to_le_bytes())?;
+            self.writer.write_all(path)?;
+            self.writer.write_all(&entry.offset.to_le_bytes())?;
+            self.writer.write_all(&entry.stored_size.to_le_bytes())?;
+            self.writer.write_all(&entry.size.to_le_bytes())?;
+            self.writer.write_all(&[entry.compression.to_u8()])?;
+            self.writer.write_all(&entry.hash)?;
+        }
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(MAGIC)?;
+        self.writer.write_all(&VERSION.to_le_bytes())?;
+        self.writer
+            .write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&0_u32.to_le_bytes())?;
+        self.writer.write_all(&index_offset.to_le_bytes())?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// An opened archive. Only the index is kept in memory, files are read from disk on demand.
+#[derive(Debug)]
+pub struct PakArchive {
+    path: PathBuf,
+    entries: Vec<PakEntry>,
+    index: HashMap<String, usize>,
+}
+impl PakArchive {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+        let mut header = [0; HEADER_SIZE as usize];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data(format!(
+                "{} is not a nepak archive",
+                path.display()
+            )));
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "{} has version {}, expected {}",
+                path.display(),
+                version,
+                VERSION
+            )));
+        }
+        let entry_count = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let index_offset = u64::from_le_bytes(header[16..24].try_into().unwrap());
+        let archive_len = reader.get_ref().metadata()?.len();
+        if index_offset < HEADER_SIZE || index_offset > archive_len {
+            return Err(invalid_data(format!(
+                "{} has its index outside of the archive",
+                path.display()
+            )));
+        }
+
+        reader.seek(SeekFrom::Start(index_offset))?;
+        // Every entry takes more than a byte of the index, a corrupt count can't reserve more.
+        let mut entries = Vec::with_capacity((entry_count as u64).min(archive_len) as usize);
+        for _ in 0..entry_count {
+            let path_len = u16::from_le_bytes(read_array(&mut reader)?);
+            let mut entry_path = vec![0; path_len as usize];
+            reader.read_exact(&mut entry_path)?;
+            let entry = PakEntry {
+                path: String::from_utf8(entry_path)
+                    .map_err(|_| invalid_data("entry path is not utf-8".to_string()))?,
+                offset: u64::from_le_bytes(read_array(&mut reader)?),
+                stored_size: u64::from_le_bytes(read_array(&mut reader)?),
+                size: u64::from_le_bytes(read_array(&mut reader)?),
+                compression: Compression::from_u8(read_array::<1>(&mut reader)?[0])?,
+                hash: read_array(&mut reader)?,
+            };
+            check_entry(&entry, index_offset)?;
+            entries.push(entry);
+        }
+        let index = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (entry.path.clone(), i))
+            .collect();
+        Ok(Self {
+            path,
+            entries,
+            index,
+        })
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// All files, sorted by path.
+    pub fn entries(&self) -> &[PakEntry] {
+        &self.entries
+    }
+    pub fn entry(&self, path: &Path) -> Option<&PakEntry> {
+        let path = normalize(path).ok()?;
+        self.index.get(&path).map(|&i| &self.entries[i])
+    }
+    pub fn contains(&self, path: &Path) -> bool {
+        self.entry(path).is_some()
+    }
+    /// Reads and decompresses a file, fails if it doesn't match its hash.
+    pub fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let entry = self.entry(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not in {}", path.display(), self.path.display()),
+            )
+        })?;
+        self.read_entry(entry)
+    }
+    pub fn read_entry(&self, entry: &PakEntry) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        // The archive may have been truncated since it was opened.
+        check_entry(entry, file.metadata()?.len())?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut stored = vec![0; entry.stored_size as usize];
+        file.read_exact(&mut stored)?;
+        let data = match entry.compression {
+            Compression::None => stored,
+            Compression::Lz4 => lz4_flex::decompress(&stored, entry.size as usize)
+                .map_err(|error| invalid_data(format!("{}: {}", entry.path, error)))?,
+        };
+        if blake3::hash(&data).as_bytes() != &entry.hash {
+            return Err(invalid_data(format!("{} is corrupted", entry.path)));
+        }
+        Ok(data)
+    }
+}
+
+/// What [`pack_dir`] wrote.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PackStats {
+    pub files: usize,
+    pub compressed_files: usize,
+    pub size: u64,
+    pub stored_size: u64,
+}
+
+/// Packs every file below `dir` into an archive at `output`.
+pub fn pack_dir(dir: &Path, output: &Path, compress: bool) -> io::Result<PackStats> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).follow_links(true) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    files.sort();
+    let mut writer = PakWriter::create(output)?;
+    let mut stats = PackStats::default();
+    for file in files {
+        let relative = file.strip_prefix(dir).unwrap();
+        let entry = writer.add(
+            &relative.to_string_lossy(),
+            &std::fs::read(&file)?,
+            compress,
+        )?;
+        stats.files += 1;
+        stats.size += entry.size;
+        stats.stored_size += entry.stored_size;
+        if entry.compression != Compression::None {
+            stats.compressed_files += 1;
+        }
+    }
+    writer.finish()?;
+    Ok(stats)
+}
+
+/// `path` as an archive path: relative, `/` separated, without `.` components.
+pub(crate) fn normalize(path: &Path) -> io::Result<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            _ => {
+                return Err(invalid_data(format!(
+                    "{} is not a relative path inside the archive",
+                    path.display()
+                )))
+            }
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Fails if the stored bytes of `entry` don't end before `data_end`, or its size doesn't fit
+/// them, so corrupt sizes are caught before anything is allocated for them.
+fn check_entry(entry: &PakEntry, data_end: u64) -> io::Result<()> {
+    let in_bounds = entry.offset >= HEADER_SIZE
+        && entry
+            .offset
+            .checked_add(entry.stored_size)
+            .is_some_and(|end| end <= data_end);
+    // lz4 doesn't compress better than 255 to 1.
+    let size_fits = match entry.compression {
+        Compression::None => entry.size == entry.stored_size,
+        Compression::Lz4 => entry.size <= entry.stored_size.saturating_mul(255),
+    };
+    if !in_bounds || !size_fits {
+        return Err(invalid_data(format!("{} has a corrupt size", entry.path)));
+    }
+    Ok(())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes an archive with a compressible and an incompressible file to a fresh path.
+    fn write_archive(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("ne_files_{}_{}.nepak", name, std::process::id()));
+        let mut writer = PakWriter::create(&path).unwrap();
+        writer.add("textures/grid.txt", &[7; 1000], true).unwrap();
+        writer.add("./a.bin", &[1, 2, 3], true).unwrap();
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = write_archive("round_trip");
+        let archive = PakArchive::open(&path).unwrap();
+        let paths: Vec<_> = archive
+            .entries()
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect();
+        assert_eq!(paths, ["a.bin", "textures/grid.txt"]);
+
+        let grid = archive.entry(Path::new("textures/grid.txt")).unwrap();
+        assert_eq!(grid.compression, Compression::Lz4);
+        assert!(grid.stored_size < grid.size);
+        assert_eq!(
+            archive.entry(Path::new("a.bin")).unwrap().compression,
+            Compression::None
+        );
+
+        assert_eq!(
+            archive.read(Path::new("textures/grid.txt")).unwrap(),
+            [7; 1000]
+        );
+        assert_eq!(archive.read(Path::new("./a.bin")).unwrap(), [1, 2, 3]);
+        let missing = archive.read(Path::new("b.bin")).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn duplicate_paths_are_rejected() {
+        let mut writer = PakWriter::new(io::Cursor::new(Vec::new())).unwrap();
+        writer.add("a/b.bin", &[1], false).unwrap();
+        let error = writer.add("a/./b.bin", &[2], false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn corrupt_index_is_rejected() {
+        let path = write_archive("corrupt_index");
+        let mut bytes = std::fs::read(&path).unwrap();
+        let index_offset = u64::from_le_bytes(bytes[16..24].try_into().unwrap()) as usize;
+        // The stored size of the first entry, after its path length, path and offset.
+        let path_len =
+            u16::from_le_bytes(bytes[index_offset..index_offset + 2].try_into().unwrap());
+        let stored_size = index_offset + 2 + path_len as usize + 8;
+        bytes[stored_size..stored_size + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let error = PakArchive::open(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_header_is_rejected() {
+        let path = write_archive("corrupt_header");
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(
+            PakArchive::open(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        bytes[0] = b'X';
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(
+            PakArchive::open(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_data_fails_the_hash() {
+        let path = write_archive("corrupt_data");
+        let archive = PakArchive::open(&path).unwrap();
+        let offset = archive.entry(Path::new("a.bin")).unwrap().offset as usize;
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[offset] ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+
+        let error = archive.read(Path::new("a.bin")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            archive.read(Path::new("textures/grid.txt")).unwrap(),
+            [7; 1000]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+//! A virtual file system over loose directories and `.nepak` archives, so code reading files
+//! doesn't care where the bytes come from.
+//!
+//! ```ignore
+//! let mut vfs = Vfs::new();
+//! vfs.mount_path(0, "engine_assets.nepak")?;
+//! vfs.mount_path(1, "my_game/assets")?; // overrides files of the archive
+//! let bytes = vfs.read("textures/grid.png")?;
+//! ```
+use std::{
+    collections::BTreeSet,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{asset_roots::AssetRoots, pak::PakArchive};
+
+/// A source of files in a [`Vfs`].
+pub trait Mount: Send + Sync + 'static {
+    /// Reads the file at the relative `path`, [`io::ErrorKind::NotFound`] if it isn't there.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn contains(&self, path: &Path) -> bool;
+    /// Relative paths of all files, with `/` separators.
+    fn files(&self) -> Vec<String>;
+    /// The directory on disk if the files are loose, changes to them can be watched.
+    fn dir(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Files in a directory on disk.
+#[derive(Debug, Clone)]
+pub struct DirMount {
+    root: PathBuf,
+}
+impl DirMount {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+impl Mount for DirMount {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.root.join(path))
+    }
+    fn contains(&self, path: &Path) -> bool {
+        self.root.join(path).is_file()
+    }
+    fn files(&self) -> Vec<String> {
+        walkdir::WalkDir::new(&self.root)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let relative = entry.path().strip_prefix(&self.root).ok()?;
+                crate::pak::normalize(relative).ok()
+            })
+            .collect()
+    }
+    fn dir(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+impl Mount for PakArchive {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        PakArchive::read(self, path)
+    }
+    fn contains(&self, path: &Path) -> bool {
+        PakArchive::contains(self, path)
+    }
+    fn files(&self) -> Vec<String> {
+        self.entries()
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+}
+
+/// Mounted directories and archives, files are read from the mount with the highest priority
+/// that has them.
+#[derive(Default)]
+pub struct Vfs {
+    /// Sorted by priority, highest first.
+    mounts: Vec<(i32, Box<dyn Mount>)>,
+}
+impl fmt::Debug for Vfs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.mounts
+                    .iter()
+                    .map(|(priority, mount)| (priority, mount.dir().map(Path::to_path_buf))),
+            )
+            .finish()
+    }
+}
+impl Vfs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Mounts the project root above the engine root.
+    pub fn from_roots(roots: &AssetRoots) -> Self {
+        let mut vfs = Self::new();
+        for (priority, root) in [(0, &roots.engine), (1, &roots.project)] {
+            if let Some(root) = root {
+                if let Err(error) = vfs.mount_path(priority, root) {
+                    tracing::warn!("can't mount {}: {}", root.display(), error);
+                }
+            }
+        }
+        vfs
+    }
+    /// Mounts `mount` below the mounts with a higher or the same priority.
+    pub fn mount(&mut self, priority: i32, mount: impl Mount) {
+        let index = self
+            .mounts
+            .iter()
+            .position(|(other, _)| *other < priority)
+            .unwrap_or(self.mounts.len());
+        self.mounts.insert(index, (priority, Box::new(mount)));
+    }
+    /// Mounts a directory, or an archive if `path` is a file.
+    pub fn mount_path(&mut self, priority: i32, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            self.mount(priority, DirMount::new(path));
+        } else {
+            self.mount(priority, PakArchive::open(path)?);
+        }
+        Ok(())
+    }
+    pub fn read(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        let path = path.as_ref();
+        match self.mounts.iter().find(|(_, mount)| mount.contains(path)) {
+            Some((_, mount)) => mount.read(path),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is in none of the mounts", path.display()),
+            )),
+        }
+    }
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        self.mounts.iter().any(|(_, mount)| mount.contains(path))
+    }
+    /// Relative paths of the files of all mounts, sorted and without duplicates.
+    pub fn files(&self) -> Vec<String> {
+        let files: BTreeSet<_> = self
+            .mounts
+            .iter()
+            .flat_map(|(_, mount)| mount.files())
+            .collect();
+        files.into_iter().collect()
+    }
+    /// Directories of the loose mounts, highest priority first.
+    pub fn dirs(&self) -> impl Iterator<Item = &Path> {
+        self.mounts.iter().filter_map(|(_, mount)| mount.dir())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// Files in memory, `files` maps paths to contents.
+    struct MemMount(HashMap<&'static str, &'static [u8]>);
+    impl MemMount {
+        fn new(files: &[(&'static str, &'static [u8])]) -> Self {
+            Self(files.iter().copied().collect())
+        }
+        fn get(&self, path: &Path) -> Option<&'static [u8]> {
+            self.0.get(path.to_str()?).copied()
+        }
+    }
+    impl Mount for MemMount {
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.get(path)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| io::ErrorKind::NotFound.into())
+        }
+        fn contains(&self, path: &Path) -> bool {
+            self.get(path).is_some()
+        }
+        fn files(&self) -> Vec<String> {
+            self.0.keys().map(|path| path.to_string()).collect()
+        }
+    }
+
+    #[test]
+    fn higher_priority_wins() {
+        let mut vfs = Vfs::new();
+        vfs.mount(1, MemMount::new(&[("a", b"project"), ("b", b"project")]));
+        vfs.mount(0, MemMount::new(&[("a", b"engine"), ("c", b"engine")]));
+        vfs.mount(2, MemMount::new(&[("b", b"patch")]));
+
+        assert_eq!(vfs.read("a").unwrap(), b"project");
+        assert_eq!(vfs.read("b").unwrap(), b"patch");
+        assert_eq!(vfs.read("c").unwrap(), b"engine");
+        assert_eq!(vfs.read("d").unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert!(vfs.contains("c") && !vfs.contains("d"));
+        assert_eq!(vfs.files(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn earlier_mount_wins_at_the_same_priority() {
+        let mut vfs = Vfs::new();
+        vfs.mount(0, MemMount::new(&[("a", b"first")]));
+        vfs.mount(0, MemMount::new(&[("a", b"second")]));
+        assert_eq!(vfs.read("a").unwrap(), b"first");
+    }
+}
@@ -26,6 +26,12 @@ impl Plugin for LogPlugin {
         tracing::subscriber::set_global_default(subscriber)
         .expect("tracing::subscriber::set_global_default failed. If tracing subscriber is already set, disable LogPlugin from DefaultPlugins");
         
+        //Plugins added earlier had no subscriber to log to.
+        let earlier_plugins = app.added_plugins().len() - 1;
+        if earlier_plugins > 0 {
+            warn!("LogPlugin should be added first, {} plugin(s) were set up without logging: {:?}",
+                earlier_plugins, &app.added_plugins()[..earlier_plugins]);
+        }
         trace!("Initialized logging [TRACE]");
         debug!("Initialized logging [DEBUG]");
         info!("Initialized logging [INFO]");
@@ -13,6 +13,11 @@ pub mod prelude {
     };
 }
 use bevy_ecs::prelude::Component;
+#[cfg(feature = "bevy_reflect")]
+use bevy_ecs::reflect::ReflectComponent;
+#[cfg(feature = "bevy_reflect")]
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
+use serde::{Deserialize, Serialize};
 pub use glam::*;
 pub use rand;
 
@@ -34,20 +39,94 @@ pub trait QuickMath {
     fn decrease_one(&mut self);
     fn randomize() -> Self;
 }
-//TODO scale.
-#[derive(Debug, Clone, Component)]
+/// Reflected as a struct, register [`Vec3`] and [`Quat`] with it to serialize it.
+#[derive(Debug, Clone, PartialEq, Component, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy_reflect", derive(Reflect), reflect(Component, Default))]
+#[serde(default)]
 pub struct Transform {
     pub pos: Vec3,
     pub rot: Quat,
+    /// Along the local axes, applied before `rot`.
+    pub scale: Vec3,
 }
 impl Default for Transform {
     fn default() -> Self {
         Self {
             pos: Vec3::ZERO,
             rot: Quat::default(),
+            scale: Vec3::ONE,
         }
     }
 }
+/// A half-line from `origin` along `direction`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    /// Normalized, so distances along the ray are in world units.
+    pub direction: Vec3,
+}
+impl Ray {
+    /// Normalizes `direction`.
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+    /// The point `distance` units along the ray.
+    pub fn get_point(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+    /// Distance to where the ray enters `aabb`, zero when it starts inside.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let inverse = self.direction.recip();
+        let to_min = (aabb.min - self.origin) * inverse;
+        let to_max = (aabb.max - self.origin) * inverse;
+        let enter = to_min.min(to_max).max_element().max(0.0);
+        let exit = to_min.max(to_max).min_element();
+        (enter <= exit).then_some(enter)
+    }
+    /// Distance to the triangle `[a, b, c]`, hit from either side.
+    pub fn intersect_triangle(&self, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+        let ab = b - a;
+        let ac = c - a;
+        let p = self.direction.cross(ac);
+        let determinant = ab.dot(p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse = determinant.recip();
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(p) * inverse;
+        let q = to_origin.cross(ab);
+        let v = self.direction.dot(q) * inverse;
+        if u < 0.0 || v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = ac.dot(q) * inverse;
+        (distance >= 0.0).then_some(distance)
+    }
+}
+/// An axis aligned box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+impl Aabb {
+    /// The smallest box around `points`, none without points.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        points.into_iter().fold(None, |aabb: Option<Self>, point| {
+            Some(aabb.map_or(Self { min: point, max: point }, |aabb| Self {
+                min: aabb.min.min(point),
+                max: aabb.max.max(point),
+            }))
+        })
+    }
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+}
 impl QuickMath for Transform {
     fn add_one(&mut self) {
         self.pos.add_one();
@@ -0,0 +1,158 @@
+//! A kinematic character controller: collide-and-slide movement with stepping up, slope limits,
+//! ground detection and gravity, for players and NPCs that shouldn't be pushed around like
+//! dynamic bodies.
+//!
+//! The character needs a [`Transform`], a [`Collider`] (usually a capsule) and
+//! [`RigidBody::KinematicPositionBased`](crate::RigidBody) so it pushes dynamic bodies aside:
+//! ```ignore
+//! app.world.spawn((
+//!     Transform::default(),
+//!     RigidBody::KinematicPositionBased,
+//!     Collider::capsule(1.8, 0.3),
+//!     CharacterController::default(),
+//! ));
+//! ```
+//! Gameplay code sets [`CharacterController::movement`] and [`CharacterController::jump`],
+//! the controller moves the character every fixed step.
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    reflect::ReflectComponent,
+    system::{Query, Res},
+};
+use bevy_reflect::Reflect;
+use ne_app::fixed_timestep::FixedTime;
+use ne_math::{Transform, Vec3};
+use rapier3d::{
+    control::{CharacterAutostep, CharacterLength, KinematicCharacterController},
+    prelude::{QueryFilter, Vector},
+};
+
+use crate::{from_vector, to_isometry, to_vector, Collider, PhysicsSettings, PhysicsWorld};
+
+/// Moves a character through the world without passing through colliders.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct CharacterController {
+    /// Horizontal velocity the character wants to move with, in world space.
+    pub movement: Vec3,
+    /// Jumps on the next fixed step if the character stands on the ground, reset after every step.
+    pub jump: bool,
+    /// Vertical speed of a jump.
+    pub jump_speed: f32,
+    /// Multiplies the gravity of [`PhysicsSettings`].
+    pub gravity_scale: f32,
+    /// Steepest slope in radians the character can walk up, it slides down steeper ones.
+    pub max_slope_angle: f32,
+    /// Highest ledge the character steps onto without jumping.
+    pub step_height: f32,
+    /// Keeps the character on the ground when walking down slopes and stairs up to this distance.
+    pub snap_to_ground: f32,
+    /// Gap kept between the character and the colliders around it.
+    pub skin_width: f32,
+    vertical_speed: f32,
+    grounded: bool,
+    velocity: Vec3,
+}
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            movement: Vec3::ZERO,
+            jump: false,
+            jump_speed: 5.0,
+            gravity_scale: 1.0,
+            max_slope_angle: 45.0_f32.to_radians(),
+            step_height: 0.3,
+            snap_to_ground: 0.2,
+            skin_width: 0.02,
+            vertical_speed: 0.0,
+            grounded: false,
+            velocity: Vec3::ZERO,
+        }
+    }
+}
+impl CharacterController {
+    /// Whether the character stood on the ground after the last step.
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+    /// The velocity the character actually moved with in the last step.
+    pub fn velocity(&self) -> Vec3 {
+        self.velocity
+    }
+    fn kinematic_controller(&self) -> KinematicCharacterController {
+        KinematicCharacterController {
+            up: Vector::y_axis(),
+            offset: CharacterLength::Absolute(self.skin_width),
+            slide: true,
+            autostep: Some(CharacterAutostep {
+                max_height: CharacterLength::Absolute(self.step_height),
+                min_width: CharacterLength::Absolute(self.skin_width * 5.0),
+                include_dynamic_bodies: false,
+            }),
+            max_slope_climb_angle: self.max_slope_angle,
+            min_slope_slide_angle: self.max_slope_angle,
+            snap_to_ground: Some(CharacterLength::Absolute(self.snap_to_ground)),
+        }
+    }
+}
+
+/// Moves every [`CharacterController`] by its movement, jump and gravity for one fixed step.
+pub fn character_controller_system(
+    physics: Res<PhysicsWorld>,
+    settings: Res<PhysicsSettings>,
+    fixed_time: Res<FixedTime>,
+    mut characters: Query<(Entity, &mut CharacterController, &mut Transform, &Collider)>,
+) {
+    if settings.paused {
+        return;
+    }
+    let dt = fixed_time.step_secs();
+    for (entity, mut controller, mut transform, collider) in &mut characters {
+        // Still rising from a jump the character may be reported as grounded for a step.
+        if controller.grounded && controller.vertical_speed <= 0.0 {
+            controller.vertical_speed = 0.0;
+            if controller.jump {
+                controller.vertical_speed = controller.jump_speed;
+            }
+        }
+        controller.jump = false;
+        controller.vertical_speed += settings.gravity.y * controller.gravity_scale * dt;
+
+        let desired = (controller.movement + Vec3::Y * controller.vertical_speed) * dt;
+        let own_collider = physics.collider_handle(entity);
+        let own_body = physics.body_handle(entity);
+        let mut filter = QueryFilter::new()
+            .exclude_sensors()
+            .groups(collider.groups.to_interaction_groups());
+        if let Some(own_collider) = own_collider {
+            filter = filter.exclude_collider(own_collider);
+        }
+        if let Some(own_body) = own_body {
+            filter = filter.exclude_rigid_body(own_body);
+        }
+        let Some(shape) = collider.shape.scaled(transform.scale).to_shared_shape() else {
+            continue;
+        };
+        let movement = controller.kinematic_controller().move_shape(
+            dt,
+            &physics.bodies,
+            &physics.colliders,
+            &physics.query_pipeline,
+            shape.as_ref(),
+            &to_isometry(&transform),
+            to_vector(desired),
+            filter,
+            |_| {},
+        );
+
+        let translation = from_vector(&movement.translation);
+        transform.pos += translation;
+        controller.velocity = translation / dt;
+        controller.grounded = movement.grounded;
+        // Bumping the head ends the jump.
+        if controller.vertical_speed > 0.0 && translation.y < desired.y * 0.5 {
+            controller.vertical_speed = 0.0;
+        }
+    }
+}
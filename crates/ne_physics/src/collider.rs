@@ -0,0 +1,226 @@
+//! Collision shapes attached to entities.
+use bevy_ecs::{component::Component, reflect::ReflectComponent};
+use bevy_reflect::{FromReflect, Reflect};
+use ne_math::Vec3;
+use rapier3d::prelude::{ActiveEvents, ColliderBuilder, Group, InteractionGroups, Point, SharedShape};
+
+/// The shape of a [`Collider`], centered on the entity and scaled by its
+/// [`Transform::scale`](ne_math::Transform::scale), see [`ColliderShape::scaled`].
+#[derive(Debug, Clone, PartialEq, Reflect, FromReflect)]
+pub enum ColliderShape {
+    /// A box, `half_extents` is half its size on each axis.
+    Cuboid { half_extents: Vec3 },
+    /// A sphere.
+    Ball { radius: f32 },
+    /// A capsule along the y axis, `half_height` excludes the rounded caps.
+    Capsule { half_height: f32, radius: f32 },
+    /// A triangle mesh, only collides properly with fixed or kinematic bodies.
+    TriMesh {
+        vertices: Vec<Vec3>,
+        indices: Vec<[u32; 3]>,
+    },
+}
+impl ColliderShape {
+    /// The shape scaled along its axes. Balls and capsules stay round, a ball takes the largest
+    /// scale and the radius of a capsule the larger of the x and z scale.
+    pub fn scaled(&self, scale: Vec3) -> Self {
+        let abs = scale.abs();
+        match self {
+            ColliderShape::Cuboid { half_extents } => ColliderShape::Cuboid {
+                half_extents: *half_extents * abs,
+            },
+            ColliderShape::Ball { radius } => ColliderShape::Ball {
+                radius: radius * abs.max_element(),
+            },
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => ColliderShape::Capsule {
+                half_height: half_height * abs.y,
+                radius: radius * abs.x.max(abs.z),
+            },
+            ColliderShape::TriMesh { vertices, indices } => ColliderShape::TriMesh {
+                vertices: vertices.iter().map(|vertex| *vertex * scale).collect(),
+                indices: indices.clone(),
+            },
+        }
+    }
+    /// A triangle mesh without triangles, or with indices past its vertices, has no shape.
+    pub fn is_degenerate(&self) -> bool {
+        match self {
+            ColliderShape::TriMesh { vertices, indices } => {
+                indices.is_empty()
+                    || indices
+                        .iter()
+                        .flatten()
+                        .any(|&index| index as usize >= vertices.len())
+            }
+            _ => false,
+        }
+    }
+    /// `None` for degenerate shapes, rapier panics on them.
+    pub(crate) fn to_shared_shape(&self) -> Option<SharedShape> {
+        if self.is_degenerate() {
+            return None;
+        }
+        Some(match self {
+            ColliderShape::Cuboid { half_extents } => {
+                SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z)
+            }
+            ColliderShape::Ball { radius } => SharedShape::ball(*radius),
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => SharedShape::capsule_y(*half_height, *radius),
+            ColliderShape::TriMesh { vertices, indices } => SharedShape::trimesh(
+                vertices
+                    .iter()
+                    .map(|vertex| Point::new(vertex.x, vertex.y, vertex.z))
+                    .collect(),
+                indices.clone(),
+            ),
+        })
+    }
+}
+
+/// The groups a collider is part of and the groups it interacts with, one bit per group.
+///
+/// Two colliders only interact if each one is part of a group the other one interacts with.
+/// [`SpatialQueryFilter`](crate::spatial_query::SpatialQueryFilter) uses the same test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, FromReflect)]
+pub struct CollisionGroups {
+    pub memberships: u32,
+    pub filters: u32,
+}
+impl CollisionGroups {
+    /// Part of and interacting with every group.
+    pub const ALL: Self = Self::new(u32::MAX, u32::MAX);
+    /// Part of no group, nothing interacts with it.
+    pub const NONE: Self = Self::new(0, 0);
+    pub const fn new(memberships: u32, filters: u32) -> Self {
+        Self {
+            memberships,
+            filters,
+        }
+    }
+    pub(crate) fn to_interaction_groups(self) -> InteractionGroups {
+        InteractionGroups::new(
+            Group::from_bits_truncate(self.memberships),
+            Group::from_bits_truncate(self.filters),
+        )
+    }
+}
+impl Default for CollisionGroups {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Makes an entity collide, together with a [`RigidBody`](crate::RigidBody) it moves with the body,
+/// without one it is a fixed collider placed at the entity's [`Transform`](ne_math::Transform).
+#[derive(Component, Debug, Clone, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub friction: f32,
+    pub restitution: f32,
+    /// Mass per volume, the mass of the body is computed from its colliders.
+    pub density: f32,
+    /// Sensors don't push anything away, they only report collision events.
+    pub sensor: bool,
+    pub groups: CollisionGroups,
+}
+impl Collider {
+    pub fn new(shape: ColliderShape) -> Self {
+        Self {
+            shape,
+            friction: 0.5,
+            restitution: 0.0,
+            density: 1.0,
+            sensor: false,
+            groups: CollisionGroups::ALL,
+        }
+    }
+    /// A box of the given size, matching `Shapes::create_box` with the same arguments.
+    pub fn cuboid(size_x: f32, size_y: f32, size_z: f32) -> Self {
+        Self::new(ColliderShape::Cuboid {
+            half_extents: Vec3::new(size_x, size_y, size_z) / 2.0,
+        })
+    }
+    pub fn ball(radius: f32) -> Self {
+        Self::new(ColliderShape::Ball { radius })
+    }
+    /// A capsule along the y axis that is `height` tall including the caps.
+    pub fn capsule(height: f32, radius: f32) -> Self {
+        Self::new(ColliderShape::Capsule {
+            half_height: (height / 2.0 - radius).max(0.0),
+            radius,
+        })
+    }
+    /// Fails without triangles or with indices past the vertices.
+    pub fn trimesh(vertices: Vec<Vec3>, indices: Vec<[u32; 3]>) -> anyhow::Result<Self> {
+        let shape = ColliderShape::TriMesh { vertices, indices };
+        if shape.is_degenerate() {
+            anyhow::bail!("a triangle mesh collider needs triangles indexing its vertices");
+        }
+        Ok(Self::new(shape))
+    }
+    /// A triangle mesh with the triangles of a render mesh, fails on a mesh without triangles.
+    #[cfg(feature = "mesh_colliders")]
+    pub fn from_mesh(mesh: &ne_render::mesh::MeshPrimitives) -> anyhow::Result<Self> {
+        let vertices = mesh
+            .vertices()
+            .iter()
+            .map(|vertex| Vec3::from(vertex.position()))
+            .collect();
+        let indices: Vec<u32> = mesh.indices().collect();
+        let indices = indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+        Self::trimesh(vertices, indices)
+    }
+    #[must_use]
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+    #[must_use]
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+    #[must_use]
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+    #[must_use]
+    pub fn sensor(mut self) -> Self {
+        self.sensor = true;
+        self
+    }
+    #[must_use]
+    pub fn with_groups(mut self, groups: CollisionGroups) -> Self {
+        self.groups = groups;
+        self
+    }
+    /// `None` for a degenerate shape, see [`ColliderShape::is_degenerate`].
+    pub(crate) fn to_builder(&self, scale: Vec3) -> Option<ColliderBuilder> {
+        let shape = self.shape.scaled(scale).to_shared_shape()?;
+        let builder = ColliderBuilder::new(shape)
+            .friction(self.friction)
+            .restitution(self.restitution)
+            .density(self.density)
+            .sensor(self.sensor)
+            .collision_groups(self.groups.to_interaction_groups())
+            .active_events(ActiveEvents::COLLISION_EVENTS);
+        Some(builder)
+    }
+}
+/// A unit box.
+impl Default for Collider {
+    fn default() -> Self {
+        Self::cuboid(1.0, 1.0, 1.0)
+    }
+}
@@ -0,0 +1,546 @@
+//! Rigid body physics with [rapier](https://rapier.rs).
+//!
+//! Entities with a [`RigidBody`] and/or a [`Collider`] are added to a rapier world that is
+//! stepped in [`CoreStage::FixedUpdate`] while the game plays, after every step the positions of
+//! moving bodies are written back into their [`Transform`]. When the editor stops playing the
+//! rapier world is rebuilt from the components it restored.
+//! ```ignore
+//! app.add_plugin(PhysicsPlugin);
+//! app.world.spawn((Transform::default(), RigidBody::Dynamic, Collider::ball(0.5)));
+//! ```
+//! Changing the [`Transform`] or [`Velocity`] of an entity moves it in the physics world too,
+//! the scale of the [`Transform`] scales its [`Collider`].
+//! Use the [`SpatialQuery`] system parameter for raycasts and other queries, and a
+//! [`CharacterController`] for walking characters.
+pub mod character;
+pub mod collider;
+pub mod spatial_query;
+
+use std::collections::HashMap;
+
+use bevy_ecs::{
+    change_detection::DetectChanges,
+    component::Component,
+    entity::Entity,
+    event::EventWriter,
+    query::{Changed, With},
+    reflect::ReflectComponent,
+    schedule::{IntoSystemDescriptor, SystemLabel},
+    system::{Query, RemovedComponents, Res, ResMut, Resource},
+};
+use bevy_reflect::Reflect;
+use ne_app::{
+    fixed_timestep::FixedTime,
+    play::{self, PlayState},
+    plugin_group::plugin_name,
+    state::SystemSet,
+    time::TimePlugin,
+    App, CoreStage, Plugin,
+};
+use ne_math::{Quat, Transform, Vec3};
+use rapier3d::{
+    crossbeam::channel::{unbounded, Receiver},
+    prelude::*,
+};
+use tracing::warn;
+
+pub use character::CharacterController;
+pub use collider::{Collider, ColliderShape, CollisionGroups};
+pub use spatial_query::{PointProjection, RayHit, SpatialQuery, SpatialQueryFilter};
+
+/// Adds [`PhysicsWorld`] and steps it at the [`FixedTime`] rate.
+///
+/// Reads [`PhysicsSettings`] every step, it is inserted with the defaults if missing.
+pub struct PhysicsPlugin;
+impl Plugin for PhysicsPlugin {
+    fn setup(&self, app: &mut App) {
+        app.register_type::<RigidBody>()
+            .register_type::<Velocity>()
+            .register_type::<Collider>()
+            .register_type::<ColliderShape>()
+            .register_type::<CollisionGroups>()
+            .register_type::<CharacterController>()
+            .register_type::<Vec3>()
+            .register_type::<Vec<Vec3>>()
+            .register_type::<[u32; 3]>()
+            .register_type::<Vec<[u32; 3]>>()
+            .init_resource::<PhysicsSettings>()
+            .init_resource::<PhysicsWorld>()
+            .add_event::<OnCollisionStart>()
+            .add_event::<OnCollisionStop>()
+            .add_system_to_stage(CoreStage::PreUpdate, reset_physics_system)
+            .add_system_to_stage(CoreStage::PostUpdate, remove_from_physics_system)
+            .add_system_set_to_stage(
+                CoreStage::FixedUpdate,
+                SystemSet::new()
+                    .with_run_criteria(play::gameplay_run_criteria)
+                    .with_system(
+                        character::character_controller_system
+                            .label(PhysicsSystem::MoveCharacters)
+                            .before(PhysicsSystem::SyncToPhysics),
+                    )
+                    .with_system(sync_to_physics_system.label(PhysicsSystem::SyncToPhysics))
+                    .with_system(
+                        step_physics_system
+                            .label(PhysicsSystem::Step)
+                            .after(PhysicsSystem::SyncToPhysics),
+                    )
+                    .with_system(
+                        sync_from_physics_system
+                            .label(PhysicsSystem::SyncFromPhysics)
+                            .after(PhysicsSystem::Step),
+                    ),
+            );
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![plugin_name::<TimePlugin>()]
+    }
+}
+
+/// The systems of [`PhysicsPlugin`] in the order they run, gameplay systems in
+/// [`CoreStage::FixedUpdate`] should run before [`PhysicsSystem::MoveCharacters`]. They only
+/// run while the game plays, see [`play::gameplay_run_criteria`].
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum PhysicsSystem {
+    /// Moves the [`CharacterController`]s.
+    MoveCharacters,
+    /// Creates and updates rapier bodies and colliders to match the components, removed ones
+    /// are removed from the rapier world in [`CoreStage::PostUpdate`].
+    SyncToPhysics,
+    /// Steps the simulation by [`FixedTime::step`].
+    Step,
+    /// Writes body positions and velocities back into [`Transform`] and [`Velocity`].
+    SyncFromPhysics,
+}
+
+/// Configures the simulation.
+#[derive(Debug, Clone, Resource)]
+pub struct PhysicsSettings {
+    pub gravity: Vec3,
+    /// Stops stepping the simulation, bodies keep their velocities.
+    pub paused: bool,
+}
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            gravity: Vec3::new(0.0, -9.81, 0.0),
+            paused: false,
+        }
+    }
+}
+
+/// How a body moves.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub enum RigidBody {
+    /// Moved by forces, gravity and collisions.
+    #[default]
+    Dynamic,
+    /// Never moves.
+    Fixed,
+    /// Follows its [`Transform`], pushes dynamic bodies out of the way.
+    KinematicPositionBased,
+    /// Moves with its [`Velocity`], pushes dynamic bodies out of the way.
+    KinematicVelocityBased,
+}
+impl RigidBody {
+    fn body_type(self) -> RigidBodyType {
+        match self {
+            RigidBody::Dynamic => RigidBodyType::Dynamic,
+            RigidBody::Fixed => RigidBodyType::Fixed,
+            RigidBody::KinematicPositionBased => RigidBodyType::KinematicPositionBased,
+            RigidBody::KinematicVelocityBased => RigidBodyType::KinematicVelocityBased,
+        }
+    }
+}
+
+/// Linear velocity in units per second and angular velocity in radians per second around each axis.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Velocity {
+    pub linear: Vec3,
+    pub angular: Vec3,
+}
+impl Velocity {
+    pub fn linear(linear: Vec3) -> Self {
+        Self {
+            linear,
+            angular: Vec3::ZERO,
+        }
+    }
+}
+
+/// Sent when two colliders start touching, or start overlapping if one of them is a sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OnCollisionStart {
+    pub entity1: Entity,
+    pub entity2: Entity,
+    pub sensor: bool,
+}
+/// Sent when two colliders stop touching, also when one of them is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OnCollisionStop {
+    pub entity1: Entity,
+    pub entity2: Entity,
+    pub sensor: bool,
+}
+
+/// The rapier simulation and the mapping between entities and rapier handles.
+#[derive(Resource)]
+pub struct PhysicsWorld {
+    pub bodies: RigidBodySet,
+    pub colliders: ColliderSet,
+    pub query_pipeline: QueryPipeline,
+    pub integration_parameters: IntegrationParameters,
+    pipeline: PhysicsPipeline,
+    islands: IslandManager,
+    broad_phase: BroadPhase,
+    narrow_phase: NarrowPhase,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    event_collector: ChannelEventCollector,
+    collision_events: Receiver<CollisionEvent>,
+    contact_force_events: Receiver<ContactForceEvent>,
+    entity_to_body: HashMap<Entity, RigidBodyHandle>,
+    entity_to_collider: HashMap<Entity, ColliderHandle>,
+    /// The scale the shape of each collider was built with.
+    collider_scales: HashMap<Entity, Vec3>,
+    /// Colliders are looked up here instead of through the collider set, the stop events of
+    /// removed colliders only arrive in the next step.
+    collider_to_entity: HashMap<ColliderHandle, Entity>,
+    removed_colliders: Vec<ColliderHandle>,
+}
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        let (collision_sender, collision_events) = unbounded();
+        let (contact_force_sender, contact_force_events) = unbounded();
+        Self {
+            bodies: RigidBodySet::new(),
+            colliders: ColliderSet::new(),
+            query_pipeline: QueryPipeline::new(),
+            integration_parameters: IntegrationParameters::default(),
+            pipeline: PhysicsPipeline::new(),
+            islands: IslandManager::new(),
+            broad_phase: BroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            impulse_joints: ImpulseJointSet::new(),
+            multibody_joints: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            event_collector: ChannelEventCollector::new(collision_sender, contact_force_sender),
+            collision_events,
+            contact_force_events,
+            entity_to_body: HashMap::new(),
+            entity_to_collider: HashMap::new(),
+            collider_scales: HashMap::new(),
+            collider_to_entity: HashMap::new(),
+            removed_colliders: Vec::new(),
+        }
+    }
+}
+impl PhysicsWorld {
+    pub fn body_handle(&self, entity: Entity) -> Option<RigidBodyHandle> {
+        self.entity_to_body.get(&entity).copied()
+    }
+    pub fn collider_handle(&self, entity: Entity) -> Option<ColliderHandle> {
+        self.entity_to_collider.get(&entity).copied()
+    }
+    /// The entity a collider belongs to.
+    pub fn collider_entity(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.collider_to_entity.get(&handle).copied()
+    }
+    fn insert_body(&mut self, entity: Entity, body: RigidBody, transform: &Transform, velocity: Velocity) {
+        let handle = self.bodies.insert(
+            RigidBodyBuilder::new(body.body_type())
+                .position(to_isometry(transform))
+                .linvel(to_vector(velocity.linear))
+                .angvel(to_vector(velocity.angular))
+                .user_data(entity.to_bits() as u128),
+        );
+        self.entity_to_body.insert(entity, handle);
+        // A collider that was added before the body has to move onto it.
+        if let Some(collider) = self.entity_to_collider.get(&entity).copied() {
+            self.colliders
+                .set_parent(collider, Some(handle), &mut self.bodies);
+            if let Some(collider) = self.colliders.get_mut(collider) {
+                collider.set_position_wrt_parent(Isometry::identity());
+            }
+        }
+    }
+    fn remove_body(&mut self, entity: Entity) {
+        let Some(handle) = self.entity_to_body.remove(&entity) else {
+            return;
+        };
+        // Colliders stay, they become fixed colliders until they are removed as well.
+        self.bodies.remove(
+            handle,
+            &mut self.islands,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            false,
+        );
+    }
+    fn insert_collider(&mut self, entity: Entity, collider: &Collider, transform: &Transform) {
+        self.remove_collider(entity);
+        let Some(builder) = collider.to_builder(transform.scale) else {
+            warn!("the collider of {entity:?} is a degenerate triangle mesh, it is skipped");
+            return;
+        };
+        let builder = builder.user_data(entity.to_bits() as u128);
+        let handle = match self.entity_to_body.get(&entity) {
+            Some(&body) => self
+                .colliders
+                .insert_with_parent(builder, body, &mut self.bodies),
+            None => self
+                .colliders
+                .insert(builder.position(to_isometry(transform))),
+        };
+        self.entity_to_collider.insert(entity, handle);
+        self.collider_scales.insert(entity, transform.scale);
+        self.collider_to_entity.insert(handle, entity);
+    }
+    /// Rebuilds the shape of the collider of `entity` if it was built with another scale.
+    fn set_collider_scale(&mut self, entity: Entity, collider: &Collider, scale: Vec3) {
+        if self.collider_scales.get(&entity) == Some(&scale) {
+            return;
+        }
+        let Some(rapier_collider) = self
+            .collider_handle(entity)
+            .and_then(|handle| self.colliders.get_mut(handle))
+        else {
+            return;
+        };
+        // Degenerate shapes aren't inserted, they have no rapier collider to get here.
+        if let Some(shape) = collider.shape.scaled(scale).to_shared_shape() {
+            rapier_collider.set_shape(shape);
+        }
+        self.collider_scales.insert(entity, scale);
+    }
+    fn remove_collider(&mut self, entity: Entity) {
+        let Some(handle) = self.entity_to_collider.remove(&entity) else {
+            return;
+        };
+        self.collider_scales.remove(&entity);
+        self.colliders
+            .remove(handle, &mut self.islands, &mut self.bodies, true);
+        self.removed_colliders.push(handle);
+    }
+    fn step(&mut self, gravity: Vec3) {
+        self.pipeline.step(
+            &to_vector(gravity),
+            &self.integration_parameters,
+            &mut self.islands,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.bodies,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            &mut self.ccd_solver,
+            Some(&mut self.query_pipeline),
+            &(),
+            &self.event_collector,
+        );
+    }
+}
+
+/// Creates, updates and removes rapier bodies and colliders to match the components.
+#[allow(clippy::type_complexity)]
+pub fn sync_to_physics_system(
+    mut physics: ResMut<PhysicsWorld>,
+    bodies: Query<(Entity, &RigidBody, Option<&Transform>, Option<&Velocity>), Changed<RigidBody>>,
+    colliders: Query<(Entity, &Collider, Option<&Transform>), Changed<Collider>>,
+    transforms: Query<(Entity, &Transform, Option<&Collider>), Changed<Transform>>,
+    velocities: Query<(Entity, &Velocity), Changed<Velocity>>,
+) {
+    let physics = &mut *physics;
+    for (entity, body, transform, velocity) in &bodies {
+        match physics.body_handle(entity) {
+            Some(handle) => {
+                if let Some(rapier_body) = physics.bodies.get_mut(handle) {
+                    rapier_body.set_body_type(body.body_type(), true);
+                }
+            }
+            None => physics.insert_body(
+                entity,
+                *body,
+                transform.unwrap_or(&Transform::default()),
+                velocity.copied().unwrap_or_default(),
+            ),
+        }
+    }
+    for (entity, collider, transform) in &colliders {
+        physics.insert_collider(entity, collider, transform.unwrap_or(&Transform::default()));
+    }
+
+    for (entity, transform, collider) in &transforms {
+        if let Some(collider) = collider {
+            physics.set_collider_scale(entity, collider, transform.scale);
+        }
+        let position = to_isometry(transform);
+        if let Some(body) = physics
+            .body_handle(entity)
+            .and_then(|handle| physics.bodies.get_mut(handle))
+        {
+            // Transforms written back after the last step are changed too, leave those alone.
+            if same_position(body.position(), &position) {
+                continue;
+            }
+            if body.body_type() == RigidBodyType::KinematicPositionBased {
+                body.set_next_kinematic_position(position);
+            } else {
+                body.set_position(position, true);
+            }
+        } else if let Some(collider) = physics
+            .collider_handle(entity)
+            .and_then(|handle| physics.colliders.get_mut(handle))
+        {
+            collider.set_position(position);
+        }
+    }
+    for (entity, velocity) in &velocities {
+        if let Some(body) = physics
+            .body_handle(entity)
+            .and_then(|handle| physics.bodies.get_mut(handle))
+        {
+            let (linear, angular) = (to_vector(velocity.linear), to_vector(velocity.angular));
+            if *body.linvel() != linear {
+                body.set_linvel(linear, true);
+            }
+            if *body.angvel() != angular {
+                body.set_angvel(angular, true);
+            }
+        }
+    }
+}
+
+/// Removes the bodies and colliders of removed components. Runs every frame, removals are only
+/// tracked until the end of the frame and the fixed steps may not run in it.
+pub fn remove_from_physics_system(
+    mut physics: ResMut<PhysicsWorld>,
+    removed_bodies: RemovedComponents<RigidBody>,
+    removed_colliders: RemovedComponents<Collider>,
+) {
+    for entity in removed_colliders.iter() {
+        physics.remove_collider(entity);
+    }
+    for entity in removed_bodies.iter() {
+        physics.remove_body(entity);
+    }
+}
+
+/// Empties the rapier world when the editor stops playing, the bodies and colliders are created
+/// again from the restored components, at their restored [`Transform`], when it plays next.
+pub fn reset_physics_system(
+    play_state: Option<Res<PlayState>>,
+    mut physics: ResMut<PhysicsWorld>,
+    mut bodies: Query<&mut RigidBody>,
+    mut colliders: Query<&mut Collider>,
+) {
+    if !play_state.is_some_and(|state| state.is_changed() && *state == PlayState::Editing) {
+        return;
+    }
+    *physics = PhysicsWorld::default();
+    bodies.for_each_mut(|mut body| body.set_changed());
+    colliders.for_each_mut(|mut collider| collider.set_changed());
+}
+
+/// Steps the simulation and sends the collision events of the step.
+pub fn step_physics_system(
+    mut physics: ResMut<PhysicsWorld>,
+    settings: Res<PhysicsSettings>,
+    fixed_time: Res<FixedTime>,
+    mut collision_start: EventWriter<OnCollisionStart>,
+    mut collision_stop: EventWriter<OnCollisionStop>,
+) {
+    let physics = &mut *physics;
+    if settings.paused {
+        // Queries should still see added and moved colliders.
+        physics
+            .query_pipeline
+            .update(&physics.bodies, &physics.colliders);
+        return;
+    }
+    physics.integration_parameters.dt = fixed_time.step_secs();
+    physics.step(settings.gravity);
+
+    while let Ok(event) = physics.collision_events.try_recv() {
+        let (Some(entity1), Some(entity2)) = (
+            physics.collider_entity(event.collider1()),
+            physics.collider_entity(event.collider2()),
+        ) else {
+            continue;
+        };
+        match event {
+            CollisionEvent::Started(_, _, flags) => collision_start.send(OnCollisionStart {
+                entity1,
+                entity2,
+                sensor: flags.contains(CollisionEventFlags::SENSOR),
+            }),
+            CollisionEvent::Stopped(_, _, flags) => collision_stop.send(OnCollisionStop {
+                entity1,
+                entity2,
+                sensor: flags.contains(CollisionEventFlags::SENSOR),
+            }),
+        }
+    }
+    while physics.contact_force_events.try_recv().is_ok() {}
+    for handle in physics.removed_colliders.drain(..) {
+        physics.collider_to_entity.remove(&handle);
+    }
+}
+
+/// Writes the simulated positions and velocities back into the components.
+pub fn sync_from_physics_system(
+    physics: Res<PhysicsWorld>,
+    mut bodies: Query<(Entity, &mut Transform, Option<&mut Velocity>), With<RigidBody>>,
+) {
+    for (entity, mut transform, velocity) in &mut bodies {
+        let Some(body) = physics
+            .body_handle(entity)
+            .and_then(|handle| physics.bodies.get(handle))
+        else {
+            continue;
+        };
+        if !matches!(
+            body.body_type(),
+            RigidBodyType::Dynamic | RigidBodyType::KinematicVelocityBased
+        ) || body.is_sleeping()
+        {
+            continue;
+        }
+        let position = body.position();
+        transform.pos = from_vector(&position.translation.vector);
+        transform.rot = from_rotation(&position.rotation);
+        if let Some(mut velocity) = velocity {
+            velocity.linear = from_vector(body.linvel());
+            velocity.angular = from_vector(body.angvel());
+        }
+    }
+}
+
+fn same_position(a: &Isometry<Real>, b: &Isometry<Real>) -> bool {
+    const EPSILON: Real = 1.0e-6;
+    (a.translation.vector - b.translation.vector).norm_squared() < EPSILON
+        && a.rotation.angle_to(&b.rotation) < EPSILON
+}
+
+pub(crate) fn to_vector(v: Vec3) -> Vector<Real> {
+    vector![v.x, v.y, v.z]
+}
+pub(crate) fn from_vector(v: &Vector<Real>) -> Vec3 {
+    Vec3::new(v.x, v.y, v.z)
+}
+pub(crate) fn to_rotation(q: Quat) -> Rotation<Real> {
+    Rotation::from_quaternion(nalgebra::Quaternion::new(q.w, q.x, q.y, q.z))
+}
+pub(crate) fn from_rotation(r: &Rotation<Real>) -> Quat {
+    Quat::from_xyzw(r.i, r.j, r.k, r.w)
+}
+pub(crate) fn to_isometry(transform: &Transform) -> Isometry<Real> {
+    Isometry::from_parts(
+        to_vector(transform.pos).into(),
+        to_rotation(transform.rot),
+    )
+}
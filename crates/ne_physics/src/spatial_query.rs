@@ -0,0 +1,294 @@
+//! Raycasts, shape casts, point projections and overlap tests against the colliders of the world.
+//!
+//! The queries see the colliders as they were after the last physics step, colliders added or
+//! moved since then are found after the next one.
+use std::marker::PhantomData;
+
+use bevy_ecs::{
+    entity::Entity,
+    system::{Res, SystemParam},
+};
+use ne_math::{Quat, Ray, Vec3};
+use rapier3d::prelude::{
+    Collider as RapierCollider, ColliderHandle, Isometry, Point, QueryFilter, Ray as RapierRay,
+};
+
+use crate::{
+    collider::{ColliderShape, CollisionGroups},
+    from_vector, to_rotation, to_vector, PhysicsWorld,
+};
+
+/// Which colliders a query considers.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialQueryFilter {
+    /// Only colliders that interact with these groups are hit.
+    pub groups: CollisionGroups,
+    /// Colliders of these entities are ignored, e.g. the entity casting the ray.
+    pub excluded: Vec<Entity>,
+    pub exclude_sensors: bool,
+}
+impl SpatialQueryFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    #[must_use]
+    pub fn with_groups(mut self, groups: CollisionGroups) -> Self {
+        self.groups = groups;
+        self
+    }
+    #[must_use]
+    pub fn exclude(mut self, entity: Entity) -> Self {
+        self.excluded.push(entity);
+        self
+    }
+    #[must_use]
+    pub fn without_sensors(mut self) -> Self {
+        self.exclude_sensors = true;
+        self
+    }
+    fn is_excluded(&self, collider: &RapierCollider) -> bool {
+        let entity = Entity::from_bits(collider.user_data as u64);
+        self.excluded.contains(&entity)
+    }
+}
+
+/// A collider hit by a ray or a shape cast.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub entity: Entity,
+    /// Where the ray hit the collider, or where the cast shape touched it.
+    pub point: Vec3,
+    /// Surface normal of the collider at `point`.
+    pub normal: Vec3,
+    /// Distance travelled along the ray or cast direction.
+    pub distance: f32,
+}
+
+/// The closest point on a collider to a given point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointProjection {
+    pub entity: Entity,
+    pub point: Vec3,
+    /// Whether the projected point was inside the collider.
+    pub is_inside: bool,
+    pub distance: f32,
+}
+
+/// Spatial queries against the colliders of the [`PhysicsWorld`].
+///
+/// ```ignore
+/// fn shoot(spatial_query: SpatialQuery, player: Query<(Entity, &Transform), With<Player>>) {
+///     let (entity, transform) = player.single();
+///     let ray = Ray::new(transform.pos, transform.rot * Vec3::NEG_Z);
+///     let filter = SpatialQueryFilter::new().exclude(entity);
+///     if let Some(hit) = spatial_query.cast_ray(ray, 100.0, &filter) {
+///         info!("hit {:?} at {}", hit.entity, hit.point);
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct SpatialQuery<'w, 's> {
+    physics: Res<'w, PhysicsWorld>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+impl<'w, 's> SpatialQuery<'w, 's> {
+    fn with_filter<R>(
+        &self,
+        filter: &SpatialQueryFilter,
+        query: impl FnOnce(&PhysicsWorld, QueryFilter) -> R,
+    ) -> R {
+        let predicate = |_: ColliderHandle, collider: &RapierCollider| !filter.is_excluded(collider);
+        let mut rapier_filter = QueryFilter::new()
+            .groups(filter.groups.to_interaction_groups())
+            .predicate(&predicate);
+        if filter.exclude_sensors {
+            rapier_filter = rapier_filter.exclude_sensors();
+        }
+        query(&self.physics, rapier_filter)
+    }
+    fn entity(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.physics.collider_entity(handle)
+    }
+
+    /// The first collider along `ray` within `max_distance`.
+    pub fn cast_ray(
+        &self,
+        ray: Ray,
+        max_distance: f32,
+        filter: &SpatialQueryFilter,
+    ) -> Option<RayHit> {
+        let rapier_ray = to_rapier_ray(ray);
+        let (handle, intersection) = self.with_filter(filter, |physics, rapier_filter| {
+            physics.query_pipeline.cast_ray_and_get_normal(
+                &physics.bodies,
+                &physics.colliders,
+                &rapier_ray,
+                max_distance,
+                true,
+                rapier_filter,
+            )
+        })?;
+        Some(RayHit {
+            entity: self.entity(handle)?,
+            point: ray.get_point(intersection.toi),
+            normal: from_vector(&intersection.normal),
+            distance: intersection.toi,
+        })
+    }
+
+    /// Every collider along `ray` within `max_distance`, closest first.
+    pub fn cast_ray_all(
+        &self,
+        ray: Ray,
+        max_distance: f32,
+        filter: &SpatialQueryFilter,
+    ) -> Vec<RayHit> {
+        let rapier_ray = to_rapier_ray(ray);
+        let mut hits = Vec::new();
+        self.with_filter(filter, |physics, rapier_filter| {
+            physics.query_pipeline.intersections_with_ray(
+                &physics.bodies,
+                &physics.colliders,
+                &rapier_ray,
+                max_distance,
+                true,
+                rapier_filter,
+                |handle, intersection| {
+                    if let Some(entity) = self.entity(handle) {
+                        hits.push(RayHit {
+                            entity,
+                            point: ray.get_point(intersection.toi),
+                            normal: from_vector(&intersection.normal),
+                            distance: intersection.toi,
+                        });
+                    }
+                    true
+                },
+            );
+        });
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    /// Moves `shape` from `position` along `direction` and returns the first collider it touches
+    /// within `max_distance`, nothing for a zero `direction`.
+    pub fn cast_shape(
+        &self,
+        shape: &ColliderShape,
+        position: Vec3,
+        rotation: Quat,
+        direction: Vec3,
+        max_distance: f32,
+        filter: &SpatialQueryFilter,
+    ) -> Option<RayHit> {
+        let shape = shape.to_shared_shape()?;
+        let direction = direction.normalize_or_zero();
+        if direction == Vec3::ZERO {
+            return None;
+        }
+        let shape_pos = to_isometry(position, rotation);
+        let (handle, toi) = self.with_filter(filter, |physics, rapier_filter| {
+            physics.query_pipeline.cast_shape(
+                &physics.bodies,
+                &physics.colliders,
+                &shape_pos,
+                &to_vector(direction),
+                shape.as_ref(),
+                max_distance,
+                true,
+                rapier_filter,
+            )
+        })?;
+        // The witness and normal of the cast shape are in its own space, at the time of impact.
+        let hit_pos = to_isometry(position + direction * toi.toi, rotation);
+        let point = hit_pos * toi.witness2;
+        let normal = -(hit_pos.rotation * toi.normal2.into_inner());
+        Some(RayHit {
+            entity: self.entity(handle)?,
+            point: from_vector(&point.coords),
+            normal: from_vector(&normal),
+            distance: toi.toi,
+        })
+    }
+
+    /// The closest point on any collider to `point`. With `solid` a point inside a collider
+    /// projects onto itself, otherwise onto the collider's surface.
+    pub fn project_point(
+        &self,
+        point: Vec3,
+        solid: bool,
+        filter: &SpatialQueryFilter,
+    ) -> Option<PointProjection> {
+        let (handle, projection) = self.with_filter(filter, |physics, rapier_filter| {
+            physics.query_pipeline.project_point(
+                &physics.bodies,
+                &physics.colliders,
+                &Point::from(to_vector(point)),
+                solid,
+                rapier_filter,
+            )
+        })?;
+        let projected = from_vector(&projection.point.coords);
+        Some(PointProjection {
+            entity: self.entity(handle)?,
+            point: projected,
+            is_inside: projection.is_inside,
+            distance: projected.distance(point),
+        })
+    }
+
+    /// Entities whose colliders contain `point`.
+    pub fn point_overlaps(&self, point: Vec3, filter: &SpatialQueryFilter) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        self.with_filter(filter, |physics, rapier_filter| {
+            physics.query_pipeline.intersections_with_point(
+                &physics.bodies,
+                &physics.colliders,
+                &Point::from(to_vector(point)),
+                rapier_filter,
+                |handle| {
+                    entities.extend(self.entity(handle));
+                    true
+                },
+            );
+        });
+        entities
+    }
+
+    /// Entities whose colliders overlap `shape` placed at `position`.
+    pub fn shape_overlaps(
+        &self,
+        shape: &ColliderShape,
+        position: Vec3,
+        rotation: Quat,
+        filter: &SpatialQueryFilter,
+    ) -> Vec<Entity> {
+        let Some(shape) = shape.to_shared_shape() else {
+            return Vec::new();
+        };
+        let shape_pos = to_isometry(position, rotation);
+        let mut entities = Vec::new();
+        self.with_filter(filter, |physics, rapier_filter| {
+            physics.query_pipeline.intersections_with_shape(
+                &physics.bodies,
+                &physics.colliders,
+                &shape_pos,
+                shape.as_ref(),
+                rapier_filter,
+                |handle| {
+                    entities.extend(self.entity(handle));
+                    true
+                },
+            );
+        });
+        entities
+    }
+}
+
+fn to_rapier_ray(ray: Ray) -> RapierRay {
+    RapierRay::new(Point::from(to_vector(ray.origin)), to_vector(ray.direction))
+}
+fn to_isometry(position: Vec3, rotation: Quat) -> Isometry<f32> {
+    Isometry::from_parts(to_vector(position).into(), to_rotation(rotation))
+}
@@ -0,0 +1,305 @@
+//! Projects: a game described by a `.neproject` manifest, with its asset roots, startup scene,
+//! input bindings and build settings.
+//!
+//! ```toml
+//! name = "Walker"
+//! startup_scene = "scenes/main.nscene"
+//!
+//! [assets]
+//! project = "assets"
+//! engine = "../engine_assets"
+//!
+//! [input]
+//! jump = ["Space"]
+//! fire = ["Mouse:Left", "LControl"]
+//!
+//! [build]
+//! output = "build"
+//! compress_textures = true
+//! ```
+//! Paths are relative to the manifest. A game is launched from its manifest with
+//! ```ignore
+//! let project = Project::open("walker.neproject")?;
+//! App::new()
+//!     .insert_resource(project.asset_settings())
+//!     .insert_resource(project.input_map(PlayerAction::from_name))
+//!     .insert_resource(project)
+//!     .add_plugins(DefaultPlugins)
+//!     .add_plugin(ProjectPlugin)
+//!     .run();
+//! ```
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use bevy_ecs::{
+    system::Resource,
+    world::{Mut, World},
+};
+use ne_app::{plugin_group::plugin_name, reflect::AppTypeRegistry, App, Plugin};
+use ne_assets::{AssetServer, AssetSettings, Assets, Handle, LoadState};
+use ne_files::AssetRoots;
+use ne_scene::{DynamicScene, ScenePlugin, SCENE_EXTENSION};
+use ne_window::action::{Action, Binding, InputMap};
+use serde::{Deserialize, Serialize};
+
+/// Extension of project manifests.
+pub const PROJECT_EXTENSION: &str = "neproject";
+
+/// An action of the manifest known by its name, for games without an action type of their own.
+pub type ActionName = &'static str;
+
+/// The contents of a `.neproject` file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    pub name: String,
+    /// Scene the game starts with and the editor opens, relative to the asset roots.
+    #[serde(default)]
+    pub startup_scene: Option<String>,
+    #[serde(default)]
+    pub assets: AssetsManifest,
+    /// Bindings of the game's actions, by action name.
+    #[serde(default)]
+    pub input: BTreeMap<String, Vec<Binding>>,
+    #[serde(default)]
+    pub build: BuildSettings,
+}
+impl ProjectManifest {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            startup_scene: Some(format!("scenes/main.{}", SCENE_EXTENSION)),
+            assets: AssetsManifest::default(),
+            input: BTreeMap::new(),
+            build: BuildSettings::default(),
+        }
+    }
+}
+
+/// The asset roots of a project, see [`AssetRoots`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetsManifest {
+    /// The assets of the game, a directory or a `.nepak` archive.
+    pub project: PathBuf,
+    /// The engine assets, discovered like [`AssetRoots::discover`] does when left out.
+    pub engine: Option<PathBuf>,
+}
+impl Default for AssetsManifest {
+    fn default() -> Self {
+        Self {
+            project: PathBuf::from("assets"),
+            engine: None,
+        }
+    }
+}
+
+/// How the assets of a project are built for release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildSettings {
+    /// Where the cooked assets go.
+    pub output: PathBuf,
+    pub mipmaps: bool,
+    pub compress_textures: bool,
+    /// Packs the cooked assets into a `.nepak` archive next to them.
+    pub pack: bool,
+}
+impl Default for BuildSettings {
+    fn default() -> Self {
+        Self {
+            output: PathBuf::from("build"),
+            mipmaps: true,
+            compress_textures: false,
+            pack: true,
+        }
+    }
+}
+
+/// A manifest and the file it was read from.
+#[derive(Debug, Clone, Resource)]
+pub struct Project {
+    path: PathBuf,
+    pub manifest: ProjectManifest,
+}
+impl Project {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("can't read {}", path.display()))?;
+        let manifest =
+            toml::from_str(&text).with_context(|| format!("invalid {}", path.display()))?;
+        Ok(Self { path, manifest })
+    }
+    /// Creates a project named `name` in `dir`, with an empty assets directory and startup
+    /// scene.
+    pub fn create(dir: impl AsRef<Path>, name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("a project needs a name");
+        }
+        let file_name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = dir
+            .as_ref()
+            .join(format!("{}.{}", file_name, PROJECT_EXTENSION));
+        if path.exists() {
+            bail!("{} already exists", path.display());
+        }
+        let project = Self {
+            path,
+            manifest: ProjectManifest::new(name),
+        };
+        fs::create_dir_all(project.asset_file(""))?;
+        if let Some(scene) = &project.manifest.startup_scene {
+            let scene_path = project.asset_file(scene);
+            if let Some(scene_dir) = scene_path.parent() {
+                fs::create_dir_all(scene_dir)?;
+            }
+            let empty = DynamicScene::default().serialize(&AppTypeRegistry::default())?;
+            fs::write(scene_path, empty)?;
+        }
+        project.save()?;
+        Ok(project)
+    }
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, toml::to_string(&self.manifest)?)
+            .with_context(|| format!("can't write {}", self.path.display()))
+    }
+    /// The manifest file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// The directory of the manifest, its paths are relative to it.
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new(""))
+    }
+    /// The file of the asset at `path`, when the project assets are a directory.
+    pub fn asset_file(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.dir().join(&self.manifest.assets.project).join(path)
+    }
+    /// The roots of the manifest, the user data directory is named after the project.
+    pub fn asset_roots(&self) -> AssetRoots {
+        let mut roots = AssetRoots::discover_for(&self.manifest.name);
+        roots.project = Some(self.dir().join(&self.manifest.assets.project));
+        if let Some(engine) = &self.manifest.assets.engine {
+            roots.engine = Some(self.dir().join(engine));
+        }
+        roots
+    }
+    /// Insert before the [`AssetPlugin`](ne_assets::AssetPlugin) to load the project's assets.
+    pub fn asset_settings(&self) -> AssetSettings {
+        AssetSettings {
+            roots: self.asset_roots(),
+            watch_for_changes: cfg!(debug_assertions),
+        }
+    }
+    /// The bindings of the actions `action` knows by name.
+    pub fn input_map<A: Action>(&self, action: impl Fn(&str) -> Option<A>) -> InputMap<A> {
+        let mut input_map = InputMap::default();
+        for (name, bindings) in &self.manifest.input {
+            let Some(action) = action(name) else {
+                tracing::warn!("{} binds the unknown action {}", self.path.display(), name);
+                continue;
+            };
+            for binding in bindings {
+                input_map.bind(action, *binding);
+            }
+        }
+        input_map
+    }
+    /// The bindings of every action of the manifest, by name. The names are leaked, build it once.
+    pub fn named_input_map(&self) -> InputMap<ActionName> {
+        self.input_map(|name| Some(&*Box::leak(Box::<str>::from(name))))
+    }
+}
+
+/// Spawns the startup scene of the [`Project`] inserted before the plugin once it is loaded.
+pub struct ProjectPlugin;
+impl Plugin for ProjectPlugin {
+    fn setup(&self, app: &mut App) {
+        let project = app
+            .world
+            .get_resource::<Project>()
+            .expect("insert the Project before adding the ProjectPlugin");
+        let Some(scene) = &project.manifest.startup_scene else {
+            return;
+        };
+        let handle = app.world.resource::<AssetServer>().load(scene);
+        app.insert_resource(StartupScene(Some(handle)))
+            .add_system(spawn_startup_scene_system);
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![plugin_name::<ScenePlugin>()]
+    }
+}
+
+/// The startup scene until it is spawned.
+#[derive(Resource)]
+struct StartupScene(Option<Handle<DynamicScene>>);
+
+fn spawn_startup_scene_system(world: &mut World) {
+    let Some(handle) = world.resource::<StartupScene>().0 else {
+        return;
+    };
+    if world.resource::<AssetServer>().load_state(handle) == LoadState::Failed {
+        world.resource_mut::<StartupScene>().0 = None;
+        return;
+    }
+    world.resource_scope(|world, scenes: Mut<Assets<DynamicScene>>| {
+        let Some(scene) = scenes.get(handle) else {
+            return;
+        };
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        if let Err(error) = scene.write_to_world(world, &registry) {
+            tracing::error!("can't spawn the startup scene: {:#}", error);
+        }
+        world.resource_mut::<StartupScene>().0 = None;
+    });
+}
+
+/// Projects opened last, the most recent first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentProjects {
+    pub projects: Vec<PathBuf>,
+}
+impl RecentProjects {
+    const MAX: usize = 10;
+    /// Reads the list saved at `path`, an empty one if there is none.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let Ok(text) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        toml::from_str(&text).unwrap_or_else(|error| {
+            tracing::warn!("ignoring {}: {}", path.display(), error);
+            Self::default()
+        })
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+    /// Moves `project` to the front of the list.
+    pub fn add(&mut self, project: &Path) {
+        let project = fs::canonicalize(project).unwrap_or_else(|_| project.to_path_buf());
+        self.projects.retain(|recent| *recent != project);
+        self.projects.insert(0, project);
+        self.projects.truncate(Self::MAX);
+    }
+}
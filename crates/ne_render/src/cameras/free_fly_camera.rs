@@ -1,6 +1,6 @@
 use ne_app::Resource;
 // use cgmath::*;
-use ne_math::{vec4, Mat4, Vec3};
+use ne_math::{vec4, Mat4, Ray, Vec2, Vec3};
 use winit::dpi::PhysicalPosition;
 use winit::event::*;
 
@@ -19,6 +19,17 @@ impl Camera {
             pitch: pitch,
         }
     }
+    /// Points the camera, `yaw` and `pitch` in radians. A yaw of zero looks along +x.
+    pub fn set_rotation(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = yaw;
+        self.pitch = pitch;
+    }
+    pub fn yaw(&self) -> f32 {
+        self.yaw
+    }
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
     pub fn calc_matrix(&self) -> Mat4 {
         let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
         let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
@@ -30,6 +41,18 @@ impl Camera {
             Vec3::Y,
         )
     }
+    /// The ray from the camera through `cursor`, given in pixels from the top left corner of a
+    /// window of `window_size` pixels. Used for picking and placing things under the mouse.
+    pub fn cursor_ray(&self, projection: &Projection, cursor: Vec2, window_size: Vec2) -> Ray {
+        let ndc = Vec2::new(
+            cursor.x / window_size.x * 2.0 - 1.0,
+            1.0 - cursor.y / window_size.y * 2.0,
+        );
+        let inverse = (projection.gl_matrix() * self.calc_matrix()).inverse();
+        let near = inverse.project_point3(ndc.extend(-1.0));
+        let far = inverse.project_point3(ndc.extend(1.0));
+        Ray::new(near, far - near)
+    }
 }
 pub fn look_to_rh(eye: Vec3, dir: Vec3, up: Vec3) -> Mat4 {
     let f = dir.normalize();
@@ -67,8 +90,10 @@ impl Projection {
     }
 
     pub fn calc_matrix(&self) -> Mat4 {
-        OPENGL_TO_WGPU_MATRIX
-            * Mat4::perspective_rh_gl(self.fovy, self.aspect, self.znear, self.zfar)
+        OPENGL_TO_WGPU_MATRIX * self.gl_matrix()
+    }
+    fn gl_matrix(&self) -> Mat4 {
+        Mat4::perspective_rh_gl(self.fovy, self.aspect, self.znear, self.zfar)
     }
 }
 #[derive(Debug, Resource)]
@@ -0,0 +1,64 @@
+//! [`AssetLoader`]s for meshes and textures, added by the [`RenderPlugin`](crate::RenderPlugin).
+use std::path::Path;
+
+use ne_assets::AssetLoader;
+use ne_cook::{CookedModel, MESH_EXTENSION, TEXTURE_EXTENSION};
+
+use crate::{
+    material::Image,
+    mesh::{MeshPrimitives, Model},
+};
+
+/// Loads `.obj` files into a [`Model`].
+pub struct ObjLoader;
+impl AssetLoader for ObjLoader {
+    type Asset = Model;
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+    fn load(&self, bytes: &[u8], _path: &Path) -> anyhow::Result<Model> {
+        Ok(Model::new(MeshPrimitives::from_obj_bytes(bytes)?))
+    }
+}
+
+/// Loads meshes cooked by `ne_cook` into a [`Model`].
+pub struct CookedMeshLoader;
+impl AssetLoader for CookedMeshLoader {
+    type Asset = Model;
+    fn extensions(&self) -> &[&str] {
+        &[MESH_EXTENSION]
+    }
+    fn load(&self, bytes: &[u8], _path: &Path) -> anyhow::Result<Model> {
+        let meshes = CookedModel::from_bytes(bytes)?
+            .meshes
+            .into_iter()
+            .map(MeshPrimitives::from_cooked)
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Model::new(meshes))
+    }
+}
+
+/// Loads textures into an [`Image`], turn it into a [`Material`](crate::material::Material)
+/// with [`Material::from_image`](crate::material::Material::from_image).
+pub struct ImageLoader;
+impl AssetLoader for ImageLoader {
+    type Asset = Image;
+    fn extensions(&self) -> &[&str] {
+        &["png", "jpg", "jpeg", "bmp", "tga"]
+    }
+    fn load(&self, bytes: &[u8], _path: &Path) -> anyhow::Result<Image> {
+        Image::from_bytes(bytes)
+    }
+}
+
+/// Loads textures cooked by `ne_cook` into an [`Image`].
+pub struct CookedTextureLoader;
+impl AssetLoader for CookedTextureLoader {
+    type Asset = Image;
+    fn extensions(&self) -> &[&str] {
+        &[TEXTURE_EXTENSION]
+    }
+    fn load(&self, bytes: &[u8], _path: &Path) -> anyhow::Result<Image> {
+        Image::from_cooked(bytes)
+    }
+}
@@ -1,6 +1,9 @@
+use std::sync::Arc;
+
 use anyhow::*;
 use bevy_ecs::prelude::{Component, Bundle};
 use ne_app::types::Name;
+use ne_cook::{CookedTexture, TextureFormat};
 
 /// A material stored as bundle in bevy ecs.
 #[derive(Bundle)]
@@ -8,11 +11,36 @@ pub struct NamedMaterial {
     pub name:Name,
     pub material:Material,
 }
-#[derive(Component)]
+/// Pixels of a texture, decoded from png, jpeg and the other formats of the `image` crate,
+/// or cooked by `ne_cook` with mip levels and block compression.
+pub struct Image {
+    pub texture: CookedTexture,
+}
+impl Image {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self {
+            texture: CookedTexture::from_rgba(image::load_from_memory(bytes)?.to_rgba8()),
+        })
+    }
+    /// Reads a `.netex` file.
+    pub fn from_cooked(bytes: &[u8]) -> Result<Self> {
+        Ok(Self {
+            texture: CookedTexture::from_bytes(bytes)?,
+        })
+    }
+    pub fn width(&self) -> u32 {
+        self.texture.width
+    }
+    pub fn height(&self) -> u32 {
+        self.texture.height
+    }
+}
+/// Cheap to clone, the clones share the GPU resources.
+#[derive(Component, Clone)]
 pub struct Material {
-    pub texture: wgpu::Texture,
-    pub view: wgpu::TextureView,
-    pub sampler: wgpu::Sampler,
+    pub texture: Arc<wgpu::Texture>,
+    pub view: Arc<wgpu::TextureView>,
+    pub sampler: Arc<wgpu::Sampler>,
 }
 /// engine default material.
 /// yeah don't know how to implement this here...
@@ -44,40 +72,70 @@ impl Material {
         bytes: &[u8],
         label: Option<&str>,
     ) -> Result<Self> {
-        let img = image::load_from_memory(bytes).unwrap();
-        let rgba = img.to_rgba8();
-        let dimensions = image::GenericImageView::dimensions(&img);
+        Self::from_image(device, queue, &Image::from_bytes(bytes)?, label)
+    }
+    /// Uploads every mip level of `image`, block compressed images are decompressed first if
+    /// the device doesn't support [`wgpu::Features::TEXTURE_COMPRESSION_BC`].
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &Image,
+        label: Option<&str>,
+    ) -> Result<Self> {
+        let decompressed;
+        let cooked = if image.texture.format.is_compressed()
+            && !device.features().contains(wgpu::Features::TEXTURE_COMPRESSION_BC)
+        {
+            decompressed = image.texture.to_rgba8();
+            &decompressed
+        } else {
+            &image.texture
+        };
+        let format = match cooked.format {
+            TextureFormat::Rgba8 => wgpu::TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Bc1 => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+            TextureFormat::Bc3 => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+        };
 
         let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
+            width: cooked.width,
+            height: cooked.height,
             depth_or_array_layers: 1,
         };
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: label,
             size,
-            mip_level_count: 1,
+            mip_level_count: cooked.mips.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
         });
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: std::num::NonZeroU32::new(4 * dimensions.0),
-                rows_per_image: std::num::NonZeroU32::new(dimensions.1),
-            },
-            size,
-        );
+        for (level, data) in cooked.mips.iter().enumerate() {
+            let (width, _) = cooked.mip_size(level);
+            // Compressed levels are copied in whole blocks, also when they are smaller.
+            let (copy_width, copy_height) = cooked.copy_size(level);
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(cooked.format.bytes_per_row(width)),
+                    rows_per_image: None,
+                },
+                wgpu::Extent3d {
+                    width: copy_width,
+                    height: copy_height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             //TODO accessibility
@@ -89,14 +147,14 @@ impl Material {
             address_mode_w: wgpu::AddressMode::Repeat,
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Linear,
             ..Default::default()
         });
 
         Ok(Self {
-            texture,
-            view,
-            sampler,
+            texture: Arc::new(texture),
+            view: Arc::new(view),
+            sampler: Arc::new(sampler),
         })
     }
 }
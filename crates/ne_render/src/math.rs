@@ -52,6 +52,6 @@ pub trait ToMat4 {
 
 impl ToMat4 for ne_math::Transform {
     fn to_raw(&self) -> Mat4 {
-        Mat4::from_translation(self.pos) * Mat4::from_quat(self.rot)
+        Mat4::from_scale_rotation_translation(self.scale, self.rot, self.pos)
     }
 }
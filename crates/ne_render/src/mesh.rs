@@ -1,10 +1,17 @@
 //=========================================
-use crate::{depth_texture, material, math::ToMat4, render_structs::RenderDevice};
-use bevy_ecs::prelude::{Component, Bundle};
+use crate::{
+    depth_texture,
+    material::{self, Material},
+    math::ToMat4,
+    render_structs::{RenderDevice, RenderQueue},
+    NCameraBuffer,
+};
+use bevy_ecs::prelude::{Bundle, ChangeTrackers, Changed, Component, Entity, Query, Res};
 use bytemuck::{Pod, Zeroable};
 use ne_app::types::Name;
-use ne_math::Transform;
-use std::{borrow::Cow, f32::consts::PI, mem};
+use ne_cook::{CookedMesh, CookedVertex};
+use ne_math::{Aabb, Ray, Transform, Vec3};
+use std::{borrow::Cow, f32::consts::PI, mem, sync::Arc};
 use wgpu::{util::DeviceExt};
 ///y is up
 #[repr(C)]
@@ -20,7 +27,12 @@ impl Vertex {
             tex_coord: [tc[0], tc[1]],
         }
     }
+    pub fn position(&self) -> [f32; 3] {
+        [self.pos[0], self.pos[1], self.pos[2]]
+    }
 }
+// Cooked vertex buffers are used as they are.
+const _: () = assert!(mem::size_of::<Vertex>() == mem::size_of::<CookedVertex>());
 #[cfg(feature = "mesh_16bit")]
 type MeshIndex = u16;
 #[cfg(not(feature = "mesh_16bit"))]
@@ -41,25 +53,43 @@ impl Model {
 #[derive(Clone)]
 pub struct MeshPrimitives(Vec<Vertex>, Vec<MeshIndex>);
 impl MeshPrimitives {
-    //TODO I don't like this... somehow gotta implement include_str() or something to verify each file.
-    //TODO return Model instead of MeshPrimitives.
-    pub async fn from_obj(file_name: &str) -> anyhow::Result<Vec<Self>> {
-        //TODO replace by assert?
-        println!(
-            "loading: {} exists: {}",
-            file_name,
-            std::path::Path::new(file_name).exists()
-        );
-        //TODO opportunity
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.0
+    }
+    /// Triangle list indices into [`vertices`](Self::vertices).
+    pub fn indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.1.iter().map(|&index| index as u32)
+    }
+    /// Takes over the buffers of a mesh cooked by `ne_cook`.
+    pub fn from_cooked(mesh: CookedMesh) -> anyhow::Result<Self> {
+        let vertices = bytemuck::cast_slice::<CookedVertex, Vertex>(&mesh.vertices).to_vec();
+        let indices = mesh
+            .indices
+            .into_iter()
+            .map(|index| {
+                MeshIndex::try_from(index)
+                    .map_err(|_| anyhow::anyhow!("index {} doesn't fit the mesh index type", index))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(MeshPrimitives(vertices, indices))
+    }
+    /// Reads the meshes of an obj file, its materials are ignored.
+    pub fn from_obj(file_name: &str) -> anyhow::Result<Vec<Self>> {
+        Self::from_obj_bytes(&std::fs::read(file_name)?)
+    }
+    /// Parses the meshes of an obj file, its materials are ignored.
+    pub fn from_obj_bytes(bytes: &[u8]) -> anyhow::Result<Vec<Self>> {
         let load_options = tobj::LoadOptions {
             triangulate: true,
             single_index: true,
             ..Default::default()
         };
         //TODO No need to use obj materials, just use default engine material.
-        //TODO use tobj::load_obj_buf_async
-
-        let (models, _) = tobj::load_obj(file_name, &load_options).unwrap();
+        let (models, _) = tobj::load_obj_buf(
+            &mut std::io::Cursor::new(bytes),
+            &load_options,
+            |_| Ok(Default::default()),
+        )?;
 
         //TODO support multiple models...
         //model into mesh_primitve
@@ -68,13 +98,15 @@ impl MeshPrimitives {
             .map(|m| {
                 let vertices = (0..m.mesh.positions.len() / 3)
                     .map(|i| {
+                        let tex_coord =
+                            m.mesh.texcoords.get(i * 2..i * 2 + 2).unwrap_or(&[0.0, 0.0]);
                         Vertex::new(
                             [
                                 m.mesh.positions[i * 3],
                                 m.mesh.positions[i * 3 + 1],
                                 m.mesh.positions[i * 3 + 2],
                             ],
-                            [m.mesh.texcoords[i * 2], m.mesh.texcoords[i * 2 + 1]],
+                            [tex_coord[0], tex_coord[1]],
                         )
                     })
                     .collect::<Vec<_>>();
@@ -87,7 +119,6 @@ impl MeshPrimitives {
                 }
             })
             .collect::<Vec<_>>();
-        //TODO only returns first mesh primitives...
         Ok(meshes)
     }
 }
@@ -269,15 +300,57 @@ impl<F: Future<Output = Option<wgpu::Error>>> Future for ErrorFuture<F> {
 // }
 //gotta try ecs somehow in a github fork
 /// Stores buffers and such
+/// The vertex and index buffers, the pipeline and the shape are shared with
+/// [duplicates](GpuMesh::duplicate).
 #[derive(Component)]
 pub struct GpuMesh {
-    pub vertex_buffer: wgpu::Buffer,
-    pub index_buffer: wgpu::Buffer,
+    pub vertex_buffer: Arc<wgpu::Buffer>,
+    pub index_buffer: Arc<wgpu::Buffer>,
     pub index_count: usize,
     pub bind_group: wgpu::BindGroup,
-    pub pipeline: wgpu::RenderPipeline,
+    pub pipeline: Arc<wgpu::RenderPipeline>,
     pub model_buffer: wgpu::Buffer,
+    pub shape: Arc<MeshShape>,
+}
+/// The triangles of a mesh kept on the cpu, for picking it with the mouse.
+#[derive(Debug)]
+pub struct MeshShape {
+    /// In the space of the mesh, none when it has no vertices.
+    pub aabb: Option<Aabb>,
+    pub triangles: Vec<[Vec3; 3]>,
+}
+impl MeshShape {
+    pub fn new(mesh: &MeshPrimitives) -> Self {
+        let position = |index: u32| Vec3::from(mesh.vertices()[index as usize].position());
+        let indices: Vec<u32> = mesh.indices().collect();
+        Self {
+            aabb: Aabb::from_points(mesh.vertices().iter().map(|vertex| vertex.position().into())),
+            triangles: indices
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]].map(position))
+                .collect(),
+        }
+    }
+    /// Distance along `ray` to the closest triangle of the mesh placed at `transform`.
+    pub fn intersect(&self, ray: Ray, transform: &Transform) -> Option<f32> {
+        let model = transform.to_raw();
+        let inverse = model.inverse();
+        let local = Ray::new(
+            inverse.transform_point3(ray.origin),
+            inverse.transform_vector3(ray.direction),
+        );
+        local.intersect_aabb(self.aabb.as_ref()?)?;
+        let distance = self
+            .triangles
+            .iter()
+            .filter_map(|&triangle| local.intersect_triangle(triangle))
+            .min_by(f32::total_cmp)?;
+        Some(model.transform_point3(local.get_point(distance)).distance(ray.origin))
+    }
 }
+/// The entity with the [`Material`] a mesh is drawn with, changing it rebinds the mesh.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshMaterial(pub Entity);
 /// Mesh that holds transform data.
 /// spawn this one to easily reuse this gpu mesh...
 /// TODO weak clone?
@@ -402,41 +475,9 @@ impl GpuMesh {
             push_constant_ranges: &[],
         });
         // Create other resources
-        let mvp_matrix = transform.to_raw();
-        let mx_ref: &[f32; 16] = mvp_matrix.as_ref();
-        let model_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Uniform Buffer"),
-            contents: bytemuck::cast_slice(mx_ref),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let camera_bge = wgpu::BindGroupEntry {
-            binding: 3,
-            resource: camera_buffer.as_entire_binding(),
-        };
-        // Create bind group
-        //TODO split maybe
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: model_buffer.as_entire_binding(),
-                },
-                //texture
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&mat.view),
-                },
-                //sampler
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&mat.sampler),
-                },
-                camera_bge,
-            ],
-            label: None,
-        });
+        let model_buffer = Self::create_model_buffer(device, &transform);
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &model_buffer, mat, camera_buffer);
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: None,
             source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("basic_cube.wgsl"))),
@@ -488,12 +529,130 @@ impl GpuMesh {
         });
         // Done
         GpuMesh {
-            vertex_buffer,
-            index_buffer,
+            vertex_buffer: Arc::new(vertex_buffer),
+            index_buffer: Arc::new(index_buffer),
             index_count: mesh_data.1.len(),
             bind_group,
-            pipeline,
+            pipeline: Arc::new(pipeline),
             model_buffer,
+            shape: Arc::new(MeshShape::new(&mesh_data)),
+        }
+    }
+    /// A mesh drawn with the same buffers and pipeline, with its own transform and material.
+    pub fn duplicate(
+        &self,
+        camera_buffer: &wgpu::Buffer,
+        device: &wgpu::Device,
+        transform: &Transform,
+        mat: &Material,
+    ) -> Self {
+        let model_buffer = Self::create_model_buffer(device, transform);
+        let bind_group = Self::create_bind_group(
+            device,
+            &self.pipeline.get_bind_group_layout(0),
+            &model_buffer,
+            mat,
+            camera_buffer,
+        );
+        GpuMesh {
+            vertex_buffer: self.vertex_buffer.clone(),
+            index_buffer: self.index_buffer.clone(),
+            index_count: self.index_count,
+            bind_group,
+            pipeline: self.pipeline.clone(),
+            model_buffer,
+            shape: self.shape.clone(),
+        }
+    }
+    /// Draws the mesh with `mat` from now on.
+    pub fn set_material(
+        &mut self,
+        camera_buffer: &wgpu::Buffer,
+        device: &wgpu::Device,
+        mat: &Material,
+    ) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.pipeline.get_bind_group_layout(0),
+            &self.model_buffer,
+            mat,
+            camera_buffer,
+        );
+    }
+    fn create_model_buffer(device: &wgpu::Device, transform: &Transform) -> wgpu::Buffer {
+        let mvp_matrix = transform.to_raw();
+        let mx_ref: &[f32; 16] = mvp_matrix.as_ref();
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(mx_ref),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        })
+    }
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        model_buffer: &wgpu::Buffer,
+        mat: &Material,
+        camera_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: model_buffer.as_entire_binding(),
+                },
+                //texture
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&mat.view),
+                },
+                //sampler
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&mat.sampler),
+                },
+                //camera
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+            ],
+            label: None,
+        })
+    }
+}
+/// Uploads the transforms that changed since the meshes were created.
+pub fn update_model_buffers_system(
+    queue: Res<RenderQueue>,
+    meshes: Query<(&GpuMesh, &Transform, ChangeTrackers<Transform>), Changed<Transform>>,
+) {
+    for (mesh, transform, tracker) in &meshes {
+        if tracker.is_added() {
+            continue;
+        }
+        let mvp_matrix = transform.to_raw();
+        let mx_ref: &[f32; 16] = mvp_matrix.as_ref();
+        queue.write_buffer(&mesh.model_buffer, 0, bytemuck::cast_slice(mx_ref));
+    }
+}
+/// Rebinds the meshes whose [`MeshMaterial`] changed.
+pub fn update_mesh_materials_system(
+    device: Res<RenderDevice>,
+    camera_buffer: Res<NCameraBuffer>,
+    mut meshes: Query<
+        (&mut GpuMesh, &MeshMaterial, ChangeTrackers<MeshMaterial>),
+        Changed<MeshMaterial>,
+    >,
+    materials: Query<&Material>,
+) {
+    for (mut mesh, mesh_material, tracker) in &mut meshes {
+        if tracker.is_added() {
+            continue;
+        }
+        match materials.get(mesh_material.0) {
+            Ok(material) => mesh.set_material(&camera_buffer, &device, material),
+            Err(_) => tracing::warn!("{:?} is not a material", mesh_material.0),
         }
     }
 }
\ No newline at end of file
@@ -16,7 +16,9 @@ use ne_window::events::{OnWindowResized, OnWindowScaleFactorChanged, ExitApp, On
                         OnWindowCloseRequested, OnFileDragAndDrop, OnCursorEntered, OnCursorLeft, OnReceivedCharacter, OnWindowFocused, OnKeyboardInput, ExitSequence, OnMouseMotion, OnMouseButton, OnMouseWheel};
                         use std::sync::Arc;
 
-use ne_app::{App, Plugin, Events, ManualEventReader, Resource};
+use ne_app::{App, AppExit, Plugin, Events, ManualEventReader, Resource, time::{Time, TimePlugin}, plugin_group::plugin_name};
+use ne_assets::{AssetApp, AssetPlugin, AssetServer};
+use ne_cook::{MESH_EXTENSION, TEXTURE_EXTENSION};
 use render_structs::{RenderQueue, RenderDevice};
 use tracing::{warn, debug, info};
 #[cfg(target_arch = "wasm32")]
@@ -43,15 +45,16 @@ pub mod mesh;
 mod model;
 mod shapes;
 pub mod material;
+pub mod loaders;
 pub mod render_structs;
 pub mod math;
+#[cfg(feature = "editor_ui")]
+pub use egui;
+#[cfg(feature = "editor_ui")]
+pub use user_interface::ui_app::{UiApp, UiContext};
 // pub mod scene;
 // use Scene as CurrentScene; //will be used as a resource...
 
-#[derive(Clone, Resource)]
-pub struct DeltaTime {
-    pub time: f32,
-}
 //move these
 //===================================================
 #[derive(Resource, Deref, DerefMut)]
@@ -78,9 +81,6 @@ mod Render {
     }
 }
 //===================================================
-//try moving all of this to app as resources.
-#[cfg(feature = "editor_ui")]
-static mut FRAME_COUNT: u32 = 0;
 #[derive(Resource)]
 struct RenderState {
     surface: wgpu::Surface,
@@ -113,7 +113,8 @@ impl RenderState {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    features: wgpu::Features::empty(),
+                    // Cooked textures can be block compressed.
+                    features: adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC,
                     // WebGL doesn't support all of wgpu's features, so if
                     // we're building for the web we'll have to disable some.
                     limits: if cfg!(target_arch = "wasm32") {
@@ -166,7 +167,8 @@ impl RenderState {
         app.insert_resource(camera_uniform);
         app.insert_resource(projection);
         #[cfg(feature = "editor_ui")]
-        app.insert_resource(ui_state);
+        app.insert_resource(UiContext(ui_state.platform.context()))
+            .insert_resource(ui_state);
         // arc might cause slowdowns
         let queue  = RenderQueue(Arc::new(queue));
         let device  = RenderDevice(Arc::new(device));
@@ -205,7 +207,7 @@ impl RenderState {
                 label: Some("Render Encoder"),
             })
     }
-    fn render(&mut self, app: &mut App, delta_time: f32) -> Result<(), wgpu::SurfaceError> {
+    fn render(&mut self, app: &mut App) -> Result<(), wgpu::SurfaceError> {
         let output_frame = self.surface.get_current_texture()?;
         let output_view = output_frame
             .texture
@@ -309,19 +311,11 @@ impl RenderState {
                             &widget.0);
                     });
                 });
+            let time = world.resource::<Time>();
             if ui_state.widget_diagnostic.1 {
                 //calculate fps...
-                let fps = 1.0 / delta_time;
-                let average_fps: f32;
-                unsafe {
-                    FRAME_COUNT += 1;
-                }
-                let f = world.resource::<ne_app::FirstFrameTime>().get_time();
-                unsafe
-                    {
-                        let time_passed = (instant::Instant::now() - f).as_secs_f32();
-                        average_fps = FRAME_COUNT as f32 / time_passed;
-                    }
+                let fps = 1.0 / time.raw_delta_seconds();
+                let average_fps = time.frame_count() as f32 / time.raw_elapsed_seconds();
                 egui::Window::new(&ui_state.widget_diagnostic.0)
                     .default_width(default_width)
                     .default_height(ctx.available_rect().height() - 46.0)
@@ -348,23 +342,15 @@ impl RenderState {
                         });
                     });
             }
-            if ui_state.widget_file_explorer.1 {
-                egui::Window::new(&ui_state.widget_file_explorer.0)
-                    .default_width(default_width)
-                    .default_height(ctx.available_rect().height() + 46.0)
-                    .vscroll(true)
-                    .open(&mut true)
-                    .resizable(false)
-                    .collapsible(true)
-                    .show(ctx, |ui| {
-                        ui.horizontal(|ui| {});
-                    });
-            }
-            let ft = world.resource::<ne_app::FirstFrameTime>().get_time();
-            let now = instant::Instant::now();
-            let time_passed = (now - ft).as_secs_f64();
-
-            ui_state.update_time(time_passed);
+            // The File Explorer window is drawn by the editor's ui systems, it needs the scene.
+            ui_state.update_time(time.raw_elapsed_seconds_f64());
+            // The windows of other plugins.
+            drop(time);
+            drop(ui_state);
+            drop(world);
+            user_interface::ui_app::UiSystems::run(&mut app.world);
+            let world = app.world.cell();
+            let mut ui_state = world.resource_mut::<NUiState>();
 
             // End the UI frame. We could now handle the output and draw the UI with the backend.
             let full_output = ui_state.platform.end_frame(Some(&world.resource::<NWindow>()));
@@ -421,7 +407,20 @@ impl RenderState {
 pub struct RenderPlugin;
 impl Plugin for RenderPlugin {
     fn setup(&self, app: &mut App) {
-        app.add_plugin(ne_window::WindowEventPlugin);
+        app.add_asset_loader(loaders::ObjLoader)
+            .add_asset_loader(loaders::ImageLoader)
+            .add_asset_loader(loaders::CookedMeshLoader)
+            .add_asset_loader(loaders::CookedTextureLoader)
+            .add_system(mesh::update_model_buffers_system)
+            .add_system(mesh::update_mesh_materials_system);
+        // Cooked meshes and textures are loaded in place of their sources when they exist.
+        let asset_server = app.world.resource::<AssetServer>();
+        for extension in ["obj", "gltf", "glb"] {
+            asset_server.add_cooked_extension(extension, MESH_EXTENSION);
+        }
+        for extension in ["png", "jpg", "jpeg", "bmp", "tga"] {
+            asset_server.add_cooked_extension(extension, TEXTURE_EXTENSION);
+        }
         //prepare resources.
         let event_loop = EventLoop::new();
         let win_settings = app.world.get_resource::<WindowSettings>().unwrap_or(&WindowSettings::default())
@@ -429,28 +428,34 @@ impl Plugin for RenderPlugin {
         let window = NWindow {0: create_window(&win_settings, &event_loop)};
         let state =
             pollster::block_on(RenderState::new(app, &window, win_settings));
-        //initial delta time. only supposed to be read outside this file...
-        let delta_time = DeltaTime { time: 0.0 };
-        app.insert_resource(delta_time.clone())
         //these resources will be removed in loop.
-        .insert_non_send_resource(event_loop)
+        app.insert_non_send_resource(event_loop)
         .insert_non_send_resource(window)
         .insert_resource(state)
 
         .set_runner(main_loop);
     }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![
+            plugin_name::<ne_window::WindowEventPlugin>(),
+            plugin_name::<TimePlugin>(),
+            plugin_name::<AssetPlugin>(),
+        ]
+    }
 }
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 fn main_loop(mut app: App) {
     let event_loop =
         app.world.remove_non_send_resource::<EventLoop<()>>().unwrap();
     let mut state = app.world.remove_resource::<RenderState>().unwrap();
+    let close_when_requested = app
+        .world
+        .get_resource::<WindowSettings>()
+        .is_none_or(|settings| settings.close_when_requested);
 
-    //benchmark values.
-    let mut once_benchmark = true;
-    let mut last_render_time = instant::Instant::now();
     //exit window event reader
-    let mut app_exit_event_reader = ManualEventReader::<ExitApp>::default();
+    let mut exit_app_event_reader = ManualEventReader::<ExitApp>::default();
+    let mut app_exit_event_reader = ManualEventReader::<AppExit>::default();
     //TODO this really needs to be the first thing to ever be called in this game engine? the engine needs a kind of startup logic that will always be performed first to prevent horrible unwrap errors.
     let event_handler =
         move |event: Event<()>,
@@ -494,7 +499,9 @@ fn main_loop(mut app: App) {
                                     app.world.resource_mut::<Events<OnWindowCloseRequested>>();
                                     window_close_requested_events.send(
                                         OnWindowCloseRequested { id: window_id });
-                                    *control_flow = ControlFlow::Exit;
+                                    if close_when_requested {
+                                        *control_flow = ControlFlow::Exit;
+                                    }
                                 }
                                 WindowEvent::KeyboardInput {
                                     input:
@@ -612,20 +619,7 @@ fn main_loop(mut app: App) {
                     });
                 }
                 event::Event::RedrawRequested(window_id) if window_id == app.world.resource::<NWindow>().id() => {
-                    //hope this gets optimized
-                    if once_benchmark //do once
-                    {
-                        app.insert_resource(ne_app::FirstFrameTime::default());
-                        once_benchmark = false;
-                    }
-                    //is it expensive to use delta_time as a resource?
-                    let now = instant::Instant::now();
-                    let delta_time = DeltaTime { time: (now - last_render_time).as_secs_f32() };
-                    app.insert_resource(delta_time.clone());
-                    let delta_time = delta_time.time;
-                    last_render_time = now;
-                    
-                    match state.render(&mut app, delta_time) {
+                    match state.render(&mut app) {
                         Ok(_) => {}
                         // Reconfigure the surface if it's lost or outdated
                         Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
@@ -648,9 +642,12 @@ fn main_loop(mut app: App) {
                     Render::update_camera_buffer(&state.queue, app.world.resource::<NCameraBuffer>(), &app.world.resource::<CameraUniform>());
                 }
                 event::Event::RedrawEventsCleared => {
-                    let app_exit_events =
+                    let exit_app_events =
                     app.world.resource::<Events<ExitApp>>();
-                    if app_exit_event_reader.iter(app_exit_events).last().is_some() {
+                    let app_exit_events =
+                    app.world.resource::<Events<AppExit>>();
+                    if exit_app_event_reader.iter(exit_app_events).last().is_some()
+                        || app_exit_event_reader.iter(app_exit_events).last().is_some() {
                         *control_flow = ControlFlow::Exit;
                     }
                 }
@@ -735,6 +732,9 @@ pub struct WindowSettings {
     /// Uses the [CSS selector format](https://developer.mozilla.org/en-US/docs/Web/API/Document/querySelector).
     /// This value has no effect on non-web platforms.
     pub canvas: Option<String>,
+    /// Whether closing the window exits the app. When `false` only [`OnWindowCloseRequested`] is
+    /// sent, and the app exits once [`ExitApp`] is sent.
+    pub close_when_requested: bool,
 }
 
 impl Default for WindowSettings {
@@ -759,6 +759,7 @@ impl Default for WindowSettings {
 
             window_mode: WindowMode::Windowed,
             canvas: None,
+            close_when_requested: true,
         }
     }
 }
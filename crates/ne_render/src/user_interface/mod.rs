@@ -5,6 +5,8 @@ use std::borrow::Cow;
 use self::egui_window::{Platform, PlatformDescriptor};
 use self::ui_render_pass::RenderPassRecipe;
 mod egui_window;
+#[cfg(feature = "editor_ui")]
+pub mod ui_app;
 pub mod ui_render_pass;
 //TODO extendable ui with egui.
 //1) zero overhead
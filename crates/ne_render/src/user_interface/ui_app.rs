@@ -0,0 +1,42 @@
+//! Lets plugins draw their own egui windows next to the built-in ones.
+use bevy_derive::Deref;
+use bevy_ecs::{
+    schedule::{IntoSystemDescriptor, Stage, SystemStage},
+    world::World,
+};
+use ne_app::{App, Resource};
+
+/// The egui context of the editor ui, draw with it from systems added with
+/// [`UiApp::add_ui_system`].
+#[derive(Resource, Clone, Deref)]
+pub struct UiContext(pub(crate) egui::Context);
+
+/// Systems that run while the egui frame is open.
+#[derive(Resource)]
+pub(crate) struct UiSystems(SystemStage);
+impl Default for UiSystems {
+    fn default() -> Self {
+        Self(SystemStage::single_threaded())
+    }
+}
+impl UiSystems {
+    pub(crate) fn run(world: &mut World) {
+        if let Some(mut systems) = world.remove_resource::<UiSystems>() {
+            systems.0.run(world);
+            world.insert_resource(systems);
+        }
+    }
+}
+
+/// Adds ui systems to an [`App`].
+pub trait UiApp {
+    /// Adds a system that draws with [`UiContext`], it runs once per rendered frame.
+    fn add_ui_system<Params>(&mut self, system: impl IntoSystemDescriptor<Params>) -> &mut Self;
+}
+impl UiApp for App {
+    fn add_ui_system<Params>(&mut self, system: impl IntoSystemDescriptor<Params>) -> &mut Self {
+        self.init_resource::<UiSystems>();
+        self.world.resource_mut::<UiSystems>().0.add_system(system);
+        self
+    }
+}
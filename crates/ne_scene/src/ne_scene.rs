@@ -0,0 +1,220 @@
+//! Scenes: entities with their reflected components, saved to and loaded from `.nscene` files.
+//!
+//! Every component registered with [`App::register_type`] and `#[reflect(Component)]` is
+//! saved, components without reflection like gpu meshes are left out.
+//! ```ignore
+//! let scene = DynamicScene::from_world(&world, &registry);
+//! std::fs::write("level.nscene", scene.serialize(&registry)?)?;
+//! let scene = DynamicScene::deserialize(&std::fs::read_to_string("level.nscene")?, &registry)?;
+//! scene.write_to_world(&mut world, &registry)?;
+//! ```
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use bevy_ecs::{
+    entity::{Entity, EntityMap},
+    world::World,
+};
+use bevy_reflect::{
+    serde::{ReflectSerializer, UntypedReflectDeserializer},
+    Reflect,
+};
+use ne_app::{
+    plugin_group::plugin_name,
+    reflect::{AppTypeRegistry, ReflectComponent, ReflectMapEntities},
+    App, Plugin,
+};
+use ne_assets::{AssetApp, AssetLoader, AssetPlugin};
+use ne_math::{Quat, Transform, Vec3};
+use serde::{de::DeserializeSeed, Deserialize, Serialize};
+
+/// Extension of scene files.
+pub const SCENE_EXTENSION: &str = "nscene";
+const VERSION: u32 = 2;
+
+/// Loads `.nscene` files as [`DynamicScene`] assets and registers [`Transform`].
+pub struct ScenePlugin;
+impl Plugin for ScenePlugin {
+    fn setup(&self, app: &mut App) {
+        let registry = app.world.resource::<AppTypeRegistry>().clone();
+        app.register_type::<Transform>()
+            .register_type::<Vec3>()
+            .register_type::<Quat>()
+            .add_asset_loader(SceneLoader { registry });
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![plugin_name::<AssetPlugin>()]
+    }
+}
+
+/// An entity of a [`DynamicScene`].
+pub struct DynamicEntity {
+    /// The entity it was saved from, other components refer to it by this id.
+    pub entity: Entity,
+    pub components: Vec<Box<dyn Reflect>>,
+}
+
+/// Entities and their reflected components, independent of a [`World`].
+#[derive(Default)]
+pub struct DynamicScene {
+    pub entities: Vec<DynamicEntity>,
+}
+impl DynamicScene {
+    /// The entities with at least one reflected component.
+    pub fn from_world(world: &World, registry: &AppTypeRegistry) -> Self {
+        let mut scene = Self::from_entities(world, world.iter_entities(), registry);
+        scene
+            .entities
+            .retain(|entity| !entity.components.is_empty());
+        scene
+    }
+    /// `entities` with their reflected components, despawned entities are skipped.
+    pub fn from_entities(
+        world: &World,
+        entities: impl IntoIterator<Item = Entity>,
+        registry: &AppTypeRegistry,
+    ) -> Self {
+        let registry = registry.read();
+        let entities = entities
+            .into_iter()
+            .filter_map(|entity| {
+                let location = world.entities().get(entity)?;
+                let components = world.archetypes()[location.archetype_id]
+                    .components()
+                    .filter_map(|id| world.components().get_info(id)?.type_id())
+                    .filter_map(|type_id| registry.get_type_data::<ReflectComponent>(type_id))
+                    .filter_map(|reflect| reflect.reflect(world, entity))
+                    .map(|component| component.clone_value())
+                    .collect();
+                Some(DynamicEntity { entity, components })
+            })
+            .collect();
+        Self { entities }
+    }
+    /// Spawns the entities, references between them are mapped to the spawned entities.
+    ///
+    /// Returns the spawned entity of each scene entity.
+    pub fn write_to_world(
+        &self,
+        world: &mut World,
+        registry: &AppTypeRegistry,
+    ) -> Result<EntityMap> {
+        let mut entity_map = EntityMap::default();
+        self.write_to_world_with(world, registry, &mut entity_map)?;
+        Ok(entity_map)
+    }
+    /// Like [`write_to_world`](Self::write_to_world), scene entities already in `entity_map`
+    /// are written to the entity they map to, it is spawned with that id if it doesn't exist.
+    pub fn write_to_world_with(
+        &self,
+        world: &mut World,
+        registry: &AppTypeRegistry,
+        entity_map: &mut EntityMap,
+    ) -> Result<()> {
+        let registry = registry.read();
+        for scene_entity in &self.entities {
+            let entity = *entity_map
+                .entry(scene_entity.entity)
+                .or_insert_with(|| world.spawn_empty().id());
+            if world.get_or_spawn(entity).is_none() {
+                bail!("{entity:?} can't be spawned, its id is taken");
+            }
+            for component in &scene_entity.components {
+                let reflect = registry
+                    .get_with_name(component.type_name())
+                    .and_then(|registration| registration.data::<ReflectComponent>())
+                    .ok_or_else(|| {
+                        anyhow!("{} is not a registered component", component.type_name())
+                    })?;
+                reflect.apply_or_insert(world, entity, &**component);
+            }
+        }
+        for registration in registry.iter() {
+            if let Some(map_entities) = registration.data::<ReflectMapEntities>() {
+                map_entities.map_entities(world, entity_map)?;
+            }
+        }
+        Ok(())
+    }
+    /// The scene as json, every component under the name of its type.
+    pub fn serialize(&self, registry: &AppTypeRegistry) -> Result<String> {
+        let registry = registry.read();
+        let entities = self
+            .entities
+            .iter()
+            .map(|entity| {
+                let components = entity
+                    .components
+                    .iter()
+                    .map(|component| {
+                        serde_json::to_value(ReflectSerializer::new(&**component, &registry))
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(SceneEntityFile {
+                    entity: entity.entity,
+                    components,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let file = SceneFile {
+            version: VERSION,
+            entities,
+        };
+        Ok(serde_json::to_string_pretty(&file)?)
+    }
+    /// Parses [`serialize`](Self::serialize)d json, every component type has to be registered.
+    pub fn deserialize(json: &str, registry: &AppTypeRegistry) -> Result<Self> {
+        let file: SceneFile = serde_json::from_str(json)?;
+        if file.version != VERSION {
+            bail!(
+                "scene version {} is not supported, expected {}",
+                file.version,
+                VERSION
+            );
+        }
+        let registry = registry.read();
+        let entities = file
+            .entities
+            .into_iter()
+            .map(|entity| {
+                let components = entity
+                    .components
+                    .into_iter()
+                    .map(|component| {
+                        UntypedReflectDeserializer::new(&registry).deserialize(component)
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(DynamicEntity {
+                    entity: entity.entity,
+                    components,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { entities })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SceneFile {
+    version: u32,
+    entities: Vec<SceneEntityFile>,
+}
+#[derive(Serialize, Deserialize)]
+struct SceneEntityFile {
+    entity: Entity,
+    components: Vec<serde_json::Value>,
+}
+
+/// Loads [`DynamicScene`]s, with the registry of the app it was added to.
+pub struct SceneLoader {
+    registry: AppTypeRegistry,
+}
+impl AssetLoader for SceneLoader {
+    type Asset = DynamicScene;
+    fn extensions(&self) -> &[&str] {
+        &[SCENE_EXTENSION]
+    }
+    fn load(&self, bytes: &[u8], _path: &Path) -> Result<DynamicScene> {
+        DynamicScene::deserialize(std::str::from_utf8(bytes)?, &self.registry)
+    }
+}
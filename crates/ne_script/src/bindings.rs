@@ -0,0 +1,183 @@
+//! Types and functions that scripts can use.
+//!
+//! ```text
+//! let t = world.get_transform(entity);   // () if the entity has no Transform
+//! t.pos.y += 1.0 * dt;
+//! t.rot = t.rot * quat_from_rotation_y(dt);
+//! world.set_transform(entity, t);
+//!
+//! world.get_name(entity);                // () if the entity has no Name
+//! world.set_name(entity, "door");
+//! world.find("player");                  // first entity with that Name, or ()
+//! world.query_transforms();              // array of entities with a Transform
+//! world.query_named();                   // array of entities with a Name
+//! world.send("door_opened", [entity, 2.0]);
+//! ```
+use std::{cell::RefCell, rc::Rc};
+
+use bevy_ecs::{entity::Entity, event::Events, world::World};
+use ne_app::types::Name;
+use ne_math::{Quat, Transform, Vec3};
+use rhai::{Array, Dynamic, Engine, FLOAT, INT};
+
+use crate::{ScriptEvent, ScriptValue};
+
+/// The [`World`] as seen by a script, only valid while scripts run.
+#[derive(Clone)]
+pub struct ScriptWorld(Rc<RefCell<World>>);
+impl ScriptWorld {
+    pub(crate) fn new(world: World) -> Self {
+        Self(Rc::new(RefCell::new(world)))
+    }
+    /// Takes the world back, copies of the handle a script kept see an empty world.
+    pub(crate) fn take(&self) -> World {
+        std::mem::take(&mut *self.0.borrow_mut())
+    }
+    /// The number of copies of the handle.
+    pub(crate) fn handles(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+    fn get_transform(&mut self, entity: Entity) -> Dynamic {
+        self.0
+            .borrow()
+            .get::<Transform>(entity)
+            .map_or(Dynamic::UNIT, |transform| Dynamic::from(transform.clone()))
+    }
+    fn set_transform(&mut self, entity: Entity, transform: Transform) {
+        if let Some(mut target) = self.0.borrow_mut().get_mut::<Transform>(entity) {
+            *target = transform;
+        }
+    }
+    fn get_name(&mut self, entity: Entity) -> Dynamic {
+        self.0
+            .borrow()
+            .get::<Name>(entity)
+            .map_or(Dynamic::UNIT, |name| name.as_str().into())
+    }
+    fn set_name(&mut self, entity: Entity, name: &str) {
+        if let Some(mut target) = self.0.borrow_mut().get_mut::<Name>(entity) {
+            target.set(name.to_owned());
+        }
+    }
+    fn find(&mut self, name: &str) -> Dynamic {
+        let mut world = self.0.borrow_mut();
+        let mut query = world.query::<(Entity, &Name)>();
+        query
+            .iter(&world)
+            .find(|(_, entity_name)| entity_name.as_str() == name)
+            .map_or(Dynamic::UNIT, |(entity, _)| Dynamic::from(entity))
+    }
+    fn query_transforms(&mut self) -> Array {
+        let mut world = self.0.borrow_mut();
+        let mut query = world.query_filtered::<Entity, bevy_ecs::query::With<Transform>>();
+        query.iter(&world).map(Dynamic::from).collect()
+    }
+    fn query_named(&mut self) -> Array {
+        let mut world = self.0.borrow_mut();
+        let mut query = world.query_filtered::<Entity, bevy_ecs::query::With<Name>>();
+        query.iter(&world).map(Dynamic::from).collect()
+    }
+    fn send(&mut self, name: &str, args: Array) {
+        let event = ScriptEvent {
+            name: name.to_owned(),
+            args: args.into_iter().map(ScriptValue::from).collect(),
+        };
+        if let Some(mut events) = self.0.borrow_mut().get_resource_mut::<Events<ScriptEvent>>() {
+            events.send(event);
+        }
+    }
+}
+
+pub(crate) fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<ScriptWorld>("World")
+        .register_fn("get_transform", ScriptWorld::get_transform)
+        .register_fn("set_transform", ScriptWorld::set_transform)
+        .register_fn("get_name", ScriptWorld::get_name)
+        .register_fn("set_name", ScriptWorld::set_name)
+        .register_fn("find", ScriptWorld::find)
+        .register_fn("query_transforms", ScriptWorld::query_transforms)
+        .register_fn("query_named", ScriptWorld::query_named)
+        .register_fn("send", ScriptWorld::send)
+        .register_fn("send", |world: &mut ScriptWorld, name: &str| {
+            world.send(name, Array::new())
+        });
+
+    engine
+        .register_type_with_name::<Entity>("Entity")
+        .register_fn("to_string", |entity: &mut Entity| format!("{:?}", entity))
+        .register_fn("==", |a: Entity, b: Entity| a == b)
+        .register_fn("!=", |a: Entity, b: Entity| a != b);
+
+    engine
+        .register_type_with_name::<Transform>("Transform")
+        .register_fn("transform", Transform::default)
+        .register_get_set(
+            "pos",
+            |transform: &mut Transform| transform.pos,
+            |transform: &mut Transform, pos: Vec3| transform.pos = pos,
+        )
+        .register_get_set(
+            "rot",
+            |transform: &mut Transform| transform.rot,
+            |transform: &mut Transform, rot: Quat| transform.rot = rot,
+        )
+        .register_get_set(
+            "scale",
+            |transform: &mut Transform| transform.scale,
+            |transform: &mut Transform, scale: Vec3| transform.scale = scale,
+        );
+
+    engine
+        .register_type_with_name::<Vec3>("Vec3")
+        .register_fn("vec3", |x: FLOAT, y: FLOAT, z: FLOAT| Vec3::new(x, y, z))
+        .register_get_set("x", |v: &mut Vec3| v.x, |v: &mut Vec3, x: FLOAT| v.x = x)
+        .register_get_set("y", |v: &mut Vec3| v.y, |v: &mut Vec3, y: FLOAT| v.y = y)
+        .register_get_set("z", |v: &mut Vec3| v.z, |v: &mut Vec3, z: FLOAT| v.z = z)
+        .register_fn("+", |a: Vec3, b: Vec3| a + b)
+        .register_fn("-", |a: Vec3, b: Vec3| a - b)
+        .register_fn("*", |a: Vec3, b: FLOAT| a * b)
+        .register_fn("/", |a: Vec3, b: FLOAT| a / b)
+        .register_fn("length", |v: &mut Vec3| v.length())
+        .register_fn("normalize", |v: &mut Vec3| v.normalize_or_zero())
+        .register_fn("to_string", |v: &mut Vec3| v.to_string());
+
+    engine
+        .register_type_with_name::<Quat>("Quat")
+        .register_fn("quat_from_rotation_x", Quat::from_rotation_x)
+        .register_fn("quat_from_rotation_y", Quat::from_rotation_y)
+        .register_fn("quat_from_rotation_z", Quat::from_rotation_z)
+        .register_fn("*", |a: Quat, b: Quat| a * b)
+        .register_fn("*", |a: Quat, b: Vec3| a * b)
+        .register_fn("to_string", |q: &mut Quat| q.to_string());
+}
+
+impl From<Dynamic> for ScriptValue {
+    fn from(value: Dynamic) -> Self {
+        if value.is::<bool>() {
+            ScriptValue::Bool(value.cast())
+        } else if value.is::<INT>() {
+            ScriptValue::Int(value.cast())
+        } else if value.is::<FLOAT>() {
+            ScriptValue::Float(value.cast())
+        } else if value.is::<Entity>() {
+            ScriptValue::Entity(value.cast())
+        } else if value.is_string() {
+            ScriptValue::String(value.into_string().unwrap_or_default())
+        } else {
+            ScriptValue::Unit
+        }
+    }
+}
+impl From<ScriptValue> for Dynamic {
+    fn from(value: ScriptValue) -> Self {
+        match value {
+            ScriptValue::Unit => Dynamic::UNIT,
+            ScriptValue::Bool(value) => value.into(),
+            ScriptValue::Int(value) => value.into(),
+            ScriptValue::Float(value) => value.into(),
+            ScriptValue::String(value) => value.into(),
+            ScriptValue::Entity(value) => Dynamic::from(value),
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Level logic written in [Rhai](https://rhai.rs) scripts.
+//!
+//! An entity gets a [`Script`] component pointing at a `.rhai` file. The script may define
+//! any of these functions, they are called from [`CoreStage::Gameplay`] while the game plays:
+//! ```text
+//! fn on_start(world, entity) { }            // once, the first frame the entity has the script
+//! fn update(world, entity, dt) { }          // every frame
+//! fn on_event(world, entity, name, args) { } // for every ScriptEvent sent since the last frame
+//! ```
+//! See [`bindings`] for what scripts can do with `world`.
+//!
+//! Scripts are reloaded when their file changes. A script that fails to compile logs the
+//! error and the previous version keeps running.
+pub mod bindings;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    event::{Events, ManualEventReader},
+    reflect::ReflectComponent,
+    world::World,
+};
+use bevy_reflect::Reflect;
+use ne_app::{plugin_group::plugin_name, time::{Time, TimePlugin}, App, CoreStage, Plugin};
+use rhai::{CallFnOptions, Dynamic, Engine, AST};
+use tracing::{error, info};
+
+pub use bindings::ScriptWorld;
+
+/// Runs the [`Script`] of every entity and reloads changed script files.
+pub struct ScriptPlugin;
+impl Plugin for ScriptPlugin {
+    fn setup(&self, app: &mut App) {
+        app.register_type::<Script>()
+            .register_type::<PathBuf>()
+            .add_event::<ScriptEvent>()
+            .insert_non_send_resource(ScriptRuntime::default())
+            .add_system_to_stage(CoreStage::Gameplay, script_system);
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![plugin_name::<TimePlugin>()]
+    }
+}
+
+/// Runs the script at `path` for this entity.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct Script {
+    pub path: PathBuf,
+}
+impl Script {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+/// An event scripts can send with `world.send(name, args)` and receive in `on_event`.
+/// Rust systems can send and read it like any other event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptEvent {
+    pub name: String,
+    pub args: Vec<ScriptValue>,
+}
+impl ScriptEvent {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            args: Vec::new(),
+        }
+    }
+    #[must_use]
+    pub fn with_arg(mut self, arg: impl Into<ScriptValue>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+}
+
+/// A value passed between scripts and Rust in a [`ScriptEvent`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptValue {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    String(String),
+    Entity(Entity),
+}
+impl From<bool> for ScriptValue {
+    fn from(value: bool) -> Self {
+        ScriptValue::Bool(value)
+    }
+}
+impl From<i64> for ScriptValue {
+    fn from(value: i64) -> Self {
+        ScriptValue::Int(value)
+    }
+}
+impl From<f32> for ScriptValue {
+    fn from(value: f32) -> Self {
+        ScriptValue::Float(value)
+    }
+}
+impl From<&str> for ScriptValue {
+    fn from(value: &str) -> Self {
+        ScriptValue::String(value.to_owned())
+    }
+}
+impl From<String> for ScriptValue {
+    fn from(value: String) -> Self {
+        ScriptValue::String(value)
+    }
+}
+impl From<Entity> for ScriptValue {
+    fn from(value: Entity) -> Self {
+        ScriptValue::Entity(value)
+    }
+}
+
+struct LoadedScript {
+    ast: AST,
+    modified: Option<SystemTime>,
+}
+impl LoadedScript {
+    fn has_fn(&self, name: &str, params: usize) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|function| function.name == name && function.params.len() == params)
+    }
+}
+
+/// The script engine and the compiled scripts, a non-send resource since the engine isn't `Sync`.
+pub struct ScriptRuntime {
+    engine: Engine,
+    scripts: HashMap<PathBuf, LoadedScript>,
+    /// Scripts that failed to load and have no previous version, retried when the file changes.
+    failed: HashMap<PathBuf, Option<SystemTime>>,
+    started: HashSet<Entity>,
+    event_reader: ManualEventReader<ScriptEvent>,
+    /// How often script files are checked for changes.
+    pub poll_interval: Duration,
+    last_poll: Instant,
+}
+impl Default for ScriptRuntime {
+    fn default() -> Self {
+        let mut engine = Engine::new();
+        bindings::register(&mut engine);
+        engine.on_print(|text| info!("[script] {}", text));
+        Self {
+            engine,
+            scripts: HashMap::new(),
+            failed: HashMap::new(),
+            started: HashSet::new(),
+            event_reader: ManualEventReader::default(),
+            poll_interval: Duration::from_millis(500),
+            last_poll: Instant::now(),
+        }
+    }
+}
+impl ScriptRuntime {
+    /// The Rhai engine, to register more functions for scripts.
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+    fn compile(&mut self, path: &Path) {
+        let modified = Self::modified(path);
+        match self.engine.compile_file(path.to_path_buf()) {
+            Ok(ast) => {
+                self.failed.remove(path);
+                self.scripts
+                    .insert(path.to_path_buf(), LoadedScript { ast, modified });
+            }
+            Err(err) => {
+                error!("Failed to load script {}: {}", path.display(), err);
+                match self.scripts.get_mut(path) {
+                    // Keep running the previous version, but don't retry until the file changes again.
+                    Some(script) => script.modified = modified,
+                    None => {
+                        self.failed.insert(path.to_path_buf(), modified);
+                    }
+                }
+            }
+        }
+    }
+    /// Compiles scripts that are new and recompiles the ones whose file changed.
+    fn load(&mut self, paths: &HashSet<PathBuf>) {
+        let poll = self.last_poll.elapsed() >= self.poll_interval;
+        if poll {
+            self.last_poll = Instant::now();
+        }
+        for path in paths {
+            let known_modified = match (self.scripts.get(path), self.failed.get(path)) {
+                (Some(script), _) => script.modified,
+                (None, Some(modified)) => *modified,
+                (None, None) => {
+                    self.compile(path);
+                    continue;
+                }
+            };
+            if poll && Self::modified(path) != known_modified {
+                info!("Reloading script {}", path.display());
+                self.compile(path);
+            }
+        }
+    }
+    fn call(&self, world: &ScriptWorld, path: &Path, name: &str, args: impl rhai::FuncArgs) {
+        let Some(script) = self.scripts.get(path) else {
+            return;
+        };
+        let mut args_vec = Vec::new();
+        args.parse(&mut args_vec);
+        if !script.has_fn(name, args_vec.len() + 1) {
+            return;
+        }
+        let mut all_args = vec![Dynamic::from(world.clone())];
+        all_args.extend(args_vec);
+        let handles = world.handles();
+        let options = CallFnOptions::new().eval_ast(false);
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut rhai::Scope::new(),
+            &script.ast,
+            name,
+            all_args,
+        );
+        if let Err(err) = result {
+            error!("Script {} failed in {}: {}", path.display(), name, err);
+        }
+        if world.handles() > handles {
+            error!(
+                "Script {} kept the world in {}, it is only valid while scripts run",
+                path.display(),
+                name
+            );
+        }
+    }
+}
+
+/// Puts the world back when the scripts are done, even if one of them panics.
+struct WorldGuard<'w> {
+    world: &'w mut World,
+    script_world: ScriptWorld,
+}
+impl Drop for WorldGuard<'_> {
+    fn drop(&mut self) {
+        *self.world = self.script_world.take();
+    }
+}
+
+/// Calls the script functions of every entity with a [`Script`].
+pub fn script_system(world: &mut World) {
+    let Some(mut runtime) = world.remove_non_send_resource::<ScriptRuntime>() else {
+        return;
+    };
+    let mut query = world.query::<(Entity, &Script)>();
+    let scripted: Vec<(Entity, PathBuf)> = query
+        .iter(world)
+        .map(|(entity, script)| (entity, script.path.clone()))
+        .collect();
+    let paths = scripted.iter().map(|(_, path)| path.clone()).collect();
+    runtime.load(&paths);
+    runtime
+        .started
+        .retain(|entity| scripted.iter().any(|(scripted, _)| scripted == entity));
+
+    let dt = world
+        .get_resource::<Time>()
+        .map_or(0.0, |time| time.delta_seconds());
+    let events: Vec<ScriptEvent> = match world.get_resource::<Events<ScriptEvent>>() {
+        Some(events) => runtime.event_reader.iter(events).cloned().collect(),
+        None => Vec::new(),
+    };
+
+    let guard = WorldGuard {
+        script_world: ScriptWorld::new(std::mem::take(world)),
+        world,
+    };
+    let script_world = &guard.script_world;
+    for (entity, path) in &scripted {
+        if runtime.started.insert(*entity) {
+            runtime.call(script_world, path, "on_start", (*entity,));
+        }
+        runtime.call(script_world, path, "update", (*entity, dt));
+        for event in &events {
+            let args: rhai::Array = event.args.iter().cloned().map(Dynamic::from).collect();
+            runtime.call(
+                script_world,
+                path,
+                "on_event",
+                (*entity, event.name.clone(), args),
+            );
+        }
+    }
+    drop(guard);
+    world.insert_non_send_resource(runtime);
+}
@@ -0,0 +1,187 @@
+//! Game actions bound to keys and mouse buttons, so gameplay code doesn't hardcode its inputs.
+//!
+//! ```ignore
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+//! enum PlayerAction { Jump, Fire }
+//!
+//! app.insert_resource(
+//!     InputMap::default()
+//!         .with(PlayerAction::Jump, VirtualKeyCode::Space)
+//!         .with(PlayerAction::Fire, MouseButton::Left),
+//! )
+//! .add_plugin(ActionPlugin::<PlayerAction>::default());
+//!
+//! fn jump(actions: Res<Input<PlayerAction>>) {
+//!     if actions.just_pressed(PlayerAction::Jump) { /* .. */ }
+//! }
+//! ```
+//!
+//! Bindings are also written as text, `"Space"` for a key and `"Mouse:Left"` for a mouse
+//! button, so they can be loaded from files.
+use std::{collections::HashMap, fmt, hash::Hash, marker::PhantomData, str::FromStr};
+
+use bevy_ecs::{
+    schedule::{IntoSystemDescriptor, SystemLabel},
+    system::{Res, ResMut, Resource},
+};
+use ne_app::{plugin_group::plugin_name, App, CoreStage, Plugin};
+use serde::{de::value::StrDeserializer, Deserialize, Serialize};
+
+use crate::{
+    events::{MouseButton, VirtualKeyCode},
+    input::{Input, InputPlugin, InputSystem},
+};
+
+/// Types that can be used as actions.
+pub trait Action: Copy + Eq + Hash + Send + Sync + 'static {}
+impl<T: Copy + Eq + Hash + Send + Sync + 'static> Action for T {}
+
+/// An input an action can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Binding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+impl From<VirtualKeyCode> for Binding {
+    fn from(key: VirtualKeyCode) -> Self {
+        Binding::Key(key)
+    }
+}
+impl From<MouseButton> for Binding {
+    fn from(button: MouseButton) -> Self {
+        Binding::Mouse(button)
+    }
+}
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // The names of the variants, the same ones `from_str` reads.
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Mouse(MouseButton::Other(button)) => write!(f, "Mouse:{}", button),
+            Binding::Mouse(button) => write!(f, "Mouse:{:?}", button),
+        }
+    }
+}
+impl FromStr for Binding {
+    type Err = String;
+    /// Reads a key name like `"Space"` or `"A"`, or a mouse button like `"Mouse:Left"` or
+    /// `"Mouse:4"`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if let Some(button) = text.strip_prefix("Mouse:") {
+            return match button {
+                "Left" => Ok(Binding::Mouse(MouseButton::Left)),
+                "Right" => Ok(Binding::Mouse(MouseButton::Right)),
+                "Middle" => Ok(Binding::Mouse(MouseButton::Middle)),
+                _ => button
+                    .parse()
+                    .map(|button| Binding::Mouse(MouseButton::Other(button)))
+                    .map_err(|_| format!("unknown mouse button {}", button)),
+            };
+        }
+        VirtualKeyCode::deserialize(StrDeserializer::<serde::de::value::Error>::new(text))
+            .map(Binding::Key)
+            .map_err(|_| format!("unknown key {}", text))
+    }
+}
+impl TryFrom<String> for Binding {
+    type Error = String;
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+impl From<Binding> for String {
+    fn from(binding: Binding) -> Self {
+        binding.to_string()
+    }
+}
+
+/// The bindings of the actions of type `A`, an action is pressed while any of its bindings is.
+#[derive(Debug, Clone, Resource)]
+pub struct InputMap<A: Action> {
+    bindings: HashMap<A, Vec<Binding>>,
+}
+impl<A: Action> Default for InputMap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+}
+impl<A: Action> InputMap<A> {
+    /// Binds `action` to one more input.
+    pub fn bind(&mut self, action: A, binding: impl Into<Binding>) -> &mut Self {
+        let binding = binding.into();
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+    /// Builder version of [`bind`](Self::bind).
+    #[must_use]
+    pub fn with(mut self, action: A, binding: impl Into<Binding>) -> Self {
+        self.bind(action, binding);
+        self
+    }
+    /// Removes all bindings of `action`.
+    pub fn unbind(&mut self, action: A) {
+        self.bindings.remove(&action);
+    }
+    pub fn bindings(&self, action: A) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (A, &[Binding])> {
+        self.bindings
+            .iter()
+            .map(|(action, bindings)| (*action, bindings.as_slice()))
+    }
+}
+
+/// Adds [`InputMap<A>`] (unless it was inserted before) and keeps [`Input<A>`] up to date
+/// with it in [`CoreStage::PreUpdate`].
+pub struct ActionPlugin<A: Action>(PhantomData<A>);
+impl<A: Action> Default for ActionPlugin<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+impl<A: Action> Plugin for ActionPlugin<A> {
+    fn setup(&self, app: &mut App) {
+        app.init_resource::<InputMap<A>>()
+            .init_resource::<Input<A>>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                action_input_system::<A>
+                    .label(ActionSystem)
+                    .after(InputSystem),
+            );
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![plugin_name::<InputPlugin>()]
+    }
+}
+
+/// Label of the systems that update the [`Input`] of actions.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub struct ActionSystem;
+
+pub fn action_input_system<A: Action>(
+    input_map: Res<InputMap<A>>,
+    keys: Res<Input<VirtualKeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut actions: ResMut<Input<A>>,
+) {
+    actions.clear();
+    for (action, bindings) in input_map.iter() {
+        let pressed = bindings.iter().any(|binding| match binding {
+            Binding::Key(key) => keys.pressed(*key),
+            Binding::Mouse(button) => mouse_buttons.pressed(*button),
+        });
+        if pressed {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
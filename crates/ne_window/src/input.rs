@@ -0,0 +1,157 @@
+//! Pressed state of keys and mouse buttons, built from the window input events.
+use std::{collections::HashSet, hash::Hash};
+
+use bevy_ecs::{
+    event::EventReader,
+    schedule::{IntoSystemDescriptor, SystemLabel},
+    system::{ResMut, Resource},
+};
+use ne_app::{plugin_group::plugin_name, App, CoreStage, Plugin};
+use ne_math::Vec2;
+
+use crate::{
+    events::{
+        ElementState, MouseButton, OnKeyboardInput, OnMouseButton, OnMouseMotion, VirtualKeyCode,
+    },
+    WindowEventPlugin,
+};
+
+/// Adds [`Input<VirtualKeyCode>`], [`Input<MouseButton>`] and [`MouseMotion`],
+/// updated in [`CoreStage::PreUpdate`].
+pub struct InputPlugin;
+impl Plugin for InputPlugin {
+    fn setup(&self, app: &mut App) {
+        app.init_resource::<Input<VirtualKeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<MouseMotion>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                keyboard_input_system.label(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                mouse_button_input_system.label(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                mouse_motion_system.label(InputSystem),
+            );
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![plugin_name::<WindowEventPlugin>()]
+    }
+}
+
+/// Label of the systems that update the input resources.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub struct InputSystem;
+
+/// How far the mouse moved this frame, in raw device units. Unlike the cursor it keeps
+/// reporting movement at the edges of the window, use it for looking around.
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct MouseMotion {
+    pub delta: Vec2,
+}
+
+/// Which inputs of type `T` are held down, and which changed this frame.
+#[derive(Debug, Clone, Resource)]
+pub struct Input<T: Copy + Eq + Hash + Send + Sync + 'static> {
+    pressed: HashSet<T>,
+    just_pressed: HashSet<T>,
+    just_released: HashSet<T>,
+}
+impl<T: Copy + Eq + Hash + Send + Sync + 'static> Default for Input<T> {
+    fn default() -> Self {
+        Self {
+            pressed: HashSet::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+        }
+    }
+}
+impl<T: Copy + Eq + Hash + Send + Sync + 'static> Input<T> {
+    pub fn press(&mut self, input: T) {
+        if self.pressed.insert(input) {
+            self.just_pressed.insert(input);
+        }
+    }
+    pub fn release(&mut self, input: T) {
+        if self.pressed.remove(&input) {
+            self.just_released.insert(input);
+        }
+    }
+    /// Returns `true` while `input` is held down.
+    pub fn pressed(&self, input: T) -> bool {
+        self.pressed.contains(&input)
+    }
+    /// Returns `true` if any of `inputs` is held down.
+    pub fn any_pressed(&self, inputs: impl IntoIterator<Item = T>) -> bool {
+        inputs.into_iter().any(|input| self.pressed(input))
+    }
+    /// Returns `true` on the frame `input` got pressed.
+    pub fn just_pressed(&self, input: T) -> bool {
+        self.just_pressed.contains(&input)
+    }
+    /// Returns `true` on the frame `input` got released.
+    pub fn just_released(&self, input: T) -> bool {
+        self.just_released.contains(&input)
+    }
+    /// `1.0` while only `positive` is held down, `-1.0` while only `negative` is, `0.0` otherwise.
+    pub fn axis(&self, negative: T, positive: T) -> f32 {
+        self.pressed(positive) as i8 as f32 - self.pressed(negative) as i8 as f32
+    }
+    pub fn get_pressed(&self) -> impl ExactSizeIterator<Item = &T> {
+        self.pressed.iter()
+    }
+    pub fn get_just_pressed(&self) -> impl ExactSizeIterator<Item = &T> {
+        self.just_pressed.iter()
+    }
+    pub fn get_just_released(&self) -> impl ExactSizeIterator<Item = &T> {
+        self.just_released.iter()
+    }
+    /// Forgets the changes of the previous frame, the held down inputs are kept.
+    pub fn clear(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+    /// Releases everything, e.g. when the window loses focus.
+    pub fn reset_all(&mut self) {
+        self.just_released.extend(self.pressed.drain());
+        self.just_pressed.clear();
+    }
+}
+
+pub fn keyboard_input_system(
+    mut keyboard_input: EventReader<OnKeyboardInput>,
+    mut keys: ResMut<Input<VirtualKeyCode>>,
+) {
+    keys.clear();
+    for event in keyboard_input.iter() {
+        match event.state {
+            ElementState::Pressed => keys.press(event.key),
+            ElementState::Released => keys.release(event.key),
+        }
+    }
+}
+
+pub fn mouse_button_input_system(
+    mut mouse_button: EventReader<OnMouseButton>,
+    mut buttons: ResMut<Input<MouseButton>>,
+) {
+    buttons.clear();
+    for event in mouse_button.iter() {
+        match event.state {
+            ElementState::Pressed => buttons.press(event.button),
+            ElementState::Released => buttons.release(event.button),
+        }
+    }
+}
+
+pub fn mouse_motion_system(
+    mut mouse_motion_events: EventReader<OnMouseMotion>,
+    mut mouse_motion: ResMut<MouseMotion>,
+) {
+    mouse_motion.delta = mouse_motion_events
+        .iter()
+        .fold(Vec2::ZERO, |delta, event| delta + event.delta);
+}
@@ -1,5 +1,7 @@
 use ne_app::{App, Plugin};
 pub mod events;
+pub mod action;
+pub mod input;
 
 pub struct WindowEventPlugin;
 impl Plugin for WindowEventPlugin {
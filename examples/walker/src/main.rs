@@ -1,2 +1,141 @@
+//! Walk around a small level with a character controller.
+//! WASD to walk, space to jump, shift to sprint, mouse to look around and tab to switch between
+//! first- and third-person.
+use bevy_ecs::{
+    query::With,
+    schedule::IntoSystemDescriptor,
+    system::{Commands, Query, Res, ResMut},
+};
+use ne_app::App;
+use ne_character::{
+    CameraRig, CharacterInputPlugin, CharacterSystem, PlayerCharacter, RigMode,
+};
+use ne_math::{Quat, Transform, Vec3};
+use ne_physics::{CharacterController, Collider, PhysicsPlugin, RigidBody};
+use ne_render::{
+    cameras::free_fly_camera::{Camera, CameraUniform, Projection},
+    material::Material,
+    mesh::{Shapes, StaticMesh},
+    render_structs::{RenderDevice, RenderQueue},
+    NCameraBuffer, NSurfaceConfig, WindowSettings,
+};
+use ne_window::{events::VirtualKeyCode, input::Input};
+use nightmare_engine::DefaultPlugins;
+
 fn main() {
+    App::new()
+        .insert_resource(WindowSettings {
+            title: "Walker".to_string(),
+            ..WindowSettings::default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_plugin(PhysicsPlugin)
+        .add_plugin(CharacterInputPlugin)
+        .insert_resource(Camera::new(Vec3::ZERO, 0.0, 0.0))
+        .add_startup_system(spawn_level)
+        .add_system(switch_rig)
+        .add_system_to_stage(
+            ne_app::CoreStage::PostUpdate,
+            follow_rig.after(CharacterSystem::PlaceCamera),
+        )
+        .run();
+}
+
+/// A floor, a staircase, a ramp, a wall and the player.
+fn spawn_level(
+    mut commands: Commands,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    surface_conf: Res<NSurfaceConfig>,
+    camera_buffer: Res<NCameraBuffer>,
+) {
+    let grid = Material::from_bytes(
+        &device,
+        &queue,
+        include_bytes!("../../../engine_assets/textures/grid.png"),
+        Some("grid.png"),
+    )
+    .unwrap();
+    let mut spawn_box = |size: Vec3, pos: Vec3, rot: Quat| {
+        let transform = Transform {
+            pos,
+            rot,
+            ..Default::default()
+        };
+        commands.spawn((
+            StaticMesh::new(
+                &camera_buffer,
+                &surface_conf,
+                &device,
+                transform,
+                Shapes::create_box(size.x, size.y, size.z),
+                &grid,
+            ),
+            Collider::cuboid(size.x, size.y, size.z),
+        ));
+    };
+    spawn_box(Vec3::new(40.0, 1.0, 40.0), Vec3::new(0.0, -0.5, 0.0), Quat::IDENTITY);
+    for step in 0..8 {
+        let height = 0.2 * (step + 1) as f32;
+        spawn_box(
+            Vec3::new(1.0, height, 3.0),
+            Vec3::new(4.0 + step as f32, height / 2.0, -4.0),
+            Quat::IDENTITY,
+        );
+    }
+    // 30 degrees can be walked up, 60 degrees is too steep.
+    spawn_box(
+        Vec3::new(6.0, 0.2, 3.0),
+        Vec3::new(-5.0, 1.0, -4.0),
+        Quat::from_rotation_z(30.0_f32.to_radians()),
+    );
+    spawn_box(
+        Vec3::new(6.0, 0.2, 3.0),
+        Vec3::new(-5.0, 2.0, 4.0),
+        Quat::from_rotation_z(60.0_f32.to_radians()),
+    );
+    spawn_box(Vec3::new(20.0, 4.0, 1.0), Vec3::new(0.0, 2.0, -12.0), Quat::IDENTITY);
+
+    commands.spawn((
+        Transform {
+            pos: Vec3::new(0.0, 2.0, 6.0),
+            rot: Quat::IDENTITY,
+            ..Default::default()
+        },
+        RigidBody::KinematicPositionBased,
+        Collider::capsule(1.8, 0.3),
+        CharacterController::default(),
+        PlayerCharacter::default(),
+        CameraRig::first_person(0.7),
+    ));
+}
+
+fn switch_rig(keys: Res<Input<VirtualKeyCode>>, mut rigs: Query<&mut CameraRig>) {
+    if !keys.just_pressed(VirtualKeyCode::Tab) {
+        return;
+    }
+    for mut rig in &mut rigs {
+        rig.mode = match rig.mode {
+            RigMode::FirstPerson { .. } => RigMode::ThirdPerson {
+                distance: 5.0,
+                height: 1.0,
+            },
+            RigMode::ThirdPerson { .. } => RigMode::FirstPerson { eye_height: 0.7 },
+        };
+    }
+}
+
+/// Renders the scene from the player's camera rig.
+fn follow_rig(
+    rigs: Query<&CameraRig, With<PlayerCharacter>>,
+    mut camera: ResMut<Camera>,
+    mut camera_uniform: ResMut<CameraUniform>,
+    projection: Res<Projection>,
+) {
+    let Ok(rig) = rigs.get_single() else {
+        return;
+    };
+    camera.position = rig.eye();
+    camera.set_rotation(rig.yaw(), rig.pitch());
+    camera_uniform.update_view_proj(&camera, &projection);
 }
@@ -0,0 +1,692 @@
+//! The File Explorer: browses the files of the project's asset directories.
+//!
+//! Textures and meshes are shown with a thumbnail. Dragging a mesh into the viewport spawns it,
+//! a texture becomes a material of the mesh it is dropped on and a scene is added to the world.
+//! Files dropped onto the window are copied into the project.
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use bevy_ecs::{
+    prelude::{Entity, EventReader, With},
+    system::{Res, ResMut},
+    world::{Mut, World},
+};
+use ne_app::{
+    hierarchy::{set_parent, Parent},
+    reflect::AppTypeRegistry,
+    types::Name,
+    Resource,
+};
+use ne_assets::{AssetServer, Assets, LoadState};
+use ne_cook::{AssetKind, MESH_EXTENSION, TEXTURE_EXTENSION};
+use ne_math::{EulerRot, Quat, Transform, Vec2, Vec3};
+use ne_render::{
+    egui::{self, Color32, Pos2, Stroke},
+    material::{Image, Material, NamedMaterial},
+    mesh::{MeshMaterial, MeshPrimitives, Model, StaticMesh},
+    render_structs::{RenderDevice, RenderQueue},
+    NCameraBuffer, NSurfaceConfig, NUiState, UiContext,
+};
+use ne_scene::{DynamicScene, SCENE_EXTENSION};
+use ne_window::events::OnFileDragAndDrop;
+use tracing::{error, info, warn};
+
+use super::{
+    history::{self, History, SetMeshMaterial, Spawn},
+    picking::pick,
+    viewport::Viewport,
+    Selection,
+};
+
+/// Size of a thumbnail, in points.
+const THUMBNAIL_SIZE: f32 = 64.0;
+/// Size of the texture thumbnails are made from, in pixels.
+const THUMBNAIL_PIXELS: u32 = 64;
+/// Meshes with more triangles draw only some of them in their thumbnail.
+const THUMBNAIL_TRIANGLES: usize = 1500;
+const LINE_WIDTH: f32 = 1.0;
+/// How far in front of the camera assets are placed when they are dropped on nothing.
+const DROP_DISTANCE: f32 = 10.0;
+
+/// The kinds of files the browser tells apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AssetType {
+    Texture,
+    Mesh,
+    Scene,
+    Other,
+}
+impl AssetType {
+    const ALL: [AssetType; 4] = [
+        AssetType::Texture,
+        AssetType::Mesh,
+        AssetType::Scene,
+        AssetType::Other,
+    ];
+    fn of(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            TEXTURE_EXTENSION => AssetType::Texture,
+            MESH_EXTENSION => AssetType::Mesh,
+            SCENE_EXTENSION => AssetType::Scene,
+            _ => match AssetKind::of(path) {
+                AssetKind::Texture => AssetType::Texture,
+                AssetKind::Mesh => AssetType::Mesh,
+                AssetKind::Other => AssetType::Other,
+            },
+        }
+    }
+    fn label(self) -> &'static str {
+        match self {
+            AssetType::Texture => "Textures",
+            AssetType::Mesh => "Meshes",
+            AssetType::Scene => "Scenes",
+            AssetType::Other => "Other",
+        }
+    }
+    /// The directory imported files of this type are copied to.
+    fn import_dir(self) -> &'static str {
+        match self {
+            AssetType::Texture => "textures",
+            AssetType::Mesh => "3D",
+            AssetType::Scene => "scenes",
+            AssetType::Other => "",
+        }
+    }
+}
+
+enum Thumbnail {
+    Texture(egui::TextureHandle),
+    /// The edges of the mesh seen from above at an angle, inside `0..1`.
+    Mesh(Vec<[Vec2; 2]>),
+    Failed,
+}
+
+/// An asset dropped into the viewport, placed once it is loaded.
+struct Placement {
+    path: String,
+    point: Vec3,
+    /// The mesh it was dropped on.
+    target: Option<Entity>,
+}
+
+#[derive(Resource, Default)]
+pub(super) struct AssetBrowser {
+    /// The files of all asset roots, listed again after an import or on refresh.
+    files: Option<Vec<String>>,
+    /// The directory that is shown, `""` for the top.
+    dir: String,
+    search: String,
+    filter: Option<AssetType>,
+    thumbnails: HashMap<String, Thumbnail>,
+    /// Assets being loaded to be placed.
+    placements: Vec<Placement>,
+    /// Whether files are dragged over the window.
+    hovering_files: bool,
+}
+impl AssetBrowser {
+    fn refresh(&mut self) {
+        self.files = None;
+    }
+    /// The files in the shown directory that pass the search and filter. Searching looks in
+    /// every directory.
+    fn shown_files<'a>(&'a self, files: &'a [String]) -> impl Iterator<Item = &'a String> {
+        let search = self.search.to_lowercase();
+        files.iter().filter(move |file| {
+            let path = Path::new(file.as_str());
+            let in_dir = if search.is_empty() {
+                parent(file) == self.dir
+            } else {
+                file_name(path).to_lowercase().contains(&search)
+            };
+            in_dir
+                && self
+                    .filter
+                    .is_none_or(|filter| AssetType::of(path) == filter)
+        })
+    }
+}
+
+pub(super) fn asset_browser_system(world: &mut World) {
+    let ctx = world.resource::<UiContext>().clone();
+    let (title, mut open) = world.resource::<NUiState>().widget_file_explorer.clone();
+    world.resource_scope(|world, mut browser: Mut<AssetBrowser>| {
+        place_loaded(world, &mut browser);
+        if !open {
+            return;
+        }
+        let server = world.resource::<AssetServer>().clone();
+        let files = browser
+            .files
+            .get_or_insert_with(|| {
+                let mut files = server.vfs().files();
+                files.sort();
+                files
+            })
+            .clone();
+        let mut released = None;
+        egui::Window::new(title)
+            .open(&mut open)
+            .default_pos([10.0, 500.0])
+            .default_size([520.0, 300.0])
+            .show(&ctx, |ui| {
+                toolbar_ui(&mut browser, ui);
+                ui.separator();
+                ui.horizontal_top(|ui| {
+                    dirs_ui(&mut browser, &files, ui);
+                    ui.separator();
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            let shown: Vec<String> = browser.shown_files(&files).cloned().collect();
+                            for file in shown {
+                                load_thumbnail(world, &mut browser, &file, ui.ctx());
+                                let response = tile_ui(&browser, &file, ui);
+                                if response.dragged() {
+                                    dragged_ui(&file, ui.ctx());
+                                }
+                                if response.drag_released() {
+                                    released = Some(file);
+                                }
+                            }
+                        });
+                    });
+                });
+                if browser.hovering_files {
+                    ui.painter().rect_stroke(
+                        ui.max_rect(),
+                        4.0,
+                        Stroke::new(LINE_WIDTH, ui.visuals().selection.stroke.color),
+                    );
+                    ui.label("Drop the files to import them.");
+                }
+            });
+        let pos = ctx.input().pointer.interact_pos();
+        if let (Some(path), Some(pos)) = (released, pos) {
+            // Assets released over a window aren't dropped.
+            if !ctx.is_pointer_over_area() {
+                drop_in_viewport(world, &mut browser, path, pos, &ctx);
+            }
+        }
+    });
+    world.resource_mut::<NUiState>().widget_file_explorer.1 = open;
+}
+
+fn toolbar_ui(browser: &mut AssetBrowser, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.label("Search");
+        ui.text_edit_singleline(&mut browser.search);
+        egui::ComboBox::from_id_source("asset filter")
+            .selected_text(browser.filter.map_or("All", AssetType::label))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut browser.filter, None, "All");
+                for asset_type in AssetType::ALL {
+                    ui.selectable_value(&mut browser.filter, Some(asset_type), asset_type.label());
+                }
+            });
+        if ui.button("Refresh").clicked() {
+            browser.refresh();
+        }
+    });
+}
+
+/// The directories of the files, clicking one shows its files.
+fn dirs_ui(browser: &mut AssetBrowser, files: &[String], ui: &mut egui::Ui) {
+    let mut dirs = BTreeSet::new();
+    for file in files {
+        let mut dir = parent(file);
+        while !dir.is_empty() {
+            dirs.insert(dir.to_owned());
+            dir = parent(dir);
+        }
+    }
+    egui::ScrollArea::vertical()
+        .id_source("asset dirs")
+        .show(ui, |ui| {
+            ui.set_width(120.0);
+            if ui
+                .selectable_label(browser.dir.is_empty(), "assets")
+                .clicked()
+            {
+                browser.dir.clear();
+            }
+            for dir in dirs {
+                let depth = dir.matches('/').count() + 1;
+                let name = file_name(Path::new(&dir)).to_owned();
+                ui.horizontal(|ui| {
+                    ui.add_space(depth as f32 * 8.0);
+                    if ui.selectable_label(browser.dir == dir, name).clicked() {
+                        browser.dir = dir.clone();
+                    }
+                });
+            }
+        });
+}
+
+fn tile_ui(browser: &AssetBrowser, file: &str, ui: &mut egui::Ui) -> egui::Response {
+    let size = egui::vec2(THUMBNAIL_SIZE + 8.0, THUMBNAIL_SIZE + 24.0);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.style().interact(&response);
+    if response.hovered() || response.dragged() {
+        painter.rect_filled(rect, 4.0, visuals.bg_fill);
+    }
+    let thumbnail = egui::Rect::from_min_size(
+        rect.min + egui::vec2(4.0, 4.0),
+        egui::Vec2::splat(THUMBNAIL_SIZE),
+    );
+    match browser.thumbnails.get(file) {
+        Some(Thumbnail::Texture(texture)) => {
+            let scale = THUMBNAIL_SIZE / texture.size_vec2().max_elem();
+            let image =
+                egui::Rect::from_center_size(thumbnail.center(), texture.size_vec2() * scale);
+            let uv = egui::Rect::from_min_max(Pos2::ZERO, egui::pos2(1.0, 1.0));
+            painter.add(egui::Shape::image(texture.id(), image, uv, Color32::WHITE));
+        }
+        Some(Thumbnail::Mesh(lines)) => {
+            let stroke = Stroke::new(LINE_WIDTH, visuals.fg_stroke.color);
+            let to_rect = |point: Vec2| thumbnail.lerp(egui::vec2(point.x, point.y));
+            for [a, b] in lines {
+                painter.line_segment([to_rect(*a), to_rect(*b)], stroke);
+            }
+        }
+        Some(Thumbnail::Failed) | None => {
+            let label = AssetType::of(Path::new(file)).label();
+            painter.text(
+                thumbnail.center(),
+                egui::Align2::CENTER_CENTER,
+                label,
+                egui::FontId::proportional(12.0),
+                visuals.fg_stroke.color,
+            );
+        }
+    }
+    painter.text(
+        egui::pos2(rect.center().x, rect.bottom() - 10.0),
+        egui::Align2::CENTER_CENTER,
+        file_name(Path::new(file)),
+        egui::FontId::proportional(11.0),
+        visuals.text_color(),
+    );
+    response.on_hover_text(file)
+}
+
+/// The name of the dragged file next to the pointer.
+fn dragged_ui(file: &str, ctx: &egui::Context) {
+    let Some(pos) = ctx.input().pointer.hover_pos() else {
+        return;
+    };
+    egui::Area::new("dragged asset")
+        .order(egui::Order::Tooltip)
+        .fixed_pos(pos + egui::vec2(12.0, 0.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.label(file_name(Path::new(file)));
+        });
+}
+
+/// Starts loading the asset of a thumbnail, and makes the thumbnail once it is loaded.
+fn load_thumbnail(world: &World, browser: &mut AssetBrowser, file: &str, ctx: &egui::Context) {
+    if browser.thumbnails.contains_key(file) {
+        return;
+    }
+    let server = world.resource::<AssetServer>();
+    let thumbnail = match AssetType::of(Path::new(file)) {
+        AssetType::Texture => {
+            let handle = server.load::<Image>(file);
+            match world.resource::<Assets<Image>>().get(handle) {
+                Some(image) => Thumbnail::Texture(ctx.load_texture(
+                    file,
+                    texture_thumbnail(image),
+                    egui::TextureFilter::Linear,
+                )),
+                None if server.load_state(handle) == LoadState::Failed => Thumbnail::Failed,
+                None => return,
+            }
+        }
+        AssetType::Mesh => {
+            let handle = server.load::<Model>(file);
+            match world.resource::<Assets<Model>>().get(handle) {
+                Some(model) => Thumbnail::Mesh(mesh_thumbnail(&model.meshes)),
+                None if server.load_state(handle) == LoadState::Failed => Thumbnail::Failed,
+                None => return,
+            }
+        }
+        AssetType::Scene | AssetType::Other => Thumbnail::Failed,
+    };
+    browser.thumbnails.insert(file.to_owned(), thumbnail);
+}
+
+/// The smallest mip level at least [`THUMBNAIL_PIXELS`] large, scaled down to it.
+fn texture_thumbnail(image: &Image) -> egui::ColorImage {
+    let texture = image.texture.to_rgba8();
+    let level = (0..texture.mips.len())
+        .rev()
+        .find(|&level| {
+            let (width, height) = texture.mip_size(level);
+            width.max(height) >= THUMBNAIL_PIXELS
+        })
+        .unwrap_or(0);
+    let (width, height) = texture.mip_size(level);
+    let pixels = &texture.mips[level];
+    let scale = (THUMBNAIL_PIXELS as f32 / width.max(height) as f32).min(1.0);
+    let size = [
+        ((width as f32 * scale) as usize).max(1),
+        ((height as f32 * scale) as usize).max(1),
+    ];
+    let mut rgba = Vec::with_capacity(size[0] * size[1] * 4);
+    for y in 0..size[1] {
+        for x in 0..size[0] {
+            let source_x = (x as f32 / scale) as usize;
+            let source_y = (y as f32 / scale) as usize;
+            let i = (source_y * width as usize + source_x) * 4;
+            rgba.extend_from_slice(&pixels[i..i + 4]);
+        }
+    }
+    egui::ColorImage::from_rgba_unmultiplied(size, &rgba)
+}
+
+/// The edges of the triangles of `meshes`, turned and fitted inside `0..1`.
+fn mesh_thumbnail(meshes: &[MeshPrimitives]) -> Vec<[Vec2; 2]> {
+    let view = Quat::from_euler(EulerRot::YXZ, 45f32.to_radians(), 30f32.to_radians(), 0.0);
+    let triangle_count: usize = meshes.iter().map(|mesh| mesh.indices().count() / 3).sum();
+    let step = triangle_count.div_ceil(THUMBNAIL_TRIANGLES).max(1);
+    let mut lines = Vec::new();
+    for mesh in meshes {
+        let project = |index: u32| {
+            let point = view * Vec3::from(mesh.vertices()[index as usize].position());
+            // Up on screen is down in egui.
+            Vec2::new(point.x, -point.y)
+        };
+        let indices: Vec<u32> = mesh.indices().collect();
+        for triangle in indices.chunks_exact(3).step_by(step) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(project);
+            lines.extend([[a, b], [b, c], [c, a]]);
+        }
+    }
+    let (min, max) = lines.iter().flatten().fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), &point| (min.min(point), max.max(point)),
+    );
+    let size = (max - min).max_element().max(f32::EPSILON);
+    // Centered along the shorter side.
+    let offset = (Vec2::splat(size) - (max - min)) / 2.0;
+    for line in &mut lines {
+        for point in line {
+            *point = (*point - min + offset) / size;
+        }
+    }
+    lines
+}
+
+/// Places the dropped asset on the mesh under `pos`, on the ground or in front of the camera.
+fn drop_in_viewport(
+    world: &mut World,
+    browser: &mut AssetBrowser,
+    path: String,
+    pos: Pos2,
+    ctx: &egui::Context,
+) {
+    let Some(viewport) = Viewport::new(world, ctx) else {
+        return;
+    };
+    let ray = viewport.ray(pos);
+    let hit = pick(world, ray);
+    let point = match hit {
+        Some((_, distance)) => ray.get_point(distance),
+        None if ray.direction.y < 0.0 => ray.get_point(-ray.origin.y / ray.direction.y),
+        None => ray.get_point(DROP_DISTANCE),
+    };
+    browser.placements.push(Placement {
+        path,
+        point,
+        target: hit.map(|(entity, _)| entity),
+    });
+}
+
+/// Places the dropped assets that finished loading.
+fn place_loaded(world: &mut World, browser: &mut AssetBrowser) {
+    browser
+        .placements
+        .retain(|placement| match try_place(world, placement) {
+            Ok(placed) => !placed,
+            Err(err) => {
+                error!("could not place {}: {err:#}", placement.path);
+                false
+            }
+        });
+}
+
+/// Returns whether the asset was placed, false while it is loading.
+fn try_place(world: &mut World, placement: &Placement) -> Result<bool> {
+    let server = world.resource::<AssetServer>().clone();
+    let path = placement.path.as_str();
+    let failed = || anyhow!("it failed to load");
+    match AssetType::of(Path::new(path)) {
+        AssetType::Mesh => {
+            let handle = server.load::<Model>(path);
+            let Some(model) = world.resource::<Assets<Model>>().get(handle) else {
+                return match server.load_state(handle) {
+                    LoadState::Failed => Err(failed()),
+                    _ => Ok(false),
+                };
+            };
+            let meshes = model.meshes.clone();
+            let entity = spawn_model(world, &file_stem(path), meshes, placement.point)?;
+            world.resource_mut::<History>().record(Spawn::new(entity));
+            world.resource_mut::<Selection>().select(entity);
+        }
+        AssetType::Texture => {
+            let handle = server.load::<Image>(path);
+            if world.resource::<Assets<Image>>().get(handle).is_none() {
+                return match server.load_state(handle) {
+                    LoadState::Failed => Err(failed()),
+                    _ => Ok(false),
+                };
+            }
+            let material = named_material(world, handle, &file_stem(path))?;
+            let target = placement
+                .target
+                .and_then(|target| Some((target, *world.get::<MeshMaterial>(target)?)));
+            match target {
+                Some((entity, before)) => {
+                    let command = SetMeshMaterial {
+                        entity,
+                        before,
+                        after: MeshMaterial(material),
+                    };
+                    history::execute(world, command);
+                    world.resource_mut::<Selection>().select(entity);
+                }
+                None => info!("added material {}", file_stem(path)),
+            }
+        }
+        AssetType::Scene => {
+            let handle = server.load::<DynamicScene>(path);
+            let registry = world.resource::<AppTypeRegistry>().clone();
+            let written = world.resource_scope(|world, scenes: Mut<Assets<DynamicScene>>| {
+                scenes
+                    .get(handle)
+                    .map(|scene| scene.write_to_world(world, &registry))
+            });
+            let Some(entity_map) = written else {
+                return match server.load_state(handle) {
+                    LoadState::Failed => Err(failed()),
+                    _ => Ok(false),
+                };
+            };
+            let roots: Vec<Entity> = entity_map?
+                .values()
+                .filter(|&entity| world.get::<Parent>(entity).is_none())
+                .collect();
+            for root in roots {
+                world.resource_mut::<History>().record(Spawn::new(root));
+            }
+        }
+        AssetType::Other => warn!("{path} can't be placed in the scene"),
+    }
+    Ok(true)
+}
+
+/// Spawns the meshes of a model at `point`, under one parent if there are several.
+fn spawn_model(
+    world: &mut World,
+    name: &str,
+    meshes: Vec<MeshPrimitives>,
+    point: Vec3,
+) -> Result<Entity> {
+    let material = default_material(world).ok_or_else(|| anyhow!("there is no material"))?;
+    let transform = Transform {
+        pos: point,
+        ..Default::default()
+    };
+    let single = meshes.len() == 1;
+    let parent = (!single).then(|| {
+        world
+            .spawn((Name::new(name.to_owned()), transform.clone()))
+            .id()
+    });
+    let mut spawned = None;
+    for (i, primitives) in meshes.into_iter().enumerate() {
+        let mesh = StaticMesh::new(
+            world.resource::<NCameraBuffer>(),
+            world.resource::<NSurfaceConfig>(),
+            world.resource::<RenderDevice>(),
+            transform.clone(),
+            primitives,
+            world.get::<Material>(material).unwrap(),
+        );
+        let name = if single {
+            name.to_owned()
+        } else {
+            format!("{name} {i}")
+        };
+        let entity = world
+            .spawn((mesh, Name::new(name), MeshMaterial(material)))
+            .id();
+        set_parent(world, entity, parent);
+        spawned = Some(entity);
+    }
+    parent
+        .or(spawned)
+        .ok_or_else(|| anyhow!("the model has no meshes"))
+}
+
+/// The material named "default", or else the first one by name.
+fn default_material(world: &mut World) -> Option<Entity> {
+    let mut materials: Vec<(Entity, String)> = world
+        .query_filtered::<(Entity, &Name), With<Material>>()
+        .iter(world)
+        .map(|(entity, name)| (entity, name.to_string()))
+        .collect();
+    materials.sort_by(|a, b| a.1.cmp(&b.1));
+    materials
+        .iter()
+        .find(|(_, name)| name == "default")
+        .or(materials.first())
+        .map(|(entity, _)| *entity)
+}
+
+/// The material named `name`, made from `image` if there is none yet.
+fn named_material(
+    world: &mut World,
+    image: ne_assets::Handle<Image>,
+    name: &str,
+) -> Result<Entity> {
+    let existing = world
+        .query_filtered::<(Entity, &Name), With<Material>>()
+        .iter(world)
+        .find(|(_, material_name)| material_name.as_str() == name)
+        .map(|(entity, _)| entity);
+    if let Some(entity) = existing {
+        return Ok(entity);
+    }
+    let material = Material::from_image(
+        world.resource::<RenderDevice>(),
+        world.resource::<RenderQueue>(),
+        world.resource::<Assets<Image>>().get(image).unwrap(),
+        Some(name),
+    )?;
+    Ok(world
+        .spawn(NamedMaterial {
+            name: Name::new(name.to_owned()),
+            material,
+        })
+        .id())
+}
+
+/// Copies files dropped onto the window into the project's asset directory.
+pub(super) fn import_dropped_files_system(
+    mut events: EventReader<OnFileDragAndDrop>,
+    server: Res<AssetServer>,
+    mut browser: ResMut<AssetBrowser>,
+) {
+    for event in events.iter() {
+        match event {
+            OnFileDragAndDrop::HoveredFile { .. } => browser.hovering_files = true,
+            OnFileDragAndDrop::HoveredFileCancelled { .. } => browser.hovering_files = false,
+            OnFileDragAndDrop::DroppedFile { path_buf, .. } => {
+                browser.hovering_files = false;
+                match import(&server, path_buf) {
+                    Ok(path) => {
+                        info!("imported {}", path.display());
+                        browser.refresh();
+                    }
+                    Err(err) => error!("could not import {}: {err:#}", path_buf.display()),
+                }
+            }
+        }
+    }
+}
+
+/// Copies `source` into the directory of its type, returns where it went. Existing files
+/// aren't overwritten, the copy gets a number instead.
+fn import(server: &AssetServer, source: &Path) -> Result<PathBuf> {
+    let root = server
+        .vfs()
+        .dirs()
+        .next()
+        .ok_or_else(|| anyhow!("there is no asset directory to import into"))?;
+    if !source.is_file() {
+        return Err(anyhow!("only files can be imported"));
+    }
+    let dir = root.join(AssetType::of(source).import_dir());
+    std::fs::create_dir_all(&dir)?;
+    let stem = source
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = source
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let mut target = dir.join(format!("{stem}{extension}"));
+    let mut number = 1;
+    while target.exists() {
+        target = dir.join(format!("{stem} {number}{extension}"));
+        number += 1;
+    }
+    std::fs::copy(source, &target)?;
+    Ok(target)
+}
+
+/// The directory of a relative asset path with `/` separators, `""` at the top.
+fn parent(file: &str) -> &str {
+    file.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+fn file_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
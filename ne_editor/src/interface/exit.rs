@@ -0,0 +1,137 @@
+//! Asks to save the scene before the editor exits.
+//!
+//! Closing the window, pressing Escape or opening another project exits right away when the
+//! scene has no unsaved changes, otherwise a prompt offers to save them, discard them or keep
+//! editing. Escape belongs to the game while it plays, and the game keeps playing until the
+//! changes are saved or discarded. The window only closes on [`ExitApp`], see
+//! [`WindowSettings::close_when_requested`].
+//!
+//! [`WindowSettings::close_when_requested`]: ne_render::WindowSettings::close_when_requested
+use std::path::{Path, PathBuf};
+
+use bevy_ecs::{
+    event::{EventReader, Events},
+    system::{Res, ResMut},
+    world::World,
+};
+use ne_app::{play::PlayState, Resource};
+use ne_render::{egui, UiContext};
+use ne_window::{
+    events::{ExitApp, OnWindowCloseRequested, VirtualKeyCode},
+    input::Input,
+};
+use tracing::{error, info};
+
+use super::{
+    hierarchy::{self, HierarchyState},
+    play, project,
+};
+
+#[derive(Resource, Default)]
+pub(super) struct ExitPrompt {
+    /// Exiting was requested and not handled yet.
+    requested: bool,
+    open: bool,
+    /// The project a new editor is started for after exiting.
+    next_project: Option<PathBuf>,
+}
+
+/// Exits, and starts a new editor working on the project at `path`.
+pub(super) fn exit_to_project(world: &mut World, path: PathBuf) {
+    let mut prompt = world.resource_mut::<ExitPrompt>();
+    prompt.requested = true;
+    prompt.next_project = Some(path);
+}
+
+/// Runs before the panels, a text field that Escape unfocuses still has the keyboard.
+pub(super) fn exit_request_system(
+    mut close_requested: EventReader<OnWindowCloseRequested>,
+    keys: Res<Input<VirtualKeyCode>>,
+    ui: Res<UiContext>,
+    play_state: Res<PlayState>,
+    mut prompt: ResMut<ExitPrompt>,
+) {
+    let escape = keys.just_pressed(VirtualKeyCode::Escape)
+        && !ui.wants_keyboard_input()
+        && !play_state.is_playing();
+    if close_requested.iter().last().is_some() || escape {
+        prompt.requested = true;
+    }
+}
+
+pub(super) fn exit_prompt_system(world: &mut World) {
+    let ctx = world.resource::<UiContext>().clone();
+    if std::mem::take(&mut world.resource_mut::<ExitPrompt>().requested) {
+        if play::edited_history(world).is_unsaved() {
+            world.resource_mut::<ExitPrompt>().open = true;
+        } else {
+            exit(world);
+        }
+    }
+    if !world.resource::<ExitPrompt>().open {
+        return;
+    }
+
+    let path = world.resource::<HierarchyState>().scene_path().to_owned();
+    let mut choice = None;
+    egui::Window::new("Unsaved changes")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(&ctx, |ui| {
+            ui.label(format!("Save the changes to {path} before exiting?"));
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    choice = Some(Choice::Save);
+                }
+                if ui.button("Discard").clicked() {
+                    choice = Some(Choice::Discard);
+                }
+                if ui.button("Cancel").clicked() {
+                    choice = Some(Choice::Cancel);
+                }
+            });
+        });
+    match choice {
+        Some(Choice::Save) => {
+            // The played world isn't the one that is saved.
+            play::set_play_state(world, PlayState::Editing);
+            match hierarchy::save_scene(world, Path::new(&path)) {
+                Ok(()) => {
+                    info!("saved scene {path}");
+                    exit(world);
+                }
+                // The prompt stays open, the changes would be lost.
+                Err(err) => error!("could not save scene {path}: {err:#}"),
+            }
+        }
+        Some(Choice::Discard) => {
+            play::set_play_state(world, PlayState::Editing);
+            exit(world);
+        }
+        Some(Choice::Cancel) => {
+            let mut prompt = world.resource_mut::<ExitPrompt>();
+            prompt.open = false;
+            prompt.next_project = None;
+        }
+        None => {}
+    }
+}
+
+enum Choice {
+    Save,
+    Discard,
+    Cancel,
+}
+
+fn exit(world: &mut World) {
+    let mut prompt = world.resource_mut::<ExitPrompt>();
+    prompt.open = false;
+    if let Some(path) = prompt.next_project.take() {
+        if let Err(err) = project::relaunch(&path) {
+            error!("could not start an editor for {}: {err}", path.display());
+            return;
+        }
+    }
+    world.resource_mut::<Events<ExitApp>>().send(ExitApp);
+}
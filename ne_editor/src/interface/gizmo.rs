@@ -0,0 +1,517 @@
+//! Handles over the selection that move, rotate and scale it.
+//!
+//! The handles are hit-tested with the ray under the cursor and keep the same size on screen.
+//! They are centered on the primary selection, the other selected entities are changed with it.
+use bevy_ecs::{
+    prelude::Entity,
+    world::{Mut, World},
+};
+use ne_app::Resource;
+use ne_math::{Quat, Ray, Transform, Vec3};
+use ne_render::{
+    egui::{self, Color32, Pos2, Stroke},
+    UiContext,
+};
+
+use super::{
+    history::{self, SetComponent},
+    viewport::Viewport,
+    Selection,
+};
+
+/// What the gizmo does to the selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+/// The axes the handles follow, scaling always uses the local ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GizmoSpace {
+    Local,
+    World,
+}
+
+#[derive(Resource)]
+pub(crate) struct Gizmo {
+    pub(crate) mode: GizmoMode,
+    pub(crate) space: GizmoSpace,
+    /// Rounds changes to the increments below.
+    pub(crate) snap: bool,
+    /// In world units.
+    pub(crate) translate_snap: f32,
+    /// In degrees.
+    pub(crate) rotate_snap: f32,
+    /// Of the scale factor.
+    pub(crate) scale_snap: f32,
+    hovered: Option<Handle>,
+    drag: Option<Drag>,
+}
+impl Default for Gizmo {
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            snap: false,
+            translate_snap: 0.5,
+            rotate_snap: 15.0,
+            scale_snap: 0.1,
+            hovered: None,
+            drag: None,
+        }
+    }
+}
+impl Gizmo {
+    /// Whether the cursor is over a handle or dragging one, clicks belong to the gizmo then.
+    pub(crate) fn is_active(&self) -> bool {
+        self.hovered.is_some() || self.drag.is_some()
+    }
+    fn snap(&self, value: f32, increment: f32) -> f32 {
+        if self.snap && increment > 0.0 {
+            (value / increment).round() * increment
+        } else {
+            value
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handle {
+    /// Index of the axis.
+    Axis(usize),
+    /// Index of the axis the plane is perpendicular to.
+    Plane(usize),
+    /// Index of the axis the ring rotates around.
+    Ring(usize),
+    Uniform,
+}
+
+struct Drag {
+    handle: Handle,
+    mode: GizmoMode,
+    /// The gizmo when the drag started, the handles don't turn while dragging.
+    frame: Frame,
+    /// Where the handle was grabbed.
+    grab: Vec3,
+    starts: Vec<(Entity, Transform)>,
+}
+
+#[derive(Clone, Copy)]
+struct Frame {
+    origin: Vec3,
+    axes: [Vec3; 3],
+    /// Length of the handles in world units.
+    size: f32,
+    /// Points from the origin to the camera.
+    to_camera: Vec3,
+}
+impl Frame {
+    fn new(transform: &Transform, local: bool, camera: Vec3) -> Self {
+        let axes = if local {
+            [Vec3::X, Vec3::Y, Vec3::Z].map(|axis| transform.rot * axis)
+        } else {
+            [Vec3::X, Vec3::Y, Vec3::Z]
+        };
+        let to_camera = camera - transform.pos;
+        Self {
+            origin: transform.pos,
+            axes,
+            size: to_camera.length() * SCREEN_SIZE,
+            to_camera: to_camera.normalize_or_zero(),
+        }
+    }
+    /// How far the handles reach from the cursor ray.
+    fn tolerance(&self) -> f32 {
+        self.size * 0.08
+    }
+    /// The other two axes, spanning the plane perpendicular to `axis`.
+    fn plane_axes(&self, axis: usize) -> [Vec3; 2] {
+        [self.axes[(axis + 1) % 3], self.axes[(axis + 2) % 3]]
+    }
+    /// Distance along `axis` of the point on it closest to the ray.
+    fn axis_param(&self, ray: Ray, axis: usize) -> Option<f32> {
+        closest_params(ray, self.origin, self.axes[axis]).map(|(_, along)| along)
+    }
+    fn plane_point(&self, ray: Ray, normal: Vec3) -> Option<Vec3> {
+        ray_plane(ray, self.origin, normal).map(|distance| ray.get_point(distance))
+    }
+    /// Where the ray hits the grabbed handle, on the plane the drag moves in.
+    fn drag_point(&self, ray: Ray, handle: Handle) -> Option<Vec3> {
+        match handle {
+            Handle::Axis(axis) => self
+                .axis_param(ray, axis)
+                .map(|along| self.origin + self.axes[axis] * along),
+            Handle::Plane(axis) | Handle::Ring(axis) => self.plane_point(ray, self.axes[axis]),
+            Handle::Uniform => self.plane_point(ray, self.to_camera),
+        }
+    }
+    /// The closest handle under the ray.
+    fn hit(&self, ray: Ray, mode: GizmoMode) -> Option<Handle> {
+        let tolerance = self.tolerance();
+        let mut hit: Option<(f32, Handle)> = None;
+        let mut consider = |distance: f32, handle| {
+            if hit.is_none_or(|(closest, _)| distance < closest) {
+                hit = Some((distance, handle));
+            }
+        };
+        for axis in 0..3 {
+            match mode {
+                GizmoMode::Translate | GizmoMode::Scale => {
+                    let Some((distance, along)) = closest_params(ray, self.origin, self.axes[axis])
+                    else {
+                        continue;
+                    };
+                    let on_axis = self.origin + self.axes[axis] * along;
+                    if distance > 0.0
+                        && (0.0..=self.size).contains(&along)
+                        && ray.get_point(distance).distance(on_axis) < tolerance
+                    {
+                        consider(distance, Handle::Axis(axis));
+                    }
+                }
+                GizmoMode::Rotate => {
+                    let Some(point) = self.plane_point(ray, self.axes[axis]) else {
+                        continue;
+                    };
+                    if (point.distance(self.origin) - self.size).abs() < tolerance {
+                        consider(point.distance(ray.origin), Handle::Ring(axis));
+                    }
+                }
+            }
+            if mode == GizmoMode::Translate {
+                let Some(point) = self.plane_point(ray, self.axes[axis]) else {
+                    continue;
+                };
+                let offset = point - self.origin;
+                let [u, v] = self.plane_axes(axis);
+                let range = PLANE_START * self.size..=PLANE_END * self.size;
+                if range.contains(&offset.dot(u)) && range.contains(&offset.dot(v)) {
+                    consider(point.distance(ray.origin), Handle::Plane(axis));
+                }
+            }
+        }
+        if mode == GizmoMode::Scale {
+            if let Some(point) = self.plane_point(ray, self.to_camera) {
+                if point.distance(self.origin) < tolerance * 1.5 {
+                    consider(point.distance(ray.origin), Handle::Uniform);
+                }
+            }
+        }
+        hit.map(|(_, handle)| handle)
+    }
+}
+
+/// Handle length as a fraction of the distance to the camera.
+const SCREEN_SIZE: f32 = 0.15;
+/// Where the plane handles start and end along their axes, as a fraction of the size.
+const PLANE_START: f32 = 0.2;
+const PLANE_END: f32 = 0.45;
+/// Keeps scales from flipping or collapsing.
+const MIN_SCALE: f32 = 0.001;
+const RING_SEGMENTS: usize = 48;
+/// In points.
+const LINE_WIDTH: f32 = 3.0;
+
+/// Parameters of the closest points of `ray` and the line through `point` along `direction`,
+/// none when they are parallel.
+fn closest_params(ray: Ray, point: Vec3, direction: Vec3) -> Option<(f32, f32)> {
+    let w = ray.origin - point;
+    let b = ray.direction.dot(direction);
+    let denominator = 1.0 - b * b;
+    if denominator < 1e-6 {
+        return None;
+    }
+    let c = ray.direction.dot(w);
+    let e = direction.dot(w);
+    Some(((b * e - c) / denominator, (e - b * c) / denominator))
+}
+
+/// Distance along the ray to the plane, none when it points away from it.
+fn ray_plane(ray: Ray, point: Vec3, normal: Vec3) -> Option<f32> {
+    let denominator = normal.dot(ray.direction);
+    if denominator.abs() < 1e-6 {
+        return None;
+    }
+    let distance = (point - ray.origin).dot(normal) / denominator;
+    (distance >= 0.0).then_some(distance)
+}
+
+pub(super) fn gizmo_system(world: &mut World) {
+    let ctx = world.resource::<UiContext>().clone();
+    world.resource_scope(|world, mut gizmo: Mut<Gizmo>| {
+        toolbar_ui(&ctx, &mut gizmo);
+        let Some(viewport) = Viewport::new(world, &ctx) else {
+            return;
+        };
+        let ray = ctx.input().pointer.hover_pos().map(|pos| viewport.ray(pos));
+
+        let selection: Vec<Entity> = world.resource::<Selection>().iter().collect();
+        let primary = world
+            .resource::<Selection>()
+            .primary()
+            .and_then(|entity| world.get::<Transform>(entity).cloned());
+        let Some(primary) = primary else {
+            gizmo.hovered = None;
+            gizmo.drag = None;
+            return;
+        };
+        let local = gizmo.space == GizmoSpace::Local || gizmo.mode == GizmoMode::Scale;
+        let frame = gizmo.drag.as_ref().map_or_else(
+            || Frame::new(&primary, local, viewport.camera),
+            |drag| drag.frame,
+        );
+
+        let (pressed, down) = {
+            let input = ctx.input();
+            (
+                input.pointer.any_pressed() && input.pointer.primary_down(),
+                input.pointer.primary_down(),
+            )
+        };
+        if gizmo.drag.is_none() {
+            gizmo.hovered = ray
+                .filter(|_| !ctx.is_pointer_over_area())
+                .and_then(|ray| frame.hit(ray, gizmo.mode));
+            if let (true, Some(handle), Some(ray)) = (pressed, gizmo.hovered, ray) {
+                if let Some(grab) = frame.drag_point(ray, handle) {
+                    let starts = selection
+                        .iter()
+                        .filter_map(|&entity| {
+                            Some((entity, world.get::<Transform>(entity)?.clone()))
+                        })
+                        .collect();
+                    gizmo.drag = Some(Drag {
+                        handle,
+                        mode: gizmo.mode,
+                        frame,
+                        grab,
+                        starts,
+                    });
+                }
+            }
+        } else if !down {
+            gizmo.drag = None;
+        } else if let Some(ray) = ray {
+            drag(world, &gizmo, ray);
+        }
+
+        let active = gizmo
+            .drag
+            .as_ref()
+            .map(|drag| drag.handle)
+            .or(gizmo.hovered);
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Background,
+            egui::Id::new("gizmo"),
+        ));
+        // Follows the selection while the drag keeps the axes it started with.
+        let frame = Frame {
+            origin: world
+                .get::<Transform>(world.resource::<Selection>().primary().unwrap())
+                .map_or(frame.origin, |transform| transform.pos),
+            ..frame
+        };
+        draw(&painter, &frame, gizmo.mode, active, |point| {
+            viewport.project(point)
+        });
+    });
+}
+
+fn toolbar_ui(ctx: &egui::Context, gizmo: &mut Gizmo) {
+    egui::Window::new("Gizmo")
+        .default_pos([240.0, 10.0])
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut gizmo.mode, GizmoMode::Translate, "Move");
+                ui.selectable_value(&mut gizmo.mode, GizmoMode::Rotate, "Rotate");
+                ui.selectable_value(&mut gizmo.mode, GizmoMode::Scale, "Scale");
+                ui.separator();
+                ui.selectable_value(&mut gizmo.space, GizmoSpace::World, "World");
+                ui.selectable_value(&mut gizmo.space, GizmoSpace::Local, "Local");
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut gizmo.snap, "Snap");
+                ui.add_enabled_ui(gizmo.snap, |ui| {
+                    let speed = 0.01;
+                    let range = 0.0..=f32::MAX;
+                    ui.add(
+                        egui::DragValue::new(&mut gizmo.translate_snap)
+                            .speed(speed)
+                            .clamp_range(range.clone()),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut gizmo.rotate_snap)
+                            .suffix("°")
+                            .clamp_range(range.clone()),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut gizmo.scale_snap)
+                            .speed(speed)
+                            .clamp_range(range),
+                    );
+                });
+            });
+        });
+}
+
+/// Applies the drag under `ray` to the transforms the selection had when it started.
+fn drag(world: &mut World, gizmo: &Gizmo, ray: Ray) {
+    let Some(drag) = &gizmo.drag else {
+        return;
+    };
+    let frame = drag.frame;
+    let Some(point) = frame.drag_point(ray, drag.handle) else {
+        return;
+    };
+    let moved = point - drag.grab;
+    let change = |start: &Transform| -> Transform {
+        let mut transform = start.clone();
+        match (drag.mode, drag.handle) {
+            (GizmoMode::Translate, Handle::Axis(axis)) => {
+                let axis = frame.axes[axis];
+                transform.pos += axis * gizmo.snap(moved.dot(axis), gizmo.translate_snap);
+            }
+            (GizmoMode::Translate, Handle::Plane(axis)) => {
+                for axis in frame.plane_axes(axis) {
+                    transform.pos += axis * gizmo.snap(moved.dot(axis), gizmo.translate_snap);
+                }
+            }
+            (GizmoMode::Rotate, Handle::Ring(axis)) => {
+                let axis = frame.axes[axis];
+                let from = drag.grab - frame.origin;
+                let to = point - frame.origin;
+                let angle = axis.dot(from.cross(to)).atan2(from.dot(to));
+                let angle = gizmo
+                    .snap(angle.to_degrees(), gizmo.rotate_snap)
+                    .to_radians();
+                let rotation = Quat::from_axis_angle(axis, angle);
+                transform.pos = frame.origin + rotation * (start.pos - frame.origin);
+                transform.rot = (rotation * start.rot).normalize();
+            }
+            (GizmoMode::Scale, Handle::Axis(axis)) => {
+                let grabbed = (drag.grab - frame.origin).dot(frame.axes[axis]);
+                let factor = (point - frame.origin).dot(frame.axes[axis]) / grabbed.max(1e-4);
+                let factor = gizmo.snap(factor, gizmo.scale_snap);
+                transform.scale[axis] = (start.scale[axis] * factor).max(MIN_SCALE);
+            }
+            (GizmoMode::Scale, Handle::Uniform) => {
+                let grabbed = drag.grab.distance(frame.origin);
+                let factor = point.distance(frame.origin) / grabbed.max(1e-4);
+                let factor = gizmo.snap(factor, gizmo.scale_snap);
+                transform.scale = (start.scale * factor).max(Vec3::splat(MIN_SCALE));
+            }
+            _ => {}
+        }
+        transform
+    };
+    // Every dragged entity is in each command, so the commands of one drag merge.
+    let mut command = SetComponent::of::<Transform>();
+    let mut changed = false;
+    for (entity, start) in &drag.starts {
+        let Some(current) = world.get::<Transform>(*entity) else {
+            continue;
+        };
+        let transform = change(start);
+        changed |= *current != transform;
+        command = command.with(*entity, Box::new(current.clone()), Box::new(transform));
+    }
+    if changed {
+        history::execute(world, command);
+    }
+}
+
+fn draw(
+    painter: &egui::Painter,
+    frame: &Frame,
+    mode: GizmoMode,
+    active: Option<Handle>,
+    to_screen: impl Fn(Vec3) -> Option<Pos2>,
+) {
+    const COLORS: [Color32; 3] = [
+        Color32::from_rgb(230, 60, 60),
+        Color32::from_rgb(60, 200, 60),
+        Color32::from_rgb(60, 110, 240),
+    ];
+    let color = |axis: usize, handle: Handle| {
+        if active == Some(handle) {
+            Color32::YELLOW
+        } else {
+            COLORS[axis]
+        }
+    };
+    let Some(origin) = to_screen(frame.origin) else {
+        return;
+    };
+    for axis in 0..3 {
+        match mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                let color = color(axis, Handle::Axis(axis));
+                let Some(tip) = to_screen(frame.origin + frame.axes[axis] * frame.size) else {
+                    continue;
+                };
+                painter.line_segment([origin, tip], Stroke::new(LINE_WIDTH, color));
+                if mode == GizmoMode::Translate {
+                    painter.circle_filled(tip, 6.0, color);
+                } else {
+                    painter.rect_filled(
+                        egui::Rect::from_center_size(tip, egui::vec2(10.0, 10.0)),
+                        0.0,
+                        color,
+                    );
+                }
+            }
+            GizmoMode::Rotate => {
+                let color = color(axis, Handle::Ring(axis));
+                let [u, v] = frame.plane_axes(axis);
+                let points: Vec<Option<Pos2>> = (0..=RING_SEGMENTS)
+                    .map(|i| {
+                        let angle = i as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                        let offset = (u * angle.cos() + v * angle.sin()) * frame.size;
+                        to_screen(frame.origin + offset)
+                    })
+                    .collect();
+                for segment in points.windows(2) {
+                    if let [Some(from), Some(to)] = segment {
+                        painter.line_segment([*from, *to], Stroke::new(LINE_WIDTH, color));
+                    }
+                }
+            }
+        }
+        if mode == GizmoMode::Translate {
+            let [u, v] = frame.plane_axes(axis);
+            let corners = [
+                (PLANE_START, PLANE_START),
+                (PLANE_END, PLANE_START),
+                (PLANE_END, PLANE_END),
+                (PLANE_START, PLANE_END),
+            ];
+            let points: Option<Vec<Pos2>> = corners
+                .iter()
+                .map(|(a, b)| to_screen(frame.origin + (u * *a + v * *b) * frame.size))
+                .collect();
+            if let Some(points) = points {
+                let color = color(axis, Handle::Plane(axis));
+                painter.add(egui::Shape::convex_polygon(
+                    points,
+                    color.linear_multiply(0.4),
+                    Stroke::new(LINE_WIDTH / 3.0, color),
+                ));
+            }
+        }
+    }
+    if mode == GizmoMode::Scale {
+        let color = if active == Some(Handle::Uniform) {
+            Color32::YELLOW
+        } else {
+            Color32::WHITE
+        };
+        painter.rect_filled(
+            egui::Rect::from_center_size(origin, egui::vec2(12.0, 12.0)),
+            0.0,
+            color,
+        );
+    }
+}
@@ -0,0 +1,443 @@
+//! The named entities of the scene as a tree of parents and children.
+//!
+//! Materials are named entities too, they are picked in the inspector instead of listed here.
+//!
+//! The listed entities are saved to and opened from `.nscene` files with their reflected
+//! components. Meshes aren't reflected, they are left out.
+use std::path::Path;
+
+use bevy_ecs::{
+    prelude::{Entity, With, Without},
+    world::{FromWorld, Mut, World},
+};
+use ne_app::{
+    hierarchy::{despawn_recursive, is_descendant, set_parent, Children, Parent},
+    reflect::AppTypeRegistry,
+    types::Name,
+    Resource,
+};
+use ne_math::Transform;
+use ne_project::Project;
+use ne_render::{
+    egui::{self, collapsing_header::CollapsingState},
+    material::Material,
+    mesh::{GpuMesh, MeshMaterial},
+    render_structs::RenderDevice,
+    NCameraBuffer, UiContext,
+};
+use ne_scene::DynamicScene;
+use tracing::{error, info};
+
+use super::{
+    history::{self, Despawn, History, SetComponent, SetParent, Spawn},
+    Selection,
+};
+
+#[derive(Resource)]
+pub(super) struct HierarchyState {
+    /// The entity being renamed and its new name.
+    renaming: Option<(Entity, String)>,
+    focus_rename: bool,
+    /// File the scene is saved to and opened from.
+    scene_path: String,
+}
+impl HierarchyState {
+    pub(super) fn scene_path(&self) -> &str {
+        &self.scene_path
+    }
+}
+impl FromWorld for HierarchyState {
+    /// Starts with the startup scene of the project.
+    fn from_world(world: &mut World) -> Self {
+        let project_scene = world.get_resource::<Project>().and_then(|project| {
+            let scene = project.manifest.startup_scene.as_ref()?;
+            Some(project.asset_file(scene).display().to_string())
+        });
+        Self {
+            renaming: None,
+            focus_rename: false,
+            scene_path: project_scene
+                .unwrap_or_else(|| format!("scenes/main.{}", ne_scene::SCENE_EXTENSION)),
+        }
+    }
+}
+
+enum Action {
+    Select(Entity),
+    ToggleSelected(Entity),
+    StartRename(Entity),
+    Rename(Entity, String),
+    CancelRename,
+    Duplicate(Entity),
+    Delete(Entity),
+    CreateChild(Entity),
+    Detach(Entity),
+    Create,
+    SaveScene(String),
+    OpenScene(String),
+    Undo,
+    Redo,
+}
+
+pub(super) fn hierarchy_panel_system(world: &mut World) {
+    forget_despawned(world);
+    let ctx = world.resource::<UiContext>().clone();
+    let roots = roots(world);
+
+    let mut actions = Vec::new();
+    world.resource_scope(|world, mut state: Mut<HierarchyState>| {
+        egui::Window::new("Hierarchy")
+            .default_pos([10.0, 200.0])
+            .default_width(220.0)
+            .show(&ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut state.scene_path);
+                    if ui.button("Save").clicked() {
+                        actions.push(Action::SaveScene(state.scene_path.clone()));
+                    }
+                    if ui.button("Open").clicked() {
+                        actions.push(Action::OpenScene(state.scene_path.clone()));
+                    }
+                    if world.resource::<History>().is_unsaved() {
+                        ui.label("*").on_hover_text("unsaved changes");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Create entity").clicked() {
+                        actions.push(Action::Create);
+                    }
+                    let history = world.resource::<History>();
+                    if ui
+                        .add_enabled(history.can_undo(), egui::Button::new("Undo"))
+                        .clicked()
+                    {
+                        actions.push(Action::Undo);
+                    }
+                    if ui
+                        .add_enabled(history.can_redo(), egui::Button::new("Redo"))
+                        .clicked()
+                    {
+                        actions.push(Action::Redo);
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for &root in &roots {
+                        entity_ui(world, root, &mut state, &mut actions, ui);
+                    }
+                });
+            });
+    });
+    if !ctx.wants_keyboard_input() && ctx.input().key_pressed(egui::Key::Delete) {
+        let selected: Vec<Entity> = world.resource::<Selection>().iter().collect();
+        actions.extend(selected.into_iter().map(Action::Delete));
+    }
+    for action in actions {
+        apply(world, action);
+    }
+}
+
+/// The entities listed at the top of the hierarchy.
+fn roots(world: &mut World) -> Vec<Entity> {
+    let mut roots: Vec<Entity> = world
+        .query_filtered::<Entity, (With<Name>, Without<Parent>, Without<Material>)>()
+        .iter(world)
+        .collect();
+    roots.sort_by_key(|entity| entity.index());
+    roots
+}
+
+fn entity_ui(
+    world: &World,
+    entity: Entity,
+    state: &mut HierarchyState,
+    actions: &mut Vec<Action>,
+    ui: &mut egui::Ui,
+) {
+    let children: Vec<Entity> = world
+        .get::<Children>(entity)
+        .map(|children| {
+            children
+                .iter()
+                .copied()
+                .filter(|&child| world.get::<Name>(child).is_some())
+                .collect()
+        })
+        .unwrap_or_default();
+    if children.is_empty() {
+        ui.horizontal(|ui| {
+            // Lines up with the names next to a collapse button.
+            ui.add_space(ui.spacing().icon_width + ui.spacing().item_spacing.x);
+            row_ui(world, entity, state, actions, ui);
+        });
+    } else {
+        let id = ui.make_persistent_id(entity);
+        let mut collapsing = CollapsingState::load_with_default_open(ui.ctx(), id, false);
+        // New children are renamed right away, they have to be visible.
+        if let Some((renaming, _)) = state.renaming {
+            if renaming != entity && is_descendant(world, renaming, entity) {
+                collapsing.set_open(true);
+            }
+        }
+        collapsing
+            .show_header(ui, |ui| row_ui(world, entity, state, actions, ui))
+            .body(|ui| {
+                for child in children {
+                    entity_ui(world, child, state, actions, ui);
+                }
+            });
+    }
+}
+
+fn row_ui(
+    world: &World,
+    entity: Entity,
+    state: &mut HierarchyState,
+    actions: &mut Vec<Action>,
+    ui: &mut egui::Ui,
+) {
+    if let Some((renaming, name)) = &mut state.renaming {
+        if *renaming == entity {
+            let response = ui.text_edit_singleline(name);
+            if state.focus_rename {
+                response.request_focus();
+                state.focus_rename = false;
+            } else if response.lost_focus() {
+                if ui.input().key_pressed(egui::Key::Escape) {
+                    actions.push(Action::CancelRename);
+                } else {
+                    actions.push(Action::Rename(entity, name.clone()));
+                }
+            }
+            return;
+        }
+    }
+    let selected = world.resource::<Selection>().contains(entity);
+    let name = world.get::<Name>(entity).unwrap();
+    let response = ui.selectable_label(selected, name.as_str());
+    if response.clicked() {
+        if ui.input().modifiers.command {
+            actions.push(Action::ToggleSelected(entity));
+        } else {
+            actions.push(Action::Select(entity));
+        }
+    }
+    if response.double_clicked() {
+        actions.push(Action::StartRename(entity));
+    }
+    response.context_menu(|ui| {
+        let mut action = None;
+        if ui.button("Rename").clicked() {
+            action = Some(Action::StartRename(entity));
+        }
+        if ui.button("Duplicate").clicked() {
+            action = Some(Action::Duplicate(entity));
+        }
+        if ui.button("Delete").clicked() {
+            action = Some(Action::Delete(entity));
+        }
+        ui.separator();
+        if ui.button("Create child").clicked() {
+            action = Some(Action::CreateChild(entity));
+        }
+        if world.get::<Parent>(entity).is_some() && ui.button("Detach from parent").clicked() {
+            action = Some(Action::Detach(entity));
+        }
+        if let Some(action) = action {
+            actions.push(action);
+            ui.close_menu();
+        }
+    });
+}
+
+fn apply(world: &mut World, action: Action) {
+    match action {
+        Action::Select(entity) => world.resource_mut::<Selection>().select(entity),
+        Action::ToggleSelected(entity) => world.resource_mut::<Selection>().toggle(entity),
+        Action::StartRename(entity) => start_rename(world, entity),
+        Action::Rename(entity, name) => {
+            world.resource_mut::<HierarchyState>().renaming = None;
+            let name = name.trim();
+            match world.get::<Name>(entity) {
+                Some(old_name) if !name.is_empty() && old_name.as_str() != name => {
+                    let command = SetComponent::of::<Name>().with(
+                        entity,
+                        Box::new(old_name.clone()),
+                        Box::new(Name::new(name.to_owned())),
+                    );
+                    history::execute(world, command);
+                }
+                _ => {}
+            }
+        }
+        Action::CancelRename => world.resource_mut::<HierarchyState>().renaming = None,
+        Action::Duplicate(entity) => {
+            if world.get_entity(entity).is_none() {
+                return;
+            }
+            let Some(copy) = duplicate(world, entity) else {
+                return;
+            };
+            if let Some(mut name) = world.get_mut::<Name>(copy) {
+                name.mutate(|name| name.push_str(" copy"));
+            }
+            world.resource_mut::<History>().record(Spawn::new(copy));
+            world.resource_mut::<Selection>().select(copy);
+        }
+        Action::Delete(entity) => {
+            if world.get_entity(entity).is_some() {
+                history::execute(world, Despawn::new(entity));
+            }
+            forget_despawned(world);
+        }
+        Action::CreateChild(parent) => {
+            let transform = world.get::<Transform>(parent).cloned().unwrap_or_default();
+            let child = world.spawn((Name::new("entity"), transform)).id();
+            set_parent(world, child, Some(parent));
+            world.resource_mut::<History>().record(Spawn::new(child));
+            world.resource_mut::<Selection>().select(child);
+            start_rename(world, child);
+        }
+        Action::Detach(entity) => {
+            let before = world.get::<Parent>(entity).map(Parent::get);
+            let command = SetParent {
+                entity,
+                before,
+                after: None,
+            };
+            history::execute(world, command);
+        }
+        Action::Create => {
+            let entity = world
+                .spawn((Name::new("entity"), Transform::default()))
+                .id();
+            world.resource_mut::<History>().record(Spawn::new(entity));
+            world.resource_mut::<Selection>().select(entity);
+            start_rename(world, entity);
+        }
+        Action::SaveScene(path) => match save_scene(world, Path::new(&path)) {
+            Ok(()) => info!("saved scene {path}"),
+            Err(err) => error!("could not save scene {path}: {err:#}"),
+        },
+        Action::OpenScene(path) => match open_scene(world, Path::new(&path)) {
+            Ok(()) => info!("opened scene {path}"),
+            Err(err) => error!("could not open scene {path}: {err:#}"),
+        },
+        Action::Undo => {
+            history::undo(world);
+            forget_despawned(world);
+        }
+        Action::Redo => {
+            history::redo(world);
+            forget_despawned(world);
+        }
+    }
+}
+
+/// Saves the listed entities and their descendants.
+pub(super) fn save_scene(world: &mut World, path: &Path) -> anyhow::Result<()> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let entities = roots(world)
+        .into_iter()
+        .flat_map(|root| with_descendants(world, root))
+        .collect::<Vec<_>>();
+    let scene = DynamicScene::from_entities(world, entities, &registry);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, scene.serialize(&registry)?)?;
+    world.resource_mut::<History>().mark_saved();
+    Ok(())
+}
+
+/// Replaces the listed entities with the ones of the scene at `path`.
+pub(super) fn open_scene(world: &mut World, path: &Path) -> anyhow::Result<()> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let scene = DynamicScene::deserialize(&std::fs::read_to_string(path)?, &registry)?;
+    for root in roots(world) {
+        despawn_recursive(world, root);
+    }
+    forget_despawned(world);
+    // The commands refer to the despawned entities.
+    world.resource_mut::<History>().clear();
+    scene.write_to_world(world, &registry)?;
+    Ok(())
+}
+
+/// `entity` followed by its descendants, parents before their children.
+pub(super) fn with_descendants(world: &World, entity: Entity) -> Vec<Entity> {
+    let mut entities = vec![entity];
+    let mut i = 0;
+    while let Some(&entity) = entities.get(i) {
+        if let Some(children) = world.get::<Children>(entity) {
+            entities.extend(children.iter().copied());
+        }
+        i += 1;
+    }
+    entities
+}
+
+fn forget_despawned(world: &mut World) {
+    world.resource_scope(|world, mut selection: Mut<Selection>| {
+        selection.retain(|&entity| world.get_entity(entity).is_some());
+    });
+}
+
+fn start_rename(world: &mut World, entity: Entity) {
+    let Some(name) = world.get::<Name>(entity).map(Name::to_string) else {
+        return;
+    };
+    let mut state = world.resource_mut::<HierarchyState>();
+    state.renaming = Some((entity, name));
+    state.focus_rename = true;
+}
+
+/// Copies the reflected components and the mesh of `source` and its descendants, the copy
+/// gets the same parent.
+///
+/// Meshes are only copied when they have a [`MeshMaterial`].
+fn duplicate(world: &mut World, source: Entity) -> Option<Entity> {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let entities = with_descendants(world, source);
+    let mut scene = DynamicScene::from_entities(world, entities.iter().copied(), &registry);
+    // The parent doesn't list the copy as its child yet, set_parent adds it.
+    let parent_name = std::any::type_name::<Parent>();
+    scene.entities[0]
+        .components
+        .retain(|component| component.type_name() != parent_name);
+    let entity_map = match scene.write_to_world(world, &registry) {
+        Ok(entity_map) => entity_map,
+        Err(err) => {
+            error!("could not duplicate {source:?}: {err:#}");
+            return None;
+        }
+    };
+    for entity in entities {
+        let copy = entity_map.get(entity).unwrap();
+        duplicate_mesh(world, entity, copy);
+    }
+    let copy = entity_map.get(source).unwrap();
+    let parent = world.get::<Parent>(source).map(Parent::get);
+    set_parent(world, copy, parent);
+    Some(copy)
+}
+
+fn duplicate_mesh(world: &mut World, source: Entity, target: Entity) {
+    let (Some(mesh), Some(&mesh_material)) = (
+        world.get::<GpuMesh>(source),
+        world.get::<MeshMaterial>(source),
+    ) else {
+        return;
+    };
+    let Some(material) = world.get::<Material>(mesh_material.0) else {
+        return;
+    };
+    let transform = world.get::<Transform>(target).cloned().unwrap_or_default();
+    let copy = mesh.duplicate(
+        world.resource::<NCameraBuffer>(),
+        world.resource::<RenderDevice>(),
+        &transform,
+        material,
+    );
+    world.entity_mut(target).insert((copy, mesh_material));
+}
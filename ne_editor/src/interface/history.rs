@@ -0,0 +1,375 @@
+//! Undo and redo of scene edits.
+//!
+//! Every edit the panels make is an [`EditorCommand`] recorded in the [`History`]. Ctrl+Z
+//! undoes the last one and ctrl+shift+Z redoes it. An edit continues while a mouse button is
+//! held or a text field has focus, commands recorded meanwhile are merged, so a drag is undone
+//! at once.
+use std::{any::Any, collections::VecDeque};
+
+use bevy_ecs::{
+    entity::EntityMap,
+    prelude::{Component, Entity},
+    reflect::ReflectComponent,
+    world::{FromWorld, Mut, World},
+};
+use ne_app::{
+    hierarchy::{despawn_recursive, set_parent, Parent},
+    reflect::{bevy_reflect::FromType, AppTypeRegistry, Reflect},
+    Resource,
+};
+use ne_render::{
+    egui,
+    mesh::{GpuMesh, MeshMaterial},
+    UiContext,
+};
+use ne_scene::DynamicScene;
+use tracing::warn;
+
+use super::hierarchy::with_descendants;
+
+/// An edit of the scene that can be undone.
+pub(crate) trait EditorCommand: Any + Send + Sync {
+    fn apply(&mut self, world: &mut World);
+    fn revert(&mut self, world: &mut World);
+    /// Takes over `next` when both are part of one continuous edit, returns whether it did.
+    fn merge(&mut self, _next: &dyn EditorCommand) -> bool {
+        false
+    }
+}
+
+/// The recorded commands, the oldest ones are forgotten past `capacity`.
+#[derive(Resource)]
+pub(crate) struct History {
+    undo: VecDeque<Box<dyn EditorCommand>>,
+    redo: Vec<Box<dyn EditorCommand>>,
+    capacity: usize,
+    /// Whether the edit of the last command continues.
+    open: bool,
+    /// Whether the scene changed since it was saved or opened.
+    unsaved: bool,
+}
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            capacity: 100,
+            open: false,
+            unsaved: false,
+        }
+    }
+}
+impl History {
+    /// Records a command that was already applied.
+    pub(crate) fn record(&mut self, command: impl EditorCommand) {
+        self.redo.clear();
+        self.unsaved = true;
+        if self.open {
+            if let Some(last) = self.undo.back_mut() {
+                if last.merge(&command) {
+                    return;
+                }
+            }
+        }
+        self.undo.push_back(Box::new(command));
+        if self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+        self.open = true;
+    }
+    /// Ends the current edit, the next command gets its own entry.
+    pub(crate) fn finish_edit(&mut self) {
+        self.open = false;
+    }
+    /// The scene was saved, it has no unsaved changes until the next command.
+    pub(crate) fn mark_saved(&mut self) {
+        self.unsaved = false;
+    }
+    pub(crate) fn is_unsaved(&self) -> bool {
+        self.unsaved
+    }
+    /// Forgets all commands, when the entities they refer to are gone.
+    pub(crate) fn clear(&mut self) {
+        *self = Self {
+            capacity: self.capacity,
+            ..Self::default()
+        };
+    }
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub(crate) fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+/// Applies `command` and records it.
+pub(crate) fn execute(world: &mut World, mut command: impl EditorCommand) {
+    command.apply(world);
+    world.resource_mut::<History>().record(command);
+}
+
+pub(crate) fn undo(world: &mut World) {
+    world.resource_scope(|world, mut history: Mut<History>| {
+        history.finish_edit();
+        if let Some(mut command) = history.undo.pop_back() {
+            command.revert(world);
+            history.redo.push(command);
+            history.unsaved = true;
+        }
+    });
+}
+
+pub(crate) fn redo(world: &mut World) {
+    world.resource_scope(|world, mut history: Mut<History>| {
+        history.finish_edit();
+        if let Some(mut command) = history.redo.pop() {
+            command.apply(world);
+            history.undo.push_back(command);
+            history.unsaved = true;
+        }
+    });
+}
+
+/// Handles the shortcuts and ends edits.
+pub(super) fn history_system(world: &mut World) {
+    let ctx = world.resource::<UiContext>().clone();
+    let (editing, undo_pressed, redo_pressed) = {
+        let input = ctx.input();
+        let z = input.modifiers.command && input.key_pressed(egui::Key::Z);
+        (
+            input.pointer.any_down() || ctx.memory().focus().is_some(),
+            z && !input.modifiers.shift,
+            z && input.modifiers.shift,
+        )
+    };
+    if !editing {
+        world.resource_mut::<History>().finish_edit();
+    }
+    // Text fields undo their own text.
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+    if undo_pressed {
+        undo(world);
+    } else if redo_pressed {
+        redo(world);
+    }
+}
+
+/// An entity with its component before and after a change.
+type Change = (Entity, Box<dyn Reflect>, Box<dyn Reflect>);
+
+/// Changes a reflected component of entities.
+pub(crate) struct SetComponent {
+    reflect: ReflectComponent,
+    type_name: String,
+    /// The entities with the component before and after the change.
+    changes: Vec<Change>,
+}
+impl SetComponent {
+    /// `reflect` is the reflection of the component type.
+    pub(crate) fn new(reflect: ReflectComponent, type_name: impl Into<String>) -> Self {
+        Self {
+            reflect,
+            type_name: type_name.into(),
+            changes: Vec::new(),
+        }
+    }
+    pub(crate) fn of<T: Component + Reflect + FromWorld>() -> Self {
+        Self::new(
+            <ReflectComponent as FromType<T>>::from_type(),
+            std::any::type_name::<T>(),
+        )
+    }
+    pub(crate) fn with(
+        mut self,
+        entity: Entity,
+        before: Box<dyn Reflect>,
+        after: Box<dyn Reflect>,
+    ) -> Self {
+        self.changes.push((entity, before, after));
+        self
+    }
+    fn set(&self, world: &mut World, after: bool) {
+        for (entity, before, new) in &self.changes {
+            let value = if after { new } else { before };
+            if self.reflect.reflect(world, *entity).is_some() {
+                self.reflect.apply(world, *entity, &**value);
+            }
+        }
+    }
+}
+impl EditorCommand for SetComponent {
+    fn apply(&mut self, world: &mut World) {
+        self.set(world, true);
+    }
+    fn revert(&mut self, world: &mut World) {
+        self.set(world, false);
+    }
+    fn merge(&mut self, next: &dyn EditorCommand) -> bool {
+        let next: &dyn Any = next;
+        let Some(next) = next.downcast_ref::<Self>() else {
+            return false;
+        };
+        let same_entities = self.changes.len() == next.changes.len()
+            && self
+                .changes
+                .iter()
+                .zip(&next.changes)
+                .all(|(a, b)| a.0 == b.0);
+        if next.type_name != self.type_name || !same_entities {
+            return false;
+        }
+        for (change, next) in self.changes.iter_mut().zip(&next.changes) {
+            change.2 = next.2.clone_value();
+        }
+        true
+    }
+}
+
+/// Moves an entity under another parent, or to the top of the hierarchy.
+pub(crate) struct SetParent {
+    pub(crate) entity: Entity,
+    pub(crate) before: Option<Entity>,
+    pub(crate) after: Option<Entity>,
+}
+impl EditorCommand for SetParent {
+    fn apply(&mut self, world: &mut World) {
+        set_parent(world, self.entity, self.after);
+    }
+    fn revert(&mut self, world: &mut World) {
+        set_parent(world, self.entity, self.before);
+    }
+}
+
+/// Changes the material a mesh is drawn with.
+pub(crate) struct SetMeshMaterial {
+    pub(crate) entity: Entity,
+    pub(crate) before: MeshMaterial,
+    pub(crate) after: MeshMaterial,
+}
+impl EditorCommand for SetMeshMaterial {
+    fn apply(&mut self, world: &mut World) {
+        if let Some(mut material) = world.get_mut::<MeshMaterial>(self.entity) {
+            *material = self.after;
+        }
+    }
+    fn revert(&mut self, world: &mut World) {
+        if let Some(mut material) = world.get_mut::<MeshMaterial>(self.entity) {
+            *material = self.before;
+        }
+    }
+}
+
+/// Records an entity and its descendants that were spawned.
+pub(crate) struct Spawn {
+    entity: Entity,
+    removed: Option<Removed>,
+}
+impl Spawn {
+    pub(crate) fn new(entity: Entity) -> Self {
+        Self {
+            entity,
+            removed: None,
+        }
+    }
+}
+impl EditorCommand for Spawn {
+    fn apply(&mut self, world: &mut World) {
+        Removed::restore_from(&mut self.removed, world);
+    }
+    fn revert(&mut self, world: &mut World) {
+        // Still taken out when restoring it failed.
+        if self.removed.is_none() {
+            self.removed = Removed::take(world, self.entity);
+        }
+    }
+}
+
+/// Despawns an entity and its descendants.
+pub(crate) struct Despawn {
+    entity: Entity,
+    removed: Option<Removed>,
+}
+impl Despawn {
+    pub(crate) fn new(entity: Entity) -> Self {
+        Self {
+            entity,
+            removed: None,
+        }
+    }
+}
+impl EditorCommand for Despawn {
+    fn apply(&mut self, world: &mut World) {
+        // Still taken out when restoring it failed.
+        if self.removed.is_none() {
+            self.removed = Removed::take(world, self.entity);
+        }
+    }
+    fn revert(&mut self, world: &mut World) {
+        Removed::restore_from(&mut self.removed, world);
+    }
+}
+
+/// An entity and its descendants taken out of the world, they are put back with the same ids
+/// so other commands still refer to them.
+struct Removed {
+    entity: Entity,
+    parent: Option<Entity>,
+    scene: DynamicScene,
+    /// Meshes aren't reflected, they are kept as they are.
+    meshes: Vec<(Entity, GpuMesh, MeshMaterial)>,
+}
+impl Removed {
+    fn take(world: &mut World, entity: Entity) -> Option<Self> {
+        world.get_entity(entity)?;
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let entities = with_descendants(world, entity);
+        let scene = DynamicScene::from_entities(world, entities.iter().copied(), &registry);
+        let meshes = entities
+            .iter()
+            .filter_map(|&entity| {
+                let mut entity_mut = world.entity_mut(entity);
+                if !entity_mut.contains::<GpuMesh>() {
+                    return None;
+                }
+                let material = entity_mut.remove::<MeshMaterial>()?;
+                Some((entity, entity_mut.remove::<GpuMesh>()?, material))
+            })
+            .collect();
+        let parent = world.get::<Parent>(entity).map(Parent::get);
+        despawn_recursive(world, entity);
+        Some(Self {
+            entity,
+            parent,
+            scene,
+            meshes,
+        })
+    }
+    /// Restores the entities in `removed`, they are kept there if that fails so undo or redo
+    /// can try again.
+    fn restore_from(removed: &mut Option<Self>, world: &mut World) {
+        let Some(taken) = removed else {
+            return;
+        };
+        match taken.restore(world) {
+            Ok(()) => *removed = None,
+            Err(err) => warn!("could not restore {:?}: {err:#}", taken.entity),
+        }
+    }
+    fn restore(&mut self, world: &mut World) -> anyhow::Result<()> {
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let mut entity_map = EntityMap::default();
+        for entity in &self.scene.entities {
+            entity_map.insert(entity.entity, entity.entity);
+        }
+        self.scene
+            .write_to_world_with(world, &registry, &mut entity_map)?;
+        for (entity, mesh, material) in self.meshes.drain(..) {
+            world.entity_mut(entity).insert((mesh, material));
+        }
+        set_parent(world, self.entity, self.parent);
+        Ok(())
+    }
+}
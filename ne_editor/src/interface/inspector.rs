@@ -0,0 +1,284 @@
+//! Edits the reflected components of the primary selection.
+//!
+//! Widgets are generated from the type registry: structs, tuples, lists and enums show their
+//! fields, values use the widget registered for their type.
+use std::collections::HashMap;
+
+use bevy_ecs::{
+    prelude::{Entity, With},
+    world::{Mut, World},
+};
+use ne_app::{
+    reflect::{bevy_reflect::ReflectMut, AppTypeRegistry, Reflect, ReflectComponent},
+    types::Name,
+    Resource,
+};
+use ne_math::{EulerRot, Quat, Vec3};
+use ne_render::{egui, material::Material, mesh::MeshMaterial, UiContext};
+
+use super::{
+    history::{self, SetComponent, SetMeshMaterial},
+    Selection,
+};
+
+type WidgetFn = Box<dyn Fn(&mut dyn Reflect, &mut egui::Ui) -> Option<bool> + Send + Sync>;
+
+/// Widgets of value types, by type name.
+#[derive(Resource)]
+pub(crate) struct InspectorRegistry {
+    widgets: HashMap<&'static str, WidgetFn>,
+}
+impl Default for InspectorRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            widgets: HashMap::new(),
+        };
+        registry
+            .register_number::<f32>()
+            .register_number::<f64>()
+            .register_number::<i8>()
+            .register_number::<i16>()
+            .register_number::<i32>()
+            .register_number::<i64>()
+            .register_number::<isize>()
+            .register_number::<u8>()
+            .register_number::<u16>()
+            .register_number::<u32>()
+            .register_number::<u64>()
+            .register_number::<usize>()
+            .register_widget::<bool>(|value, ui| ui.checkbox(value, "").changed())
+            .register_widget::<String>(|value, ui| ui.text_edit_singleline(value).changed())
+            .register_widget::<Entity>(|entity, ui| {
+                ui.label(format!("{entity:?}"));
+                false
+            })
+            .register_widget::<Name>(name_ui)
+            .register_widget::<Vec3>(vec3_ui)
+            .register_widget::<Quat>(rotation_ui);
+        registry
+    }
+}
+impl InspectorRegistry {
+    /// Edits values of type `T` with `edit`, it returns whether it changed the value.
+    pub(crate) fn register_widget<T: Reflect>(
+        &mut self,
+        edit: fn(&mut T, &mut egui::Ui) -> bool,
+    ) -> &mut Self {
+        self.widgets.insert(
+            std::any::type_name::<T>(),
+            Box::new(move |value, ui| value.downcast_mut::<T>().map(|value| edit(value, ui))),
+        );
+        self
+    }
+    fn register_number<T: Reflect + egui::emath::Numeric>(&mut self) -> &mut Self {
+        self.register_widget::<T>(|value, ui| ui.add(egui::DragValue::new(value)).changed())
+    }
+    /// Draws the widgets of `value`, returns whether they changed it.
+    fn reflect_ui(&self, value: &mut dyn Reflect, ui: &mut egui::Ui) -> bool {
+        if let Some(changed) = self
+            .widgets
+            .get(value.type_name())
+            .and_then(|widget| widget(value, ui))
+        {
+            return changed;
+        }
+        let mut changed = false;
+        match value.reflect_mut() {
+            ReflectMut::Struct(value) => {
+                for i in 0..value.field_len() {
+                    let name = value.name_at(i).unwrap_or_default().to_owned();
+                    changed |= self.field_ui(&name, value.field_at_mut(i).unwrap(), ui);
+                }
+            }
+            ReflectMut::TupleStruct(value) => {
+                for i in 0..value.field_len() {
+                    changed |= self.field_ui(&i.to_string(), value.field_mut(i).unwrap(), ui);
+                }
+            }
+            ReflectMut::Tuple(value) => {
+                for i in 0..value.field_len() {
+                    changed |= self.field_ui(&i.to_string(), value.field_mut(i).unwrap(), ui);
+                }
+            }
+            ReflectMut::List(value) => {
+                for i in 0..value.len() {
+                    changed |= self.field_ui(&i.to_string(), value.get_mut(i).unwrap(), ui);
+                }
+            }
+            ReflectMut::Array(value) => {
+                for i in 0..value.len() {
+                    changed |= self.field_ui(&i.to_string(), value.get_mut(i).unwrap(), ui);
+                }
+            }
+            ReflectMut::Enum(value) => {
+                ui.label(value.variant_name());
+                for i in 0..value.field_len() {
+                    let name = value
+                        .name_at(i)
+                        .map_or_else(|| i.to_string(), str::to_owned);
+                    changed |= self.field_ui(&name, value.field_at_mut(i).unwrap(), ui);
+                }
+            }
+            // Maps and values without a widget are only shown.
+            ReflectMut::Map(value) => {
+                ui.label(format!("{:?}", value.as_reflect()));
+            }
+            ReflectMut::Value(value) => {
+                ui.label(format!("{value:?}"));
+            }
+        }
+        changed
+    }
+    /// Values are drawn next to their name, compound values below it.
+    fn field_ui(&self, name: &str, value: &mut dyn Reflect, ui: &mut egui::Ui) -> bool {
+        let compound = !self.widgets.contains_key(value.type_name())
+            && !matches!(
+                value.reflect_mut(),
+                ReflectMut::Value(_) | ReflectMut::Map(_)
+            );
+        if compound {
+            egui::CollapsingHeader::new(name)
+                .default_open(true)
+                .show(ui, |ui| self.reflect_ui(value, ui))
+                .body_returned
+                .unwrap_or(false)
+        } else {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                self.reflect_ui(value, ui)
+            })
+            .inner
+        }
+    }
+}
+
+pub(super) fn inspector_panel_system(world: &mut World) {
+    let ctx = world.resource::<UiContext>().clone();
+    let primary = world
+        .resource::<Selection>()
+        .primary()
+        .filter(|&entity| world.get_entity(entity).is_some());
+    let screen_width = ctx.input().screen_rect.width();
+    egui::Window::new("Inspector")
+        .default_pos([screen_width - 310.0, 10.0])
+        .default_width(300.0)
+        .vscroll(true)
+        .show(&ctx, |ui| {
+            let Some(entity) = primary else {
+                ui.label("Select an entity in the hierarchy.");
+                return;
+            };
+            world.resource_scope(|world, registry: Mut<InspectorRegistry>| {
+                for (name, type_name, reflect) in reflected_components(world, entity) {
+                    let Some(component) = reflect.reflect(world, entity) else {
+                        continue;
+                    };
+                    // Edits a copy so unchanged components aren't marked as changed.
+                    let before = component.clone_value();
+                    let mut value = component.clone_value();
+                    let changed = egui::CollapsingHeader::new(name)
+                        .default_open(true)
+                        .show(ui, |ui| registry.reflect_ui(&mut *value, ui))
+                        .body_returned
+                        .unwrap_or(false);
+                    if changed {
+                        let command =
+                            SetComponent::new(reflect, type_name).with(entity, before, value);
+                        history::execute(world, command);
+                    }
+                }
+            });
+            material_ui(world, entity, ui);
+        });
+}
+
+/// The short and full type names and the reflections of the registered components of `entity`.
+fn reflected_components(
+    world: &World,
+    entity: Entity,
+) -> Vec<(String, &'static str, ReflectComponent)> {
+    let Some(location) = world.entities().get(entity) else {
+        return Vec::new();
+    };
+    let registry = world.resource::<AppTypeRegistry>().read();
+    world.archetypes()[location.archetype_id]
+        .components()
+        .filter_map(|id| world.components().get_info(id)?.type_id())
+        .filter_map(|type_id| {
+            let registration = registry.get(type_id)?;
+            let reflect = registration.data::<ReflectComponent>()?;
+            Some((
+                registration.short_name().to_owned(),
+                registration.type_name(),
+                reflect.clone(),
+            ))
+        })
+        .collect()
+}
+
+fn name_ui(name: &mut Name, ui: &mut egui::Ui) -> bool {
+    let mut text = name.to_string();
+    let changed = ui.text_edit_singleline(&mut text).changed();
+    if changed {
+        name.set(text);
+    }
+    changed
+}
+
+fn vec3_ui(value: &mut Vec3, ui: &mut egui::Ui) -> bool {
+    let mut changed = false;
+    for value in [&mut value.x, &mut value.y, &mut value.z] {
+        changed |= ui.add(egui::DragValue::new(value).speed(0.1)).changed();
+    }
+    changed
+}
+
+/// Edits a rotation as pitch, yaw and roll in degrees.
+fn rotation_ui(rotation: &mut Quat, ui: &mut egui::Ui) -> bool {
+    let (yaw, pitch, roll) = rotation.to_euler(EulerRot::YXZ);
+    let mut angles = [pitch.to_degrees(), yaw.to_degrees(), roll.to_degrees()];
+    let mut changed = false;
+    for value in &mut angles {
+        changed |= ui
+            .add(egui::DragValue::new(value).speed(1.0).suffix("°"))
+            .changed();
+    }
+    if changed {
+        let [pitch, yaw, roll] = angles.map(f32::to_radians);
+        *rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll);
+    }
+    changed
+}
+
+/// Picks the material of a mesh from the named materials.
+fn material_ui(world: &mut World, entity: Entity, ui: &mut egui::Ui) {
+    let Some(&MeshMaterial(current)) = world.get::<MeshMaterial>(entity) else {
+        return;
+    };
+    let mut materials: Vec<(Entity, String)> = world
+        .query_filtered::<(Entity, &Name), With<Material>>()
+        .iter(world)
+        .map(|(material, name)| (material, name.to_string()))
+        .collect();
+    materials.sort_by(|a, b| a.1.cmp(&b.1));
+    let current_name = materials
+        .iter()
+        .find(|(material, _)| *material == current)
+        .map_or_else(|| "missing".to_owned(), |(_, name)| name.clone());
+    let mut selected = current;
+    egui::ComboBox::from_label("material")
+        .selected_text(current_name)
+        .show_ui(ui, |ui| {
+            for (material, name) in &materials {
+                ui.selectable_value(&mut selected, *material, name);
+            }
+        });
+    if selected != current {
+        let command = SetMeshMaterial {
+            entity,
+            before: MeshMaterial(current),
+            after: MeshMaterial(selected),
+        };
+        history::execute(world, command);
+    }
+}
@@ -1,8 +1,119 @@
-use ne_app::Plugin;
+use bevy_ecs::{prelude::Entity, schedule::IntoSystemDescriptor};
+use ne_app::{play::PlayState, CoreStage, Plugin, Resource};
+use ne_render::UiApp;
 
+mod asset_browser;
+mod exit;
+mod gizmo;
+mod hierarchy;
+mod history;
+mod inspector;
+mod picking;
+mod play;
+mod project;
+mod viewport;
+pub(crate) use gizmo::Gizmo;
+pub(crate) use history::{History, Spawn};
+pub(crate) use inspector::InspectorRegistry;
+pub(crate) use project::startup_project;
+
+/// The scene editor panels: the hierarchy of named entities and the inspector of the selected
+/// one. Entities are picked in the viewport and moved, rotated and scaled with a gizmo.
+/// Gameplay only runs after pressing Play, Stop brings back the edited scene. Assets are
+/// browsed in the File Explorer and dragged from it into the viewport. Exiting with unsaved
+/// changes asks to save them. Projects are created and opened from the Project panel.
 pub(crate) struct EditorPlugin;
 impl Plugin for EditorPlugin {
     fn setup(&self, app: &mut ne_app::App) {
-        todo!()
+        app.init_resource::<Selection>()
+            .init_resource::<asset_browser::AssetBrowser>()
+            .init_resource::<hierarchy::HierarchyState>()
+            .init_resource::<InspectorRegistry>()
+            .init_resource::<Gizmo>()
+            .init_resource::<history::History>()
+            .init_resource::<picking::Picking>()
+            .init_resource::<PlayState>()
+            .init_resource::<play::PlayMode>()
+            .init_resource::<exit::ExitPrompt>()
+            .init_resource::<project::ProjectPanel>()
+            .add_startup_system(project::open_startup_scene_system)
+            .add_event::<picking::OnSelect>()
+            .add_system(picking::select_system)
+            .add_system(play::pause_time_system)
+            .add_system_to_stage(
+                CoreStage::FixedUpdate,
+                play::gameplay_stage_start_system.at_start(),
+            )
+            .add_system_to_stage(
+                CoreStage::FixedUpdate,
+                play::gameplay_stage_end_system.at_end(),
+            )
+            .add_system_to_stage(
+                CoreStage::Gameplay,
+                play::gameplay_stage_start_system.at_start(),
+            )
+            .add_system_to_stage(
+                CoreStage::Gameplay,
+                play::gameplay_stage_end_system.at_end(),
+            )
+            .add_system(asset_browser::import_dropped_files_system)
+            .add_system(exit::exit_request_system)
+            .add_ui_system(hierarchy::hierarchy_panel_system)
+            .add_ui_system(inspector::inspector_panel_system)
+            .add_ui_system(gizmo::gizmo_system)
+            .add_ui_system(picking::picking_system)
+            .add_ui_system(history::history_system)
+            .add_ui_system(play::play_panel_system)
+            .add_ui_system(asset_browser::asset_browser_system)
+            .add_ui_system(project::project_panel_system)
+            .add_ui_system(exit::exit_prompt_system);
+    }
+    fn dependencies(&self) -> Vec<&'static str> {
+        vec![ne_app::plugin_group::plugin_name::<ne_render::RenderPlugin>()]
+    }
+}
+
+/// The selected entities, the last one selected is the primary one the inspector shows.
+#[derive(Resource, Debug, Default)]
+pub(crate) struct Selection {
+    entities: Vec<Entity>,
+}
+impl Selection {
+    pub(crate) fn primary(&self) -> Option<Entity> {
+        self.entities.last().copied()
+    }
+    pub(crate) fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter().copied()
+    }
+    /// Selects only `entity`.
+    pub(crate) fn select(&mut self, entity: Entity) {
+        self.entities.clear();
+        self.entities.push(entity);
+    }
+    /// Selects only `entities`, the last one is the primary one.
+    pub(crate) fn set(&mut self, entities: impl IntoIterator<Item = Entity>) {
+        self.entities.clear();
+        self.entities.extend(entities);
+    }
+    /// Adds `entity` to the selection as the primary one, unless it was selected.
+    pub(crate) fn add(&mut self, entity: Entity) {
+        if !self.contains(entity) {
+            self.entities.push(entity);
+        }
+    }
+    /// Adds `entity` to the selection as the primary one, or removes it if it was selected.
+    pub(crate) fn toggle(&mut self, entity: Entity) {
+        if self.contains(entity) {
+            self.entities.retain(|&selected| selected != entity);
+        } else {
+            self.entities.push(entity);
+        }
+    }
+    /// Forgets the entities that don't pass `keep`, like despawned ones.
+    pub(crate) fn retain(&mut self, keep: impl FnMut(&Entity) -> bool) {
+        self.entities.retain(keep);
     }
 }
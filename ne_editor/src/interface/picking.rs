@@ -0,0 +1,149 @@
+//! Selects entities by clicking them in the viewport, or dragging a box around them.
+//!
+//! Clicks are tested against the triangles of the meshes, boxes against the centers of the
+//! named entities. Holding shift toggles clicked entities and adds boxed ones.
+use bevy_ecs::{
+    prelude::{Entity, EventReader, With, Without},
+    system::ResMut,
+    world::{Mut, World},
+};
+use ne_app::{types::Name, Resource};
+use ne_math::{Ray, Transform};
+use ne_render::{
+    egui::{self, Color32, Pos2, Stroke},
+    material::Material,
+    math::ToMat4,
+    mesh::GpuMesh,
+    UiContext,
+};
+
+use super::{viewport::Viewport, Gizmo, Selection};
+
+/// Sent when entities are picked in the viewport, the editor selects them.
+pub(crate) struct OnSelect {
+    pub(crate) entities: Vec<Entity>,
+    pub(crate) mode: SelectMode,
+}
+
+/// How picked entities change the [`Selection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SelectMode {
+    Replace,
+    Toggle,
+    Add,
+}
+
+#[derive(Resource, Default)]
+pub(super) struct Picking {
+    /// Where the button went down in the viewport.
+    press: Option<Pos2>,
+}
+
+/// Moving less than this many points between press and release is a click.
+const CLICK_DISTANCE: f32 = 4.0;
+/// Of the box outline, in points.
+const BOX_WIDTH: f32 = 1.0;
+
+pub(super) fn picking_system(world: &mut World) {
+    let ctx = world.resource::<UiContext>().clone();
+    let Some(viewport) = Viewport::new(world, &ctx) else {
+        return;
+    };
+    let (pressed, released, pos, shift) = {
+        let input = ctx.input();
+        (
+            input.pointer.any_pressed() && input.pointer.primary_down(),
+            input.pointer.primary_released(),
+            input.pointer.interact_pos(),
+            input.modifiers.shift,
+        )
+    };
+    let over_ui = ctx.wants_pointer_input() || ctx.is_pointer_over_area();
+    let gizmo_active = world.resource::<Gizmo>().is_active();
+    world.resource_scope(|world, mut picking: Mut<Picking>| {
+        if pressed && !over_ui && !gizmo_active {
+            picking.press = pos;
+        }
+        let (Some(start), Some(pos)) = (picking.press, pos) else {
+            return;
+        };
+        let rect = egui::Rect::from_two_pos(start, pos);
+        let boxing = start.distance(pos) > CLICK_DISTANCE;
+        if !released {
+            if boxing {
+                let painter = ctx.layer_painter(egui::LayerId::new(
+                    egui::Order::Foreground,
+                    egui::Id::new("box select"),
+                ));
+                let color = Color32::from_rgb(90, 150, 240);
+                painter.rect(
+                    rect,
+                    0.0,
+                    color.linear_multiply(0.15),
+                    Stroke::new(BOX_WIDTH, color),
+                );
+            }
+            return;
+        }
+        picking.press = None;
+        let (entities, mode) = if boxing {
+            let mode = if shift {
+                SelectMode::Add
+            } else {
+                SelectMode::Replace
+            };
+            (box_select(world, &viewport, rect), mode)
+        } else {
+            let mode = if shift {
+                SelectMode::Toggle
+            } else {
+                SelectMode::Replace
+            };
+            let picked = pick(world, viewport.ray(pos)).map(|(entity, _)| entity);
+            (picked.into_iter().collect(), mode)
+        };
+        world.send_event(OnSelect { entities, mode });
+    });
+}
+
+/// The closest named mesh under `ray` and the distance to it.
+pub(super) fn pick(world: &mut World, ray: Ray) -> Option<(Entity, f32)> {
+    world
+        .query_filtered::<(Entity, &GpuMesh, &Transform), (With<Name>, Without<Material>)>()
+        .iter(world)
+        .filter_map(|(entity, mesh, transform)| {
+            Some((mesh.shape.intersect(ray, transform)?, entity))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(distance, entity)| (entity, distance))
+}
+
+/// The named entities whose centers are inside `rect`, the center of the bounds for meshes.
+fn box_select(world: &mut World, viewport: &Viewport, rect: egui::Rect) -> Vec<Entity> {
+    world
+        .query_filtered::<(Entity, &Transform, Option<&GpuMesh>), (With<Name>, Without<Material>)>()
+        .iter(world)
+        .filter(|(_, transform, mesh)| {
+            let center = mesh
+                .and_then(|mesh| mesh.shape.aabb)
+                .map_or(transform.pos, |aabb| {
+                    transform.to_raw().transform_point3(aabb.center())
+                });
+            viewport
+                .project(center)
+                .is_some_and(|center| rect.contains(center))
+        })
+        .map(|(entity, ..)| entity)
+        .collect()
+}
+
+pub(super) fn select_system(mut events: EventReader<OnSelect>, mut selection: ResMut<Selection>) {
+    for event in events.iter() {
+        let entities = event.entities.iter().copied();
+        match event.mode {
+            SelectMode::Replace => selection.set(entities),
+            SelectMode::Toggle => entities.for_each(|entity| selection.toggle(entity)),
+            SelectMode::Add => entities.for_each(|entity| selection.add(entity)),
+        }
+    }
+}
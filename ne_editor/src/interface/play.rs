@@ -0,0 +1,335 @@
+//! Plays the scene inside the editor.
+//!
+//! Play takes a [`Snapshot`] of the edited world and runs [`CoreStage::Gameplay`], Stop puts the
+//! snapshot back so nothing that happened while playing is kept, edits included. Components are
+//! only put back when their type is registered with `ReflectComponent`, or kept by the snapshot
+//! like meshes and materials. Only entities spawned by the gameplay stages are despawned, the
+//! engine and the editor keep what they created while playing.
+//!
+//! [`CoreStage::Gameplay`]: ne_app::CoreStage::Gameplay
+use std::{collections::HashSet, sync::Arc};
+
+use bevy_ecs::{
+    prelude::Entity,
+    system::{Res, ResMut},
+    world::{Mut, World},
+};
+use ne_app::{
+    play::PlayState,
+    reflect::{
+        bevy_reflect::ReflectRef, AppTypeRegistry, Reflect, ReflectComponent, ReflectResource,
+    },
+    time::Time,
+    Resource,
+};
+use ne_math::Transform;
+use ne_render::{
+    egui,
+    material::Material,
+    mesh::{GpuMesh, MeshMaterial},
+    render_structs::RenderDevice,
+    NCameraBuffer, UiContext,
+};
+use ne_scene::DynamicScene;
+use tracing::warn;
+
+use super::history::History;
+
+/// The edited world while playing.
+#[derive(Resource, Default)]
+pub(super) struct PlayMode {
+    snapshot: Option<Snapshot>,
+    /// The entities when the running gameplay stage started.
+    stage_entities: HashSet<Entity>,
+    /// Entities spawned by the gameplay stages, Stop despawns them.
+    spawned: HashSet<Entity>,
+}
+
+/// Runs at the start of the stages gameplay systems run in.
+pub(super) fn gameplay_stage_start_system(world: &mut World) {
+    if !world.resource::<PlayState>().is_playing() {
+        return;
+    }
+    let entities = world.iter_entities().collect();
+    world.resource_mut::<PlayMode>().stage_entities = entities;
+}
+
+/// Runs at the end of those stages, after their commands are applied, and records the entities
+/// they spawned.
+pub(super) fn gameplay_stage_end_system(world: &mut World) {
+    if !world.resource::<PlayState>().is_playing() {
+        return;
+    }
+    world.resource_scope(|world, mut play_mode: Mut<PlayMode>| {
+        let play_mode = &mut *play_mode;
+        let stage_entities = std::mem::take(&mut play_mode.stage_entities);
+        play_mode.spawned.extend(
+            world
+                .iter_entities()
+                .filter(|entity| !stage_entities.contains(entity)),
+        );
+    });
+}
+
+pub(super) fn play_panel_system(world: &mut World) {
+    let ctx = world.resource::<UiContext>().clone();
+    let state = *world.resource::<PlayState>();
+    let screen_width = ctx.input().screen_rect.width();
+    let mut next = None;
+    egui::Window::new("Play")
+        .default_pos([screen_width / 2.0 - 80.0, 10.0])
+        .resizable(false)
+        .show(&ctx, |ui| {
+            ui.horizontal(|ui| {
+                let play = egui::Button::new("Play");
+                if ui.add_enabled(!state.is_playing(), play).clicked() {
+                    next = Some(PlayState::Playing);
+                }
+                let pause = egui::Button::new("Pause");
+                if ui.add_enabled(state.is_playing(), pause).clicked() {
+                    next = Some(PlayState::Paused);
+                }
+                let stop = egui::Button::new("Stop");
+                if ui.add_enabled(state != PlayState::Editing, stop).clicked() {
+                    next = Some(PlayState::Editing);
+                }
+            });
+        });
+    if let Some(next) = next {
+        set_play_state(world, next);
+    }
+}
+
+/// The history of the edited world, it is kept aside while playing.
+pub(super) fn edited_history(world: &World) -> &History {
+    match &world.resource::<PlayMode>().snapshot {
+        Some(snapshot) => &snapshot.history,
+        None => world.resource::<History>(),
+    }
+}
+
+/// Takes the snapshot when playing starts and restores it when editing again.
+pub(super) fn set_play_state(world: &mut World, next: PlayState) {
+    let state = *world.resource::<PlayState>();
+    if state == next {
+        return;
+    }
+    if state == PlayState::Editing {
+        let snapshot = Snapshot::take(world);
+        let mut play_mode = world.resource_mut::<PlayMode>();
+        play_mode.snapshot = Some(snapshot);
+        play_mode.spawned.clear();
+    } else if next == PlayState::Editing {
+        let mut play_mode = world.resource_mut::<PlayMode>();
+        let spawned = std::mem::take(&mut play_mode.spawned);
+        if let Some(snapshot) = play_mode.snapshot.take() {
+            snapshot.restore(world, &spawned);
+        }
+    }
+    *world.resource_mut::<PlayState>() = next;
+}
+
+/// The scaled clock only runs while playing, so the fixed stage stops with the gameplay.
+pub(super) fn pause_time_system(play_state: Res<PlayState>, time: Option<ResMut<Time>>) {
+    let Some(mut time) = time else {
+        return;
+    };
+    if play_state.is_changed() {
+        if play_state.is_playing() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+}
+
+/// Everything Stop puts back: entities with their reflected components, reflected resources,
+/// materials and meshes.
+struct Snapshot {
+    scene: DynamicScene,
+    resources: Vec<(ReflectResource, Box<dyn Reflect>)>,
+    /// Materials aren't reflected, a material despawned while playing gets its clone back.
+    materials: Vec<(Entity, Material)>,
+    /// The entities with a mesh, their material and the index of the mesh in `templates`.
+    meshes: Vec<(Entity, MeshMaterial, usize)>,
+    /// A copy of every distinct mesh, meshes despawned while playing are recreated from them.
+    templates: Vec<GpuMesh>,
+    /// Commands recorded while playing refer to the played world, they are dropped with it.
+    history: History,
+}
+impl Snapshot {
+    fn take(world: &mut World) -> Self {
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let entities: Vec<Entity> = world.iter_entities().collect();
+        let scene = DynamicScene::from_entities(world, entities, &registry);
+        let resources = registry
+            .read()
+            .iter()
+            .filter_map(|registration| registration.data::<ReflectResource>())
+            .filter_map(|reflect| Some((reflect.clone(), reflect.reflect(world)?.clone_value())))
+            .collect();
+        let materials = world
+            .query::<(Entity, &Material)>()
+            .iter(world)
+            .map(|(entity, material)| (entity, material.clone()))
+            .collect();
+
+        let mut meshes = Vec::new();
+        let mut templates: Vec<GpuMesh> = Vec::new();
+        let mut query = world.query::<(Entity, &GpuMesh, &MeshMaterial)>();
+        for (entity, mesh, &mesh_material) in query.iter(world) {
+            let template = templates
+                .iter()
+                .position(|template| Arc::ptr_eq(&template.vertex_buffer, &mesh.vertex_buffer));
+            let template = match template {
+                Some(template) => template,
+                None => {
+                    let Some(material) = world.get::<Material>(mesh_material.0) else {
+                        continue;
+                    };
+                    templates.push(mesh.duplicate(
+                        world.resource::<NCameraBuffer>(),
+                        world.resource::<RenderDevice>(),
+                        &Transform::default(),
+                        material,
+                    ));
+                    templates.len() - 1
+                }
+            };
+            meshes.push((entity, mesh_material, template));
+        }
+
+        let history = std::mem::take(&mut *world.resource_mut::<History>());
+        Self {
+            scene,
+            resources,
+            materials,
+            meshes,
+            templates,
+            history,
+        }
+    }
+
+    /// Despawns the entities `spawned` by gameplay and puts back the snapshot.
+    fn restore(self, world: &mut World, spawned: &HashSet<Entity>) {
+        for &entity in spawned {
+            // Gameplay may have despawned it already.
+            world.despawn(entity);
+        }
+        let kept: HashSet<Entity> = self.scene.entities.iter().map(|e| e.entity).collect();
+
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        for scene_entity in &self.scene.entities {
+            let entity = scene_entity.entity;
+            // Despawned while playing, spawned again with the same id.
+            if world.get_or_spawn(entity).is_none() {
+                warn!("{entity:?} can't be restored, its id is taken");
+                continue;
+            }
+            let location = world.entities().get(entity).unwrap();
+            let added: Vec<ReflectComponent> = world.archetypes()[location.archetype_id]
+                .components()
+                .filter_map(|id| world.components().get_info(id)?.type_id())
+                .filter_map(|type_id| registry.get(type_id))
+                .filter(|registration| {
+                    !scene_entity
+                        .components
+                        .iter()
+                        .any(|component| component.type_name() == registration.type_name())
+                })
+                .filter_map(|registration| registration.data::<ReflectComponent>().cloned())
+                .collect();
+            for reflect in added {
+                reflect.remove(world, entity);
+            }
+            for component in &scene_entity.components {
+                let Some(reflect) = registry
+                    .get_with_name(component.type_name())
+                    .and_then(|registration| registration.data::<ReflectComponent>())
+                else {
+                    continue;
+                };
+                let current = reflect.reflect(world, entity).map(|current| {
+                    let unchanged = current.reflect_partial_eq(&**component) == Some(true);
+                    let value = matches!(current.reflect_ref(), ReflectRef::Value(_));
+                    (unchanged, value)
+                });
+                match current {
+                    Some((true, _)) => {}
+                    // Values are replaced as a whole, and stay changed rather than added.
+                    Some((false, true)) => reflect.apply(world, entity, &**component),
+                    // Applying to a longer list would keep its tail.
+                    Some((false, false)) => {
+                        reflect.remove(world, entity);
+                        reflect.insert(world, entity, &**component);
+                    }
+                    None => reflect.insert(world, entity, &**component),
+                }
+            }
+        }
+
+        // Resources that didn't exist when playing started are left alone.
+        for (reflect, value) in &self.resources {
+            let unchanged = reflect
+                .reflect(world)
+                .and_then(|current| current.reflect_partial_eq(&**value));
+            if unchanged != Some(true) {
+                reflect.insert(world, &**value);
+            }
+        }
+        drop(registry);
+
+        for (entity, material) in &self.materials {
+            if world.get::<Material>(*entity).is_some() {
+                continue;
+            }
+            if let Some(mut entity) = world.get_entity_mut(*entity) {
+                entity.insert(material.clone());
+            }
+        }
+        self.restore_meshes(world, &kept);
+        *world.resource_mut::<History>() = self.history;
+    }
+
+    /// Meshes aren't reflected, kept ones get their material back and despawned ones are
+    /// recreated.
+    fn restore_meshes(&self, world: &mut World, kept: &HashSet<Entity>) {
+        let with_mesh: HashSet<Entity> = self.meshes.iter().map(|(entity, ..)| *entity).collect();
+        let added: Vec<Entity> = world
+            .query::<(Entity, &GpuMesh)>()
+            .iter(world)
+            .map(|(entity, _)| entity)
+            .filter(|entity| kept.contains(entity) && !with_mesh.contains(entity))
+            .collect();
+        for entity in added {
+            world
+                .entity_mut(entity)
+                .remove_intersection::<(GpuMesh, MeshMaterial)>();
+        }
+        for &(entity, mesh_material, template) in &self.meshes {
+            if world.get::<GpuMesh>(entity).is_some() {
+                match world.get_mut::<MeshMaterial>(entity) {
+                    Some(mut current) if *current != mesh_material => *current = mesh_material,
+                    Some(_) => {}
+                    None => {
+                        world.entity_mut(entity).insert(mesh_material);
+                    }
+                }
+                continue;
+            }
+            let Some(material) = world.get::<Material>(mesh_material.0) else {
+                warn!("the mesh of {entity:?} can't be restored, its material is gone");
+                continue;
+            };
+            let transform = world.get::<Transform>(entity).cloned().unwrap_or_default();
+            let mesh = self.templates[template].duplicate(
+                world.resource::<NCameraBuffer>(),
+                world.resource::<RenderDevice>(),
+                &transform,
+                material,
+            );
+            world.entity_mut(entity).insert((mesh, mesh_material));
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! The project the editor works on, see [`ne_project`].
+//!
+//! The editor starts with the `.neproject` passed on the command line, or else the project
+//! opened last. The assets of a project are mounted when the editor starts, so opening or
+//! creating another project starts a new editor for it, after asking to save the scene.
+//! Builds run on the [`IoTaskPool`], the editor keeps going while the assets cook.
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use bevy_ecs::world::{Mut, World};
+use bevy_tasks::IoTaskPool;
+use crossbeam_channel::{bounded, Receiver, TryRecvError};
+use ne_app::Resource;
+use ne_cook::{Cooker, TextureSettings};
+use ne_files::{pak, AssetRoots};
+use ne_project::{BuildSettings, Project, RecentProjects, PROJECT_EXTENSION};
+use ne_render::{egui, UiContext};
+use tracing::{error, info, warn};
+
+use super::{exit, hierarchy};
+
+const RECENT_FILE: &str = "recent_projects.toml";
+
+/// The recent projects are kept in the user data directory of the editor.
+fn recent_path() -> Option<PathBuf> {
+    AssetRoots::discover_for("ne_editor")
+        .user
+        .map(|dir| dir.join(RECENT_FILE))
+}
+
+fn save_recent(recent: &RecentProjects) {
+    if let Some(path) = recent_path() {
+        if let Err(err) = recent.save(&path) {
+            warn!("could not save {}: {err:#}", path.display());
+        }
+    }
+}
+
+/// The project passed as the first argument or else the last one opened, it becomes the most
+/// recent one. Runs before logging is set up.
+pub(crate) fn startup_project() -> Option<Project> {
+    let mut recent = recent_path().map(RecentProjects::load).unwrap_or_default();
+    let project = match std::env::args().nth(1) {
+        Some(path) => match Project::open(&path) {
+            Ok(project) => project,
+            Err(err) => {
+                eprintln!("could not open project {path}: {err:#}");
+                return None;
+            }
+        },
+        None => recent
+            .projects
+            .iter()
+            .find_map(|path| Project::open(path).ok())?,
+    };
+    recent.add(project.path());
+    save_recent(&recent);
+    Some(project)
+}
+
+/// Opens the startup scene of the project, when it was saved before.
+pub(super) fn open_startup_scene_system(world: &mut World) {
+    let Some(project) = world.get_resource::<Project>() else {
+        return;
+    };
+    let Some(scene) = &project.manifest.startup_scene else {
+        return;
+    };
+    let path = project.asset_file(scene);
+    if !path.is_file() {
+        return;
+    }
+    match hierarchy::open_scene(world, &path) {
+        Ok(()) => info!("opened scene {}", path.display()),
+        Err(err) => error!("could not open scene {}: {err:#}", path.display()),
+    }
+}
+
+#[derive(Resource)]
+pub(super) struct ProjectPanel {
+    recent: RecentProjects,
+    open_path: String,
+    /// The new project wizard, while it is shown.
+    wizard: Option<Wizard>,
+    /// The outcome of the last build or failed open.
+    status: Option<String>,
+    /// Receives the outcome of the running build.
+    build: Option<Receiver<String>>,
+}
+impl Default for ProjectPanel {
+    fn default() -> Self {
+        Self {
+            recent: recent_path().map(RecentProjects::load).unwrap_or_default(),
+            open_path: String::new(),
+            wizard: None,
+            status: None,
+            build: None,
+        }
+    }
+}
+
+struct Wizard {
+    name: String,
+    /// Directory the manifest is created in.
+    location: String,
+}
+
+enum Action {
+    Open(PathBuf),
+    Create { name: String, location: String },
+    SaveManifest(Project),
+    Build,
+}
+
+pub(super) fn project_panel_system(world: &mut World) {
+    let ctx = world.resource::<UiContext>().clone();
+    let current = world.get_resource::<Project>().cloned();
+    let mut actions = Vec::new();
+    world.resource_scope(|_, mut panel: Mut<ProjectPanel>| {
+        poll_build(&mut panel);
+        let building = panel.build.is_some();
+        egui::Window::new("Project")
+            .default_pos([10.0, 520.0])
+            .default_width(220.0)
+            .show(&ctx, |ui| {
+                match &current {
+                    Some(project) => project_ui(ui, project, building, &mut actions),
+                    None => {
+                        ui.label("No project, the assets are found next to the editor.");
+                    }
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut panel.open_path)
+                        .on_hover_text(format!("a .{PROJECT_EXTENSION} file"));
+                    if ui.button("Open").clicked() {
+                        actions.push(Action::Open(PathBuf::from(&panel.open_path)));
+                    }
+                });
+                if ui.button("New project...").clicked() && panel.wizard.is_none() {
+                    let location = current
+                        .as_ref()
+                        .and_then(|project| project.dir().parent())
+                        .map(|dir| dir.display().to_string())
+                        .unwrap_or_default();
+                    panel.wizard = Some(Wizard {
+                        name: String::new(),
+                        location,
+                    });
+                }
+                // The recent paths are canonical.
+                let current_path = current
+                    .as_ref()
+                    .and_then(|project| std::fs::canonicalize(project.path()).ok());
+                let recent: Vec<&PathBuf> = panel
+                    .recent
+                    .projects
+                    .iter()
+                    .filter(|path| Some(*path) != current_path.as_ref())
+                    .collect();
+                if !recent.is_empty() {
+                    ui.label("Recent");
+                    for path in recent {
+                        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                        let button = ui.button(name).on_hover_text(path.display().to_string());
+                        if button.clicked() {
+                            actions.push(Action::Open(path.clone()));
+                        }
+                    }
+                }
+                if let Some(status) = &panel.status {
+                    ui.separator();
+                    ui.label(status);
+                }
+            });
+        wizard_ui(&ctx, &mut panel, &mut actions);
+    });
+    for action in actions {
+        apply(world, action);
+    }
+}
+
+/// Shows the outcome of the build once it finished.
+fn poll_build(panel: &mut ProjectPanel) {
+    let Some(build) = &panel.build else {
+        return;
+    };
+    let status = match build.try_recv() {
+        Ok(status) => status,
+        Err(TryRecvError::Empty) => return,
+        Err(TryRecvError::Disconnected) => "build failed: the build task stopped".to_string(),
+    };
+    info!("{status}");
+    panel.status = Some(status);
+    panel.build = None;
+}
+
+fn project_ui(ui: &mut egui::Ui, project: &Project, building: bool, actions: &mut Vec<Action>) {
+    ui.heading(&project.manifest.name);
+    ui.label(project.path().display().to_string());
+    let mut edited = project.clone();
+    let build = &mut edited.manifest.build;
+    ui.collapsing("Build", |ui| {
+        ui.checkbox(&mut build.mipmaps, "Mipmaps");
+        ui.checkbox(&mut build.compress_textures, "Compress textures");
+        ui.checkbox(&mut build.pack, "Pack into .nepak");
+        let button = egui::Button::new("Build");
+        if ui.add_enabled(!building, button).clicked() {
+            actions.push(Action::Build);
+        }
+    });
+    if edited.manifest != project.manifest {
+        actions.push(Action::SaveManifest(edited));
+    }
+}
+
+fn wizard_ui(ctx: &egui::Context, panel: &mut ProjectPanel, actions: &mut Vec<Action>) {
+    let Some(wizard) = &mut panel.wizard else {
+        return;
+    };
+    let mut close = false;
+    egui::Window::new("New project")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("new_project").show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut wizard.name);
+                ui.end_row();
+                ui.label("Location");
+                ui.text_edit_singleline(&mut wizard.location)
+                    .on_hover_text("the directory the project is created in");
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                let create = egui::Button::new("Create");
+                if ui
+                    .add_enabled(!wizard.name.trim().is_empty(), create)
+                    .clicked()
+                {
+                    actions.push(Action::Create {
+                        name: wizard.name.clone(),
+                        location: wizard.location.clone(),
+                    });
+                }
+                if ui.button("Cancel").clicked() {
+                    close = true;
+                }
+            });
+        });
+    if close {
+        panel.wizard = None;
+    }
+}
+
+fn apply(world: &mut World, action: Action) {
+    match action {
+        Action::Open(path) => open(world, path),
+        Action::Create { name, location } => match Project::create(&location, &name) {
+            Ok(project) => {
+                info!("created project {}", project.path().display());
+                world.resource_mut::<ProjectPanel>().wizard = None;
+                open(world, project.path().to_path_buf());
+            }
+            Err(err) => world.resource_mut::<ProjectPanel>().status = Some(format!("{err:#}")),
+        },
+        Action::SaveManifest(project) => {
+            if let Err(err) = project.save() {
+                error!("{err:#}");
+            }
+            world.insert_resource(project);
+        }
+        Action::Build => {
+            let project = world.resource::<Project>().clone();
+            let (sender, receiver) = bounded(1);
+            IoTaskPool::get()
+                .spawn(async move {
+                    let status = match build(&project) {
+                        Ok(summary) => summary,
+                        Err(err) => format!("build failed: {err:#}"),
+                    };
+                    let _ = sender.send(status);
+                })
+                .detach();
+            let mut panel = world.resource_mut::<ProjectPanel>();
+            panel.status = Some("building...".to_string());
+            panel.build = Some(receiver);
+        }
+    }
+}
+
+/// Switches to the project at `path` if it can be opened.
+fn open(world: &mut World, path: PathBuf) {
+    if let Err(err) = Project::open(&path) {
+        world.resource_mut::<ProjectPanel>().status = Some(format!("{err:#}"));
+        return;
+    }
+    {
+        let mut panel = world.resource_mut::<ProjectPanel>();
+        panel.recent.add(&path);
+        save_recent(&panel.recent);
+    }
+    exit::exit_to_project(world, path);
+}
+
+/// Starts a new editor working on the project at `path`.
+pub(super) fn relaunch(path: &Path) -> std::io::Result<()> {
+    std::process::Command::new(std::env::current_exe()?)
+        .arg(path)
+        .spawn()?;
+    Ok(())
+}
+
+/// Cooks the project and engine assets into the build output, and packs them when the settings
+/// say so. Next to the game they are found as its `assets` and `engine_assets` roots, see
+/// [`AssetRoots::discover`].
+fn build(project: &Project) -> anyhow::Result<String> {
+    let output = project.dir().join(&project.manifest.build.output);
+    let roots = project.asset_roots();
+    let mut summaries = Vec::new();
+    for (name, root) in [("assets", roots.project), ("engine_assets", roots.engine)] {
+        let Some(root) = root else {
+            continue;
+        };
+        let summary = build_root(&project.manifest.build, &root, &output, name)
+            .with_context(|| format!("can't build {}", root.display()))?;
+        summaries.push(format!("{name}: {summary}"));
+    }
+    Ok(summaries.join("; "))
+}
+
+/// Builds the assets of `root` into `output/name`, or `output/name.nepak`.
+fn build_root(
+    settings: &BuildSettings,
+    root: &Path,
+    output: &Path,
+    name: &str,
+) -> anyhow::Result<String> {
+    let archive = output.join(format!("{name}.nepak"));
+    // Archives are already cooked.
+    if root.is_file() {
+        std::fs::create_dir_all(output)?;
+        std::fs::copy(root, &archive)?;
+        return Ok(format!("copied to {}", archive.display()));
+    }
+    let cooked = output.join(name);
+    let textures = TextureSettings {
+        mipmaps: settings.mipmaps,
+        compress: settings.compress_textures,
+    };
+    let report = Cooker::new(root, &cooked)
+        .with_texture_settings(textures)
+        .run()?;
+    for (path, err) in &report.failed {
+        warn!("could not cook {path}: {err}");
+    }
+    let mut summary = format!(
+        "{} cooked, {} up to date, {} failed",
+        report.cooked.len(),
+        report.up_to_date,
+        report.failed.len()
+    );
+    if settings.pack {
+        pak::pack_dir(&cooked, &archive, true)?;
+        summary += &format!(", packed into {}", archive.display());
+    }
+    Ok(summary)
+}
@@ -0,0 +1,51 @@
+//! The part of the screen the scene is rendered to.
+use bevy_ecs::world::World;
+use ne_math::{Mat4, Ray, Vec2, Vec3};
+use ne_render::{
+    cameras::free_fly_camera::{Camera, Projection},
+    egui::{self, Pos2},
+};
+
+/// Maps between the world and the screen the editor camera renders to, in egui points.
+#[derive(Clone, Copy)]
+pub(crate) struct Viewport {
+    view_proj: Mat4,
+    screen: egui::Rect,
+    pub(crate) camera: Vec3,
+}
+impl Viewport {
+    /// None until the camera and its projection exist.
+    pub(crate) fn new(world: &World, ctx: &egui::Context) -> Option<Self> {
+        let camera = world.get_resource::<Camera>()?;
+        let projection = world.get_resource::<Projection>()?;
+        Some(Self {
+            view_proj: projection.calc_matrix() * camera.calc_matrix(),
+            screen: ctx.input().screen_rect(),
+            camera: camera.position,
+        })
+    }
+    /// None behind the camera.
+    pub(crate) fn project(&self, point: Vec3) -> Option<Pos2> {
+        let clip = self.view_proj * point.extend(1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+        Some(egui::pos2(
+            self.screen.left() + (ndc.x + 1.0) / 2.0 * self.screen.width(),
+            self.screen.top() + (1.0 - ndc.y) / 2.0 * self.screen.height(),
+        ))
+    }
+    /// The ray from the camera through `pos`.
+    pub(crate) fn ray(&self, pos: Pos2) -> Ray {
+        let ndc = Vec2::new(
+            (pos.x - self.screen.left()) / self.screen.width() * 2.0 - 1.0,
+            1.0 - (pos.y - self.screen.top()) / self.screen.height() * 2.0,
+        );
+        // The projection maps depth to 0..1.
+        let inverse = self.view_proj.inverse();
+        let near = inverse.project_point3(ndc.extend(0.0));
+        let far = inverse.project_point3(ndc.extend(1.0));
+        Ray::new(near, far - near)
+    }
+}
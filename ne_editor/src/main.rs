@@ -3,31 +3,35 @@
 use std::collections::HashMap;
 use std::thread::Thread;
 
-use bevy_ecs::prelude::{EventReader, EventWriter, Component};
+use bevy_ecs::prelude::{EventReader, EventWriter, Component, Entity};
 use bevy_ecs::system::{Res, ResMut, NonSend, Commands, Query};
 use bevy_derive::Deref;
-use ne::L::LogPlugin;
+use nightmare_engine::DefaultPlugins;
+use ne_app::hierarchy::HierarchyCommands;
 use ne_app::types::Name;
 use ne_app::{App, Plugin, Resource};
+use ne_app::time::Time;
 use ne_bench::thread::println_current_thread_id;
 use ne_math::{Vec3, Transform, Quat};
 use ne_render::cameras::free_fly_camera::{self, Camera, CameraUniform, Projection, CameraController};
-use ne_render::material::{Material, NamedMaterial};
-use ne_render::mesh::{StaticMesh, GpuMesh, MeshPrimitives, NamedGpuMesh};
+use ne_assets::{AssetServer, Assets, Handle, OnAssetLoaded};
+use ne_render::material::{Image, Material, NamedMaterial};
+use ne_render::mesh::{StaticMesh, GpuMesh, MeshMaterial, MeshPrimitives, Model, NamedGpuMesh};
 use ne_render::render_structs::{RenderDevice, RenderQueue};
-use ne_render::{RenderPlugin, WindowSettings, DeltaTime, PhysicalPosition, NWindow, NCameraBuffer, NSurfaceConfig, material};
+use ne_render::{WindowSettings, PhysicalPosition, NWindow, NCameraBuffer, NSurfaceConfig, UiContext, material};
+use ne_window::input::Input;
 use ne_window::events::{
     ElementState, ExitApp, ExitSequence, OnKeyboardInput, OnRedrawRequested,
     OnWindowCloseRequested, OnWindowResized, VirtualKeyCode, OnMouseMotion, OnMouseButton, MouseButton, OnMouseWheel,
 };
-use tracing::info;
+use tracing::{info, warn};
 mod interface;
+use interface::{History, Spawn};
 
 struct NightmareEditor;
 impl Plugin for NightmareEditor {
     fn setup(&self, app: &mut App) {
-        app.add_system(on_exit)
-        .add_plugin(NECamera);
+        app.add_plugin(NECamera);
     }
 }
 #[derive(Debug, Resource)]
@@ -49,25 +53,36 @@ fn main() {
     env();
     const WIDTH: f32 = 800.0;
     const HEIGHT: f32 = 800.0;
-    App::new()
-        .add_plugin(LogPlugin::default())
+    let mut app = App::new();
+    let mut title = "Nightmare_Editor".to_string();
+    if let Some(project) = interface::startup_project() {
+        title = format!("{} - {}", title, project.manifest.name);
+        app.insert_resource(project.asset_settings())
+            .insert_resource(project);
+    }
+    app
         .insert_resource(WindowSettings {
-            title: "Nightmare_Editor".to_string(),
+            title,
             width: WIDTH,
             height: HEIGHT,
             present_mode: ne_render::PresentMode::Immediate,
             window_mode: ne_render::WindowMode::Windowed,
+            // The editor asks to save first.
+            close_when_requested: false,
             ..WindowSettings::default()
         })
-        .add_plugin(RenderPlugin)
+        .add_plugins(DefaultPlugins)
         .add_plugin(NightmareEditor)
+        .add_plugin(interface::EditorPlugin)
         .add_system(on_keyboard_pressed)
         .insert_resource(MouseValues{ is_right_mouse_pressed: false })
         .add_system(mouse_motion)
         .add_system(on_scroll)
         //ALPHA
-        .add_startup_system(prepare_meshes)
+        .add_startup_system(load_scene_assets)
+        .add_system(prepare_meshes)
         .add_system(on_mouse_button)
+        .add_system(spawn_cubes)
         .run();
 }
 //=======================================
@@ -76,13 +91,6 @@ fn main() {
 fn on_mouse_button (
     mut mouse_button: EventReader<OnMouseButton>,
     mut mouse_values: ResMut<MouseValues>,
-    mut commands: Commands,
-    //mesh
-    device: Res<RenderDevice>,
-    queue: Res<RenderQueue>,
-    surface_conf: Res<NSurfaceConfig>,
-    camera_buffer: Res<NCameraBuffer>,
-    material_query: Query<(&Name, &Material)>,
 ) {
     for event in mouse_button.iter().rev() {
         if (event.button == MouseButton::Right && event.state == ElementState::Pressed) {
@@ -91,81 +99,124 @@ fn on_mouse_button (
         else {
             mouse_values.is_right_mouse_pressed =false;
         }
-        if (event.button == MouseButton::Left && event.state == ElementState::Pressed) {
-            //TODO improve: use {a} instead of {path_to_file}
-            let count = 1000;
-            let mut mesh_primss: Vec<Vec<MeshPrimitives>> = Vec::new();
-            // let a = ne_files::find_file!("../../../", "/engine_assets/3D/double_cube.obj");
-            let path_to_file = "./engine_assets/3D/double_cube.obj";
-            // println!("A: {}, B: {}", a, path_to_file);
-            let m = pollster::block_on(MeshPrimitives::from_obj(path_to_file)).unwrap();
-            for _ in 0..count {
-                mesh_primss.push(m.clone());
-            }
-            //Maybe it's cheaper to keep a hashmap of string and material-entities. Who knows how expensive material_query.iter() is.
-            let mut mat3 = None;
-            for (name, material) in material_query.iter() {
-                if *name == Name::new("red_brick") {
-                    println!("mat3 set!");
-                    mat3 = Some(material);
-                }
-            }
-            let size_of_meshes = mesh_primss.len();
-            let y = 2.0;
-            //only spawn if mat3 is initialized:
-            match mat3 {
-                Some(material) => {
-                    let mut base_transform = Transform { pos: Vec3 { x: -2.0 * (size_of_meshes as f32) / 2.0, y: y, z: 4.0 }, rot: Quat::default() };
-                for meshes in mesh_primss {
-                    base_transform.pos.x += 2.0;
-                for mesh in meshes {
-                    {
-                    let mesh = StaticMesh::new(
-                        &camera_buffer,
-                        &surface_conf, &device,
-                        base_transform.clone(),
-                        mesh,
-                        mat3.unwrap()
-                    );
-                    commands.spawn(mesh);
-            }}}},
-                None => println!("mat3 is not init....... "),
-            }
+    }
+}
+/// Spawns 1000 cubes when C is pressed, left clicks select entities instead.
+fn spawn_cubes (
+    keys: Res<Input<VirtualKeyCode>>,
+    ui: Res<UiContext>,
+    mut commands: Commands,
+    //mesh
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    surface_conf: Res<NSurfaceConfig>,
+    camera_buffer: Res<NCameraBuffer>,
+    material_query: Query<(Entity, &Name, &Material)>,
+    scene_assets: Res<SceneAssets>,
+    models: Res<Assets<Model>>,
+    mut history: ResMut<History>,
+) {
+    if !keys.just_pressed(VirtualKeyCode::C) || ui.wants_keyboard_input() {
+        return;
+    }
+    let count = 1000;
+    let mut mesh_primss: Vec<Vec<MeshPrimitives>> = Vec::new();
+    let Some(model) = models.get(scene_assets.cube) else {
+        warn!("double_cube.obj is not loaded yet");
+        return;
+    };
+    for _ in 0..count {
+        mesh_primss.push(model.meshes.clone());
+    }
+    //Maybe it's cheaper to keep a hashmap of string and material-entities. Who knows how expensive material_query.iter() is.
+    let mut mat3 = None;
+    for (entity, name, material) in material_query.iter() {
+        if *name == Name::new("red_brick") {
+            println!("mat3 set!");
+            mat3 = Some((entity, material));
         }
     }
+    let size_of_meshes = mesh_primss.len();
+    let y = 2.0;
+    //only spawn if mat3 is initialized:
+    match mat3 {
+        Some((material_entity, material)) => {
+            let cubes = commands.spawn((Name::new("cubes"), Transform::default())).id();
+            // Undoing it despawns the cubes with their root.
+            history.record(Spawn::new(cubes));
+            let mut base_transform = Transform { pos: Vec3 { x: -2.0 * (size_of_meshes as f32) / 2.0, y: y, z: 4.0 }, rot: Quat::default(), ..Default::default() };
+        for meshes in mesh_primss {
+            base_transform.pos.x += 2.0;
+        for mesh in meshes {
+            {
+            let mesh = StaticMesh::new(
+                &camera_buffer,
+                &surface_conf, &device,
+                base_transform.clone(),
+                mesh,
+                material
+            );
+            commands.spawn((mesh, Name::new("cube"), MeshMaterial(material_entity))).set_parent(cubes);
+    }}}},
+        None => println!("mat3 is not init....... "),
+    }
+}
+/// The assets the scene is built from.
+#[derive(Resource)]
+struct SceneAssets {
+    cube: Handle<Model>,
+    /// Textures and the names of the materials made from them.
+    textures: Vec<(Handle<Image>, &'static str)>,
+}
+fn load_scene_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SceneAssets {
+        cube: asset_server.load("3D/double_cube.obj"),
+        textures: vec![
+            (asset_server.load("textures/grid.png"), "default"),
+            (asset_server.load("textures/orangebricks.png"), "brick"),
+            (asset_server.load("textures/redbricks.png"), "red_brick"),
+        ],
+    });
 }
 //TODO This while need to be replaced by a scene loaded used internally in ne_render. will allow for a traditional game engine setup.
+/// Creates the materials once their textures are loaded, and the platform once its material is.
 fn prepare_meshes(
     mut commands: Commands,
+    mut loaded_images: EventReader<OnAssetLoaded<Image>>,
+    scene_assets: Res<SceneAssets>,
+    images: Res<Assets<Image>>,
     // mesh
     device: Res<RenderDevice>,
     queue: Res<RenderQueue>,
     surface_conf: Res<NSurfaceConfig>,
     camera_buffer: Res<NCameraBuffer>,
 ) {
-    //materials
-    let mat1 = Material::from_bytes(&device, &queue,
-        include_bytes!("../../engine_assets/textures/grid.png"), Some("grid.png")).unwrap();
-    let mat2 = material::Material::from_bytes(&device, &queue,
-        include_bytes!("../../engine_assets/textures/orangebricks.png"), Some("orangebricks.png")).unwrap();
-    let mat3 = material::Material::from_bytes(&device, &queue,
-    include_bytes!("../../engine_assets/textures/redbricks.png"), Some("redbricks.png")).unwrap();
-
-    //single platform mesh
-    let transform_platform = Transform { pos: Vec3 { x: 0.0, y: 0.0, z: 0.0 }, rot: Quat::default() };
-    let mesh = StaticMesh::new(
-        &camera_buffer,
-        &surface_conf, &device,
-        transform_platform,
-        ne_render::mesh::Shapes::create_box(20.0, 0.1, 20.0),
-        &mat1
-    );
-    commands.spawn(mesh);
-    let x:String = "mat1".into();
-    commands.spawn(NamedMaterial{name: "default".into(), material: mat1 });
-    commands.spawn(NamedMaterial{name: "brick".into(), material: mat2 });
-    commands.spawn(NamedMaterial{name: "red_brick".into(), material: mat3 });
-
+    for event in loaded_images.iter() {
+        //TODO meshes keep the texture they were created with, reloaded textures only affect new meshes.
+        if event.reloaded {
+            continue;
+        }
+        let Some((_, name)) = scene_assets.textures.iter().find(|(handle, _)| *handle == event.handle) else {
+            continue;
+        };
+        let image = images.get(event.handle).unwrap();
+        let material = Material::from_image(&device, &queue, image, Some(name)).unwrap();
+        //single platform mesh
+        let platform = (*name == "default").then(|| {
+            let transform_platform = Transform { pos: Vec3 { x: 0.0, y: 0.0, z: 0.0 }, rot: Quat::default(), ..Default::default() };
+            StaticMesh::new(
+                &camera_buffer,
+                &surface_conf, &device,
+                transform_platform,
+                ne_render::mesh::Shapes::create_box(20.0, 0.1, 20.0),
+                &material
+            )
+        });
+        let material = commands.spawn(NamedMaterial{name: (*name).into(), material }).id();
+        if let Some(mesh) = platform {
+            commands.spawn((mesh, Name::new("platform"), MeshMaterial(material)));
+        }
+    }
 }
 //=======================================
 //              ^^ ALPHA ^^
@@ -193,11 +244,11 @@ fn camera_on_redraw(
     mut camera_controller: ResMut<CameraController>,
     mut camera: ResMut<Camera>,
     projection: Res<Projection>,
-    dt: Res<DeltaTime>,
+    time: Res<Time>,
 ) { 
     for event in redraw_event.iter().rev() 
     {
-        camera_controller.update_camera(&mut camera, dt.time);
+        camera_controller.update_camera(&mut camera, time.raw_delta_seconds());
         camera_uniform.update_view_proj(&camera, &projection);
     }
 }
@@ -206,13 +257,6 @@ fn resize_sys(mut window_resized_events: EventReader<OnWindowResized>) {
         info!("window is resized w: {}, h:{}", event.width, event.height);
     }
 }
-fn on_exit(mut program_end_event: EventReader<ExitSequence>) {
-    for _ in program_end_event.iter().rev() {
-        //TODO this is suppoed to pop up a small window: with yes/no/cancel buttons. noy sure how cancel would work here tbh...
-        ne::log!("Would you like to save?");
-        ne::log!("exiting program");
-    }
-}
 fn mouse_motion(
     mut mouse_motion: EventReader<OnMouseMotion>,
     mouse_values: Res<MouseValues>,
@@ -243,7 +287,6 @@ fn on_scroll(
 }
 fn on_keyboard_pressed(
     mut keyboard_input: EventReader<OnKeyboardInput>,
-    mut exit_event: EventWriter<ExitApp>,
     mut camera_controller: ResMut<CameraController>,
     //somehow get mutable exit boolean here. No figure out a way to easily obtain data here.
 ) {
@@ -284,10 +327,6 @@ fn on_keyboard_pressed(
             VirtualKeyCode::Z => {
                 println!("Z is pressed");
             }
-            VirtualKeyCode::Escape => {
-                println!("ESCAPE");
-                exit_event.send(ExitApp{});
-            }
             VirtualKeyCode::Z => {
                 println!("Z is pressed");
             }
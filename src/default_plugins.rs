@@ -0,0 +1,33 @@
+use ne::L::LogPlugin;
+use ne_app::{
+    plugin_group::{PluginGroup, PluginGroupBuilder},
+    time::TimePlugin,
+};
+use ne_assets::AssetPlugin;
+use ne_render::RenderPlugin;
+use ne_scene::ScenePlugin;
+use ne_window::{input::InputPlugin, WindowEventPlugin};
+
+/// The plugins a windowed nightmare_engine app needs, in dependency order:
+/// [`LogPlugin`], [`WindowEventPlugin`], [`TimePlugin`], [`InputPlugin`], [`AssetPlugin`],
+/// [`ScenePlugin`] and [`RenderPlugin`].
+///
+/// Insert [`WindowSettings`](ne_render::WindowSettings) and
+/// [`AssetSettings`](ne_assets::AssetSettings) before adding the group.
+/// Single plugins can be swapped out or skipped:
+/// ```ignore
+/// App::new().add_plugins(DefaultPlugins.build().disable::<LogPlugin>());
+/// ```
+pub struct DefaultPlugins;
+impl PluginGroup for DefaultPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(LogPlugin::default())
+            .add(WindowEventPlugin)
+            .add(TimePlugin)
+            .add(InputPlugin)
+            .add(AssetPlugin)
+            .add(ScenePlugin)
+            .add(RenderPlugin)
+    }
+}
@@ -1 +1,8 @@
 pub mod prelude;
+mod default_plugins;
+mod project;
+pub use default_plugins::DefaultPlugins;
+pub use project::project_app;
+pub use ne_project;
+#[cfg(feature = "dynamic")]
+pub use ne_dylib;
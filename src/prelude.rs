@@ -2,3 +2,4 @@
 
 // pub use ne::prelude::*;
 pub use ne::*;
+pub use crate::DefaultPlugins;
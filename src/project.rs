@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use ne_app::App;
+use ne_project::{ActionName, Project, ProjectPlugin};
+use ne_render::WindowSettings;
+use ne_window::action::ActionPlugin;
+
+use crate::DefaultPlugins;
+
+/// An app running the game of the `.neproject` at `path`: the [`DefaultPlugins`] loading the
+/// project's assets in a window named after it, the manifest's bindings in
+/// `Input<`[`ActionName`]`>`, and the [`ProjectPlugin`] spawning its startup scene. Add the
+/// gameplay plugins and run it, a game with its own action type binds it too:
+/// ```ignore
+/// let mut app = project_app("walker.neproject")?;
+/// let input_map = app.world.resource::<Project>().input_map(PlayerAction::from_name);
+/// app.insert_resource(input_map).add_plugin(GameplayPlugin).run();
+/// ```
+pub fn project_app(path: impl Into<PathBuf>) -> anyhow::Result<App> {
+    let project = Project::open(path)?;
+    let mut app = App::new();
+    app.insert_resource(WindowSettings {
+        title: project.manifest.name.clone(),
+        ..WindowSettings::default()
+    })
+    .insert_resource(project.asset_settings())
+    .insert_resource(project.named_input_map())
+    .insert_resource(project)
+    .add_plugins(DefaultPlugins)
+    .add_plugin(ActionPlugin::<ActionName>::default())
+    .add_plugin(ProjectPlugin);
+    Ok(app)
+}
@@ -0,0 +1,111 @@
+//! Builds and inspects `.nepak` archives.
+//!
+//! ```text
+//! ne_pack build engine_assets engine_assets.nepak
+//! ne_pack list engine_assets.nepak
+//! ne_pack verify engine_assets.nepak
+//! ne_pack extract engine_assets.nepak out_dir
+//! ```
+use std::{fs, path::Path, process::ExitCode};
+
+use ne_files::pak::{self, Compression, PakArchive};
+
+const USAGE: &str = "usage:
+    ne_pack build <dir> <archive> [--store]   pack every file below <dir>, --store skips compression
+    ne_pack list <archive>                    print the files in <archive>
+    ne_pack verify <archive>                  check every file against its hash
+    ne_pack extract <archive> <dir>           unpack <archive> into <dir>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["build", dir, archive] => build(dir, archive, true),
+        ["build", dir, archive, "--store"] => build(dir, archive, false),
+        ["list", archive] => list(archive),
+        ["verify", archive] => verify(archive),
+        ["extract", archive, dir] => extract(archive, dir),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn build(dir: &str, archive: &str, compress: bool) -> Result<(), String> {
+    if !Path::new(dir).is_dir() {
+        return Err(format!("{} is not a directory", dir));
+    }
+    let stats = pak::pack_dir(Path::new(dir), Path::new(archive), compress)
+        .map_err(|error| format!("can't pack {}: {}", dir, error))?;
+    println!(
+        "packed {} files ({} compressed) into {}: {} -> {} bytes",
+        stats.files, stats.compressed_files, archive, stats.size, stats.stored_size
+    );
+    Ok(())
+}
+
+fn list(archive: &str) -> Result<(), String> {
+    let archive = open(archive)?;
+    for entry in archive.entries() {
+        let compression = match entry.compression {
+            Compression::None => "stored",
+            Compression::Lz4 => "lz4",
+        };
+        println!(
+            "{:>12} {:>12} {:<6} {}",
+            entry.size, entry.stored_size, compression, entry.path
+        );
+    }
+    println!("{} files", archive.entries().len());
+    Ok(())
+}
+
+fn verify(archive: &str) -> Result<(), String> {
+    let archive = open(archive)?;
+    let mut failed = 0;
+    for entry in archive.entries() {
+        if let Err(error) = archive.read_entry(entry) {
+            eprintln!("{}: {}", entry.path, error);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} files are corrupted",
+            failed,
+            archive.entries().len()
+        ));
+    }
+    println!("{} files ok", archive.entries().len());
+    Ok(())
+}
+
+fn extract(archive: &str, dir: &str) -> Result<(), String> {
+    let archive = open(archive)?;
+    for entry in archive.entries() {
+        let data = archive
+            .read_entry(entry)
+            .map_err(|error| format!("can't read {}: {}", entry.path, error))?;
+        let path = Path::new(dir).join(&entry.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("can't create {}: {}", parent.display(), error))?;
+        }
+        fs::write(&path, data)
+            .map_err(|error| format!("can't write {}: {}", path.display(), error))?;
+    }
+    println!("extracted {} files into {}", archive.entries().len(), dir);
+    Ok(())
+}
+
+fn open(archive: &str) -> Result<PakArchive, String> {
+    PakArchive::open(archive).map_err(|error| format!("can't open {}: {}", archive, error))
+}
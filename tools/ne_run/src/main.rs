@@ -0,0 +1,26 @@
+//! Runs a game from its manifest: its assets, window title, input bindings and startup scene.
+//!
+//! ```text
+//! ne_run walker/walker.neproject
+//! ```
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: ne_run <project>.neproject";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [path] = args.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    match nightmare_engine::project_app(path) {
+        Ok(mut app) => {
+            app.run();
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {:#}", error);
+            ExitCode::FAILURE
+        }
+    }
+}